@@ -1,8 +1,51 @@
-use rocket::{Request, State, catchers, get, post, routes};
-use rocket_dyn_templates::{Template, context, tera::Tera};
+pub mod admin;
+pub mod audit;
+pub mod auth;
+pub mod check;
+pub mod collections;
+pub mod csrf;
+pub mod export;
+pub mod extensions;
+pub mod forms;
+pub mod i18n;
+pub mod import;
+pub mod live;
+pub mod redirects;
+pub mod request;
+pub mod route_table;
+pub mod search;
+pub mod session;
+pub mod url_for;
+
+use crate::seo::sitemap::{SitemapEntry, render_sitemap};
+use crate::seo::social_card::{CARDS_BASE, SocialCards};
+use crate::seo::{Alternate, Seo, SiteSeoDefaults, html_escape};
+use admin::{ADMIN_API_BASE, AdminConfig};
+use auth::{Access, AccessDecision, AuthConfig, Identity, SESSION_USER_KEY, safe_next};
+use collections::{Collection, CollectionPage, Pagination, item_value, page_uri, split_page_uri};
+use csrf::{CSRF_FIELD, CsrfToken};
+use extensions::TemplateExtensions;
+use forms::{FormErrors, FormSchema};
+use i18n::{LOCALE_COOKIE, Translation, localized_uri, localized_url, negotiate};
+use live::LiveRegistry;
+use redirects::{RedirectRule, RedirectStatus, RedirectTarget, UrlNormalization};
+use request::{RequestInfo, Submission, Visitor};
+use rocket::http::{ContentType, Header, RawStr, Status, uri::Origin};
+use rocket::response::Redirect;
+use rocket::{Request, Responder, State, catchers, delete, get, post, put, routes};
+use rocket_dyn_templates::tera::{self, Filter, Function, Tera};
+use rocket_dyn_templates::{Template, context};
+use search::{QUERY_PARAM, SearchIndex, SearchPage};
 // core.rs
-use serde::Serialize;
-use std::{collections::HashMap, sync::Arc};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use session::{Flash, PostActions, Session, SessionConfig};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+use url_for::UrlFor;
 
 /// Represents the HTTP methods supported by the application.
 ///
@@ -25,6 +68,9 @@ use std::{collections::HashMap, sync::Arc};
 ///   libraries like `serde`.
 /// - `Clone`: Allows duplication of `Method` values.
 /// - `Copy`: Permits the `Method` enum variants to be copied instead of moved.
+/// - `Deserialize`: Allows reading the method from capsule manifests (e.g. `method = "POST"`).
+/// - `Default`: Defaults to `GET`.
+/// - `ValueEnum`: Allows command-line filters such as `routes --method post`.
 ///
 /// # Examples
 /// ```
@@ -33,8 +79,9 @@ use std::{collections::HashMap, sync::Arc};
 /// let method = Method::GET;
 /// println!("{:?}", method); // Prints: GET
 /// ```
-#[derive(Debug, Serialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
 pub enum Method {
+    #[default]
     GET,
     POST,
     PUT,
@@ -53,12 +100,33 @@ pub enum Method {
 /// * `uri` - A `String` representing the target URI (Uniform Resource Identifier) where the HTTP request is directed.
 /// * `method` - A `Method` enumeration representing the HTTP method (e.g., GET, POST, PUT, DELETE) for the associated request.
 /// * `data` - A `serde_json::Value` field used to store the payload or body of the HTTP request as a JSON structure.
+/// * `csrf` - Whether `POST`, `PUT` and `DELETE` requests to this capsule must carry a valid CSRF token.
+///   Enabled by default; disable it with [`Capsule::without_csrf`] for endpoints such as webhooks.
+/// * `actions` - The [`PostActions`] (session values, flash message, redirect) applied when the capsule
+///   receives a `POST`, `PUT` or `DELETE` request.
+/// * `access` - The [`Access`] requirement visitors must satisfy: public (the default), HTTP Basic, or
+///   session login, optionally restricted to roles.
+/// * `form` - An optional [`FormSchema`] validating submissions before the `actions` run.
+/// * `aliases` - Other URIs of the capsule. They answer with a `301` redirect to `uri`, so old URLs keep working.
+/// * `redirect` - Turns the capsule into a redirect capsule: instead of rendering `template`, requests to `uri`
+///   are sent to the [`RedirectTarget`] with a `301`, `302`, `307` or `308` status.
+/// * `locale` - The language of the capsule (`"en"`, `"fr-CA"`), if the site is multilingual.
+/// * `translations` - [`Translation`]s of the capsule by locale. Each one is served under the locale
+///   prefix, e.g. `/fr/about` for the `fr` translation of `/about`.
+/// * `alternates` - The hreflang [`Alternate`]s of the capsule, exposed to templates as `alternates`.
+///   Filled in by [`CapsuleRegistry::add`] for translated capsules; set it by hand to link external translations.
+/// * `seo` - The [`Seo`] metadata of the page, exposed to templates as `seo`. Collections select items by its `content_type`.
+/// * `collection` - Turns the capsule into a [`Collection`] listing other capsules, paginated under `{uri}/page/N`.
+/// * `search` - Turns the capsule into a [`SearchPage`] answering `{uri}?q=…` from the site's [`SearchIndex`].
+/// * `source` - The manifest the capsule was loaded from, set by [`Capsule::from_manifest`]. Never serialized.
 ///
 /// # Traits
 ///
 /// The struct derives the following traits:
 /// * `Debug`: Enables formatting for debugging purposes.
 /// * `Serialize`: Allows the struct to be serialized into formats like JSON.
+/// * `Deserialize`: Allows loading capsules from manifest files (see [`Capsule::from_manifest`]).
+///   Only `name`, `uri` and `template` are mandatory there (`template` may be omitted for redirect capsules).
 /// * `Clone`: Enables creating deep copies of the struct.
 ///
 /// # Example
@@ -77,18 +145,91 @@ pub enum Method {
 ///     data: json!({
 ///         "key": "value"
 ///     }),
+///     csrf: true,
+///     actions: Default::default(),
+///     access: Default::default(),
+///     form: None,
+///     aliases: vec!["https://api.example.com/old-resource".to_string()],
+///     redirect: None,
+///     locale: None,
+///     translations: Default::default(),
+///     alternates: Vec::new(),
+///     seo: None,
+///     collection: None,
+///     search: None,
+///     source: None,
 /// };
 ///
 /// println!("{:?}", capsule);
 /// ```
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Capsule {
+    #[serde(default)]
     pub template: String,
     pub name: String,
+    #[serde(default)]
     pub description: String,
     pub uri: String,
+    #[serde(default)]
     pub method: Method,
+    #[serde(default = "empty_data")]
     pub data: serde_json::Value,
+    #[serde(default = "csrf_enabled")]
+    pub csrf: bool,
+    #[serde(default)]
+    pub actions: PostActions,
+    #[serde(default)]
+    pub access: Access,
+    #[serde(default)]
+    pub form: Option<FormSchema>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub redirect: Option<RedirectTarget>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub translations: BTreeMap<String, Translation>,
+    #[serde(default)]
+    pub alternates: Vec<Alternate>,
+    #[serde(default)]
+    pub seo: Option<Seo>,
+    #[serde(default)]
+    pub collection: Option<Collection>,
+    #[serde(default)]
+    pub search: Option<SearchPage>,
+    #[serde(skip)]
+    pub source: Option<std::path::PathBuf>,
+}
+
+fn empty_data() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+fn csrf_enabled() -> bool {
+    true
+}
+
+/// Removes the `null` fields of every object in `value`.
+fn strip_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// HTML-escapes every string in `value`, for values sent by visitors that templates render as is.
+fn escape_strings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(text) => *text = html_escape(text),
+        serde_json::Value::Object(map) => map.values_mut().for_each(escape_strings),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(escape_strings),
+        _ => {}
+    }
 }
 
 impl Capsule {
@@ -106,39 +247,619 @@ impl Capsule {
             template: template.into(),
             method,
             data: serde_json::json!({}),
+            csrf: true,
+            actions: PostActions::default(),
+            access: Access::Public,
+            form: None,
+            aliases: Vec::new(),
+            redirect: None,
+            locale: None,
+            translations: BTreeMap::new(),
+            alternates: Vec::new(),
+            seo: None,
+            collection: None,
+            search: None,
+            source: None,
         }
     }
+
+    /// Creates a redirect capsule sending requests for `uri` to `to` with `status`.
+    pub fn redirect<N: Into<String>, U: Into<String>, T: Into<String>>(
+        name: N,
+        uri: U,
+        to: T,
+        status: RedirectStatus,
+    ) -> Self {
+        let mut capsule = Self::new(name, "", uri, "", Method::GET);
+        capsule.redirect = Some(RedirectTarget {
+            to: to.into(),
+            status,
+        });
+        capsule
+    }
+
+    /// Loads a capsule from a manifest file.
+    ///
+    /// The format is picked from the extension: `.toml` or `.json`. A contact form can be
+    /// declared without writing any Rust:
+    ///
+    /// ```toml
+    /// name = "contact"
+    /// uri = "/contact"
+    /// template = "contact"
+    /// method = "POST"
+    ///
+    /// [form]
+    /// fields = [{ name = "email", type = "email", required = true }]
+    ///
+    /// [actions]
+    /// redirect = "/contact"
+    /// flash = { kind = "success", message = "Thanks, we will get back to you." }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails when the file cannot be read, has another extension, or does not describe a capsule.
+    pub fn from_manifest(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read capsule manifest {}", path.display()))?;
+        let mut capsule: Capsule = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&source)?,
+            Some("json") => serde_json::from_str(&source)?,
+            _ => anyhow::bail!("unsupported capsule manifest {}", path.display()),
+        };
+        capsule.source = Some(path.to_path_buf());
+        Ok(capsule)
+    }
+
+    /// Writes the capsule as a manifest read back by [`Capsule::from_manifest`]: JSON when `path`
+    /// ends with `.json`, TOML otherwise.
+    ///
+    /// `null` values, top-level fields equal to their defaults (`[]`, `{}`) and `seo` fields equal
+    /// to theirs (`[]`, `false`) are left out, to keep manifests short and because TOML has no
+    /// `null`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the capsule cannot be written in that format (e.g. TOML arrays mixing types).
+    pub fn to_manifest(&self, path: impl AsRef<Path>) -> anyhow::Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        strip_nulls(&mut value);
+        if let Some(map) = value.as_object_mut() {
+            map.retain(|_, value| match value {
+                serde_json::Value::Array(items) => !items.is_empty(),
+                serde_json::Value::Object(fields) => !fields.is_empty(),
+                _ => true,
+            });
+            if let Some(seo) = map.get_mut("seo").and_then(|seo| seo.as_object_mut()) {
+                seo.retain(|_, value| match value {
+                    serde_json::Value::Array(items) => !items.is_empty(),
+                    serde_json::Value::Bool(set) => *set,
+                    _ => true,
+                });
+            }
+        }
+        Ok(match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::to_string_pretty(&value)?,
+            _ => toml::to_string_pretty(&value).context("the capsule cannot be written as TOML")?,
+        })
+    }
+
+    /// Writes the manifest of the capsule (see [`Capsule::to_manifest`]) to `path`, creating its
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the manifest cannot be written in that format or to that path.
+    pub fn write_manifest(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let manifest = self.to_manifest(path)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, manifest).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    /// Disables CSRF verification for this capsule.
+    ///
+    /// Use it for endpoints that receive cross-site requests on purpose, such as
+    /// webhooks called by third-party services.
+    pub fn without_csrf(&mut self) -> &mut Self {
+        self.csrf = false;
+        self
+    }
+
+    /// Stores `value` under `key` in the visitor session when the capsule handles a submission.
+    ///
+    /// String values may reference submitted form fields with `{field}` placeholders.
+    pub fn with_session_value<K: Into<String>, V: Into<serde_json::Value>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> &mut Self {
+        self.actions.session.insert(key.into(), value.into());
+        self
+    }
+
+    /// Shows a flash message on the next rendered page after a submission.
+    pub fn with_flash<K: Into<String>, M: Into<String>>(
+        &mut self,
+        kind: K,
+        message: M,
+    ) -> &mut Self {
+        self.actions.flash = Some(Flash::new(kind, message));
+        self
+    }
+
+    /// Redirects the visitor to `uri` after a submission instead of rendering the capsule.
+    pub fn with_redirect<S: Into<String>>(&mut self, uri: S) -> &mut Self {
+        self.actions.redirect = Some(uri.into());
+        self
+    }
+
+    /// Restricts who can reach the capsule.
+    pub fn with_access(&mut self, access: Access) -> &mut Self {
+        self.access = access;
+        self
+    }
+
+    /// Validates submissions against `form` before running the capsule's actions.
+    pub fn with_form(&mut self, form: FormSchema) -> &mut Self {
+        self.form = Some(form);
+        self
+    }
+
+    /// Makes the capsule reachable at `uri` too, through a `301` redirect to its canonical URI.
+    pub fn with_alias<S: Into<String>>(&mut self, uri: S) -> &mut Self {
+        self.aliases.push(uri.into());
+        self
+    }
+
+    /// Sets the SEO metadata of the capsule.
+    pub fn with_seo(&mut self, seo: Seo) -> &mut Self {
+        self.seo = Some(seo);
+        self
+    }
+
+    /// Turns the capsule into a listing of the capsules selected by `collection`.
+    pub fn with_collection(&mut self, collection: Collection) -> &mut Self {
+        self.collection = Some(collection);
+        self
+    }
+
+    /// Turns the capsule into a search page.
+    pub fn with_search(&mut self, search: SearchPage) -> &mut Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Sets the language of the capsule.
+    pub fn with_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Serves `translation` of the capsule under the `locale` prefix (`/fr/about` for `/about`).
+    pub fn with_translation<S: Into<String>>(
+        &mut self,
+        locale: S,
+        translation: Translation,
+    ) -> &mut Self {
+        self.translations.insert(locale.into(), translation);
+        self
+    }
+
+    /// Builds the capsule serving the `locale` translation.
+    ///
+    /// The SEO metadata is the translation's own, or the capsule's with `lang` set to `locale` and
+    /// the canonical and shared URLs moved under the locale prefix.
+    fn translated(&self, locale: &str, translation: &Translation) -> Capsule {
+        let mut variant = self.clone();
+        variant.uri = localized_uri(locale, &self.uri);
+        variant.locale = Some(locale.to_string());
+        variant.aliases.clear();
+        variant.translations.clear();
+        if let Some(template) = &translation.template {
+            variant.template = template.clone();
+        }
+        if let Some(description) = &translation.description {
+            variant.description = description.clone();
+        }
+        match (variant.data.as_object_mut(), &translation.data) {
+            (Some(data), Some(serde_json::Value::Object(overrides))) => {
+                data.extend(overrides.clone());
+            }
+            (_, Some(overrides)) => variant.data = overrides.clone(),
+            _ => {}
+        }
+        match &translation.seo {
+            Some(seo) => variant.seo = Some(seo.clone()),
+            None => {
+                if let Some(seo) = &mut variant.seo {
+                    seo.lang = None;
+                    seo.og_locale = None;
+                    seo.og_locale_alternates.clear();
+                    for url in [&mut seo.canonical_url, &mut seo.og_url]
+                        .into_iter()
+                        .flatten()
+                    {
+                        *url = localized_url(locale, url);
+                    }
+                }
+            }
+        }
+        if let Some(seo) = &mut variant.seo {
+            seo.lang.get_or_insert_with(|| locale.to_string());
+            if seo.alternates.is_empty() {
+                seo.alternates = variant.alternates.clone();
+            }
+        }
+        variant
+    }
 }
 
 #[derive(Default, Clone)]
 pub struct CapsuleRegistry {
-    map: HashMap<String, Capsule>, // key = uri
+    map: BTreeMap<String, Capsule>,           // key = uri
+    aliases: HashMap<String, String>,         // alias -> canonical uri
+    redirects: HashMap<String, RedirectRule>, // key = from
+    patterns: Vec<RedirectRule>,              // rules with `*` or `:name`, in order
+    names: HashMap<String, String>,           // name -> uri
 }
 
 impl CapsuleRegistry {
-    pub fn add(&mut self, capsule: Capsule) {
+    /// Adds a capsule, along with one capsule per translation.
+    ///
+    /// The capsule and its translations all get the same hreflang `alternates`: one per locale,
+    /// plus `x-default` pointing to the capsule itself. They are copied into the capsule's
+    /// [`Seo::alternates`] when it has none, so that the head links the translations.
+    ///
+    /// A capsule already at the same `uri` is replaced, and [removed](CapsuleRegistry::remove) first
+    /// along with its translations, aliases and name.
+    ///
+    /// The capsule is indexed by `name` for [`CapsuleRegistry::url_for`]; a capsule with the same
+    /// name replaces the previous one in that index. Use [`CapsuleRegistry::try_add`] to reject duplicates.
+    pub fn add(&mut self, mut capsule: Capsule) {
+        self.remove(&capsule.uri);
+        self.names.insert(capsule.name.clone(), capsule.uri.clone());
+        for alias in &capsule.aliases {
+            self.aliases.insert(alias.clone(), capsule.uri.clone());
+        }
+        if !capsule.translations.is_empty() {
+            if let Some(locale) = &capsule.locale {
+                capsule
+                    .alternates
+                    .push(Alternate::new(locale.clone(), capsule.uri.clone()));
+            }
+            for locale in capsule.translations.keys() {
+                capsule.alternates.push(Alternate::new(
+                    locale.clone(),
+                    localized_uri(locale, &capsule.uri),
+                ));
+            }
+            capsule
+                .alternates
+                .push(Alternate::new("x-default", capsule.uri.clone()));
+            if let Some(seo) = &mut capsule.seo {
+                if seo.alternates.is_empty() {
+                    seo.alternates = capsule.alternates.clone();
+                }
+                if seo.lang.is_none() {
+                    seo.lang = capsule.locale.clone();
+                }
+            }
+            for (locale, translation) in &capsule.translations {
+                let variant = capsule.translated(locale, translation);
+                self.map.insert(variant.uri.clone(), variant);
+            }
+        }
         self.map.insert(capsule.uri.clone(), capsule);
     }
+    /// Adds a capsule like [`CapsuleRegistry::add`], unless another capsule already has its `name`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the name is taken by a capsule at another URI, naming both.
+    pub fn try_add(&mut self, capsule: Capsule) -> anyhow::Result<()> {
+        if let Some(existing) = self
+            .names
+            .get(&capsule.name)
+            .filter(|uri| **uri != capsule.uri)
+            .and_then(|uri| self.map.get(uri))
+        {
+            let from = |c: &Capsule| match &c.source {
+                Some(path) => format!("{} ({})", c.uri, path.display()),
+                None => c.uri.clone(),
+            };
+            anyhow::bail!(
+                "duplicate capsule name `{}`: used by {} and {}",
+                capsule.name,
+                from(existing),
+                from(&capsule)
+            );
+        }
+        self.add(capsule);
+        Ok(())
+    }
+    /// Removes the capsule at `uri`, along with its translations, aliases and name.
+    ///
+    /// # Returns
+    ///
+    /// The capsule removed, or `None` when there was none at `uri`.
+    pub fn remove(&mut self, uri: &str) -> Option<Capsule> {
+        let capsule = self.map.remove(uri)?;
+        if self.names.get(&capsule.name).is_some_and(|u| u == uri) {
+            self.names.remove(&capsule.name);
+        }
+        self.aliases.retain(|_, target| target != uri);
+        for locale in capsule.translations.keys() {
+            self.map.remove(&localized_uri(locale, uri));
+        }
+        Some(capsule)
+    }
+    /// Adds a standalone redirect rule. Capsules and their aliases take precedence over rules.
+    ///
+    /// Pattern rules (see [`RedirectRule::is_pattern`]) are tried after the exact ones, in the
+    /// order they were added: the first one matching wins, as with static hosts.
+    pub fn add_redirect(&mut self, rule: RedirectRule) {
+        if !rule.is_pattern() {
+            self.redirects.insert(rule.from.clone(), rule);
+        } else if let Some(existing) = self.patterns.iter_mut().find(|p| p.from == rule.from) {
+            *existing = rule;
+        } else {
+            self.patterns.push(rule);
+        }
+    }
+    /// Adds every redirect rule of a file. See [`redirects::load_redirects`] for the formats.
+    ///
+    /// Returns the number of rules added.
+    pub fn load_redirects(&mut self, path: impl AsRef<Path>) -> anyhow::Result<usize> {
+        let rules = redirects::load_redirects(path)?;
+        let count = rules.len();
+        for rule in rules {
+            self.add_redirect(rule);
+        }
+        Ok(count)
+    }
+    /// Adds every capsule manifest (`*.toml` or `*.json`, one capsule per file) found under `dir`.
+    ///
+    /// Returns the number of capsules added. See [`Capsule::from_manifest`] for the format.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first invalid manifest, or when two capsules share a name (see [`CapsuleRegistry::try_add`]).
+    pub fn load_manifests(&mut self, dir: impl AsRef<Path>) -> anyhow::Result<usize> {
+        let mut paths: Vec<_> =
+            globwalk::GlobWalkerBuilder::from_patterns(dir.as_ref(), &["**/*.{toml,json}"])
+                .build()?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path().to_path_buf())
+                .collect();
+        paths.sort();
+        for path in &paths {
+            let capsule = Capsule::from_manifest(path)
+                .with_context(|| format!("invalid capsule manifest {}", path.display()))?;
+            self.try_add(capsule)?;
+        }
+        Ok(paths.len())
+    }
     pub fn get(&self, uri: &str) -> Option<&Capsule> {
         self.map.get(uri)
     }
+    /// Returns the capsule named `name`. Translations share the name of the capsule they translate
+    /// and are not returned.
+    pub fn get_by_name(&self, name: &str) -> Option<&Capsule> {
+        self.names.get(name).and_then(|uri| self.map.get(uri))
+    }
+    /// Builds the URL of the capsule named `name`.
+    ///
+    /// `params` fill the `<param>` and `<param..>` segments of the capsule URI; the others become the
+    /// query string (see [`url_for::fill_route`]). A `locale` parameter selects a translation of the
+    /// capsule, when it has one.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // A capsule named "post" at "/blog/<slug>"
+    /// let params = BTreeMap::from([("slug".to_string(), "hello world".to_string())]);
+    /// assert_eq!(registry.url_for("post", &params)?, "/blog/hello%20world");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails when no capsule is named `name` or a route parameter is missing.
+    pub fn url_for(&self, name: &str, params: &BTreeMap<String, String>) -> anyhow::Result<String> {
+        let capsule = self
+            .get_by_name(name)
+            .ok_or_else(|| anyhow::anyhow!("no capsule is named `{name}`"))?;
+        let mut params = params.clone();
+        let uri = match params.remove("locale") {
+            Some(locale) if capsule.translations.contains_key(&locale) => {
+                localized_uri(&locale, &capsule.uri)
+            }
+            Some(locale) if capsule.locale.as_deref() == Some(locale.as_str()) => {
+                capsule.uri.clone()
+            }
+            Some(locale) => anyhow::bail!("capsule `{name}` has no `{locale}` translation"),
+            None => capsule.uri.clone(),
+        };
+        url_for::fill_route(&uri, &params).map_err(anyhow::Error::msg)
+    }
+    /// Returns the redirect answering requests for `uri`, if any.
+    ///
+    /// In order of precedence: a redirect capsule at `uri`, an alias of a capsule (`301` to its
+    /// canonical URI), then a standalone rule, exact rules before pattern ones. Render capsules at
+    /// `uri` are never redirected.
+    pub fn redirect_for(&self, uri: &str) -> Option<RedirectRule> {
+        if let Some(capsule) = self.map.get(uri) {
+            return capsule
+                .redirect
+                .as_ref()
+                .map(|target| RedirectRule::new(uri, target.to.clone(), target.status));
+        }
+        if let Some(canonical) = self.aliases.get(uri) {
+            return Some(RedirectRule::new(
+                uri,
+                canonical.clone(),
+                RedirectStatus::Moved,
+            ));
+        }
+        if let Some(rule) = self.redirects.get(uri) {
+            return Some(rule.clone());
+        }
+        self.patterns.iter().find_map(|rule| rule.apply(uri))
+    }
+    /// Lists every redirect known to the registry (redirect capsules, aliases and rules), sorted by source,
+    /// followed by the pattern rules in the order they were added.
+    ///
+    /// Used by the static export, see [`redirects::export_redirects`].
+    pub fn redirects(&self) -> Vec<RedirectRule> {
+        let mut sources: Vec<&String> = self
+            .map
+            .iter()
+            .filter(|(_, capsule)| capsule.redirect.is_some())
+            .map(|(uri, _)| uri)
+            .chain(self.aliases.keys())
+            .chain(self.redirects.keys())
+            .collect();
+        sources.sort();
+        sources.dedup();
+        let mut rules: Vec<RedirectRule> = sources
+            .into_iter()
+            .filter_map(|uri| self.redirect_for(uri))
+            .collect();
+        rules.extend(self.patterns.iter().cloned());
+        rules
+    }
+    /// Iterates over the capsules, sorted by URI.
     pub fn all(&self) -> impl Iterator<Item = (&String, &Capsule)> {
         self.map.iter()
     }
+    /// Returns page `page` (from 1) of the collection capsule at `uri`.
+    ///
+    /// # Returns
+    ///
+    /// `None` when there is no collection capsule at `uri`, or when it has fewer pages.
+    pub fn collection_page(&self, uri: &str, page: usize) -> Option<CollectionPage> {
+        let capsule = self.map.get(uri)?;
+        let collection = capsule.collection.as_ref()?;
+        let mut items: Vec<&Capsule> = self
+            .map
+            .values()
+            .filter(|item| collection.selects(capsule, item))
+            .collect();
+        collection.sort(&mut items);
+        let total = items.len();
+        let pages = collection.pages(total);
+        if page == 0 || page > pages {
+            return None;
+        }
+        let per_page = collection.per_page.max(1);
+        Some(CollectionPage {
+            items: items
+                .into_iter()
+                .skip((page - 1) * per_page)
+                .take(per_page)
+                .map(item_value)
+                .collect(),
+            pagination: Pagination {
+                page,
+                pages,
+                per_page,
+                total,
+                prev: (page > 1).then(|| page_uri(uri, page - 1)),
+                next: (page < pages).then(|| page_uri(uri, page + 1)),
+            },
+        })
+    }
+    /// Picks the translation of `uri` best matching an `Accept-Language` header value.
+    ///
+    /// # Returns
+    ///
+    /// The URI of the chosen translation, or `None` when the visitor prefers the capsule at `uri`
+    /// itself, or none of its languages.
+    pub fn negotiate(&self, uri: &str, accept_language: &str) -> Option<&str> {
+        let capsule = self.map.get(uri)?;
+        let locales: Vec<&str> = capsule
+            .alternates
+            .iter()
+            .filter(|alt| alt.hreflang != "x-default")
+            .map(|alt| alt.hreflang.as_str())
+            .collect();
+        let locale = negotiate(accept_language, &locales)?;
+        capsule
+            .alternates
+            .iter()
+            .find(|alt| alt.hreflang == locale)
+            .map(|alt| alt.href.as_str())
+            .filter(|href| *href != uri)
+    }
+    /// Lists the pages worth indexing: public `GET` capsules that are not redirects, sorted by URI.
+    ///
+    /// Translations are listed as pages of their own, each carrying the hreflang alternates.
+    pub fn sitemap_entries(&self) -> Vec<SitemapEntry> {
+        let mut entries: Vec<SitemapEntry> = self
+            .map
+            .values()
+            .filter(|c| c.method == Method::GET && c.redirect.is_none() && c.access.is_public())
+            .map(|c| SitemapEntry {
+                loc: c.uri.clone(),
+                lastmod: None,
+                alternates: c.alternates.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.loc.cmp(&b.loc));
+        entries
+    }
+    /// Renders the `sitemap.xml` of the registry, see [`CapsuleRegistry::sitemap_entries`].
+    pub fn sitemap(&self, base_url: &str) -> String {
+        render_sitemap(base_url, &self.sitemap_entries())
+    }
 }
 
 /// Abstract template engine: compiles templates and can produce a renderable context.
 pub trait TemplateEngine: Send + Sync {
     /// Load templates from disk or memory; adapter decides how.
     fn load_all(&self) -> anyhow::Result<()>;
+    /// Renders `template` with `ctx` outside of a request, e.g. for a static export.
+    ///
+    /// The default implementation fails: engines that cannot render offline only serve pages.
+    fn render(&self, template: &str, _ctx: &serde_json::Value) -> anyhow::Result<String> {
+        anyhow::bail!("this template engine cannot render `{template}` outside of the server")
+    }
+    /// The names of the loaded templates, as capsules refer to them.
+    ///
+    /// The default implementation returns an empty list; engines should override it so that
+    /// [`check::check_site`] can tell missing templates apart.
+    fn templates(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// The file a template was loaded from, if it comes from disk.
+    fn template_path(&self, _name: &str) -> Option<std::path::PathBuf> {
+        None
+    }
+    /// The filters, functions and globals to install in the server's Tera instance, if any.
+    ///
+    /// Globals are also merged into every capsule context.
+    fn extensions(&self) -> Option<&TemplateExtensions> {
+        None
+    }
     /// Build the context map for a capsule (you can enrich this globally).
-    fn context_for(&self, capsule: &Capsule) -> serde_json::Value {
+    ///
+    /// `request` describes the request being answered (query, headers, cookies, client);
+    /// it is exposed to templates as `request`.
+    fn context_for(&self, capsule: &Capsule, request: &RequestInfo) -> serde_json::Value {
         serde_json::json!({
             "name": capsule.name,
             "description": capsule.description,
             "uri": capsule.uri,
             "method": format!("{:?}", capsule.method),
-            "data": capsule.data
+            "data": capsule.data,
+            "locale": capsule.locale,
+            "alternates": capsule.alternates,
+            "seo": capsule.seo,
+            "request": request,
         })
     }
 }
@@ -156,7 +877,8 @@ pub trait HttpServer: Send + Sync {
     /// # Parameters
     ///
     /// * `&'a self` - A reference to the instance of the service that will handle the requests.
-    /// * `registry` - An `Arc`-wrapped instance of `CapsuleRegistry` containing the registry information or handlers for the service.
+    /// * `registry` - The [`LiveRegistry`] of the capsules served. Keep a clone of the `Arc` to add, replace or
+    ///   remove capsules while the server runs.
     /// * `engine` - An `Arc`-wrapped implementation of the `TemplateEngine` trait, used for template rendering within the service.
     ///
     /// # Returns
@@ -171,7 +893,7 @@ pub trait HttpServer: Send + Sync {
     /// use std::sync::Arc;
     /// use your_crate::{CapsuleRegistry, TemplateEngine};
     ///
-    /// let registry = Arc::new(CapsuleRegistry::new());
+    /// let registry = Arc::new(LiveRegistry::new(CapsuleRegistry::default()));
     /// let engine = Arc::new(MyTemplateEngine::new());
     /// let server = MyService::new();
     ///
@@ -186,7 +908,7 @@ pub trait HttpServer: Send + Sync {
     ///
     fn serve<'a>(
         &'a self,
-        registry: Arc<CapsuleRegistry>,
+        registry: Arc<LiveRegistry>,
         engine: Arc<dyn TemplateEngine>,
     ) -> std::pin::Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
 }
@@ -201,6 +923,10 @@ pub trait HttpServer: Send + Sync {
 /// * `root` - A `String` representing the directory where templates are stored. This is used to define the
 ///   base path for accessing template files.
 ///
+/// * `extensions` - The [`TemplateExtensions`] (filters, functions, globals) installed in this instance and in
+///   the server's. Starts with the built-ins; add your own with [`TeraEngine::register_filter`],
+///   [`TeraEngine::register_function`] and [`TeraEngine::register_global`].
+///
 /// # Examples
 ///
 /// ```
@@ -218,9 +944,9 @@ pub struct TeraEngine {
     tera: parking_lot::RwLock<Tera>,
     /// Where your templates live, e.g. "templates"
     root: String,
+    extensions: TemplateExtensions,
 }
 
-
 impl TeraEngine {
     /// Creates a new instance of the struct containing a Tera template engine
     /// and a root directory.
@@ -234,7 +960,7 @@ impl TeraEngine {
     ///
     /// Returns a new instance of the struct initialized with a default `Tera`
     /// instance wrapped in a `parking_lot::RwLock` for thread-safe access,
-    /// and the specified root directory.
+    /// the specified root directory, and the built-in [`TemplateExtensions`].
     ///
     /// # Examples
     ///
@@ -245,8 +971,50 @@ impl TeraEngine {
         Self {
             tera: parking_lot::RwLock::new(Tera::default()),
             root: root.into(),
+            extensions: TemplateExtensions::with_builtins(),
         }
     }
+
+    /// Registers a filter, used in templates as `{{ value | name(arg=…) }}`.
+    ///
+    /// Extensions are installed when templates are loaded: register them before serving.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut engine = TeraEngine::new("templates");
+    /// engine.register_filter("shout", |value: &Value, _: &HashMap<String, Value>| {
+    ///     Ok(value.as_str().unwrap_or_default().to_uppercase().into())
+    /// });
+    /// ```
+    pub fn register_filter<F: Filter + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        filter: F,
+    ) -> &mut Self {
+        self.extensions.register_filter(name, filter);
+        self
+    }
+
+    /// Registers a function, used in templates as `{{ name(arg=…) }}`.
+    pub fn register_function<F: Function + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        function: F,
+    ) -> &mut Self {
+        self.extensions.register_function(name, function);
+        self
+    }
+
+    /// Registers a variable available in every capsule template, e.g. `base_url` for the `url` function.
+    pub fn register_global(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> &mut Self {
+        self.extensions.register_global(name, value);
+        self
+    }
 }
 
 impl TemplateEngine for TeraEngine {
@@ -261,8 +1029,10 @@ impl TemplateEngine for TeraEngine {
     /// 1. Creates a glob pattern to match all files with the `.html.tera` extension in the root directory and its subdirectories.
     /// 2. Initializes a default `Tera` template engine instance.
     /// 3. Uses the `globwalk` crate to walk the directory tree and find all matching `.html.tera` files, returning an iterator over their paths.
-    /// 4. Adds the matching files to the `Tera` instance.
-    /// 5. Updates the `tera` instance stored in the shared `tera` writeable reference with the newly loaded templates.
+    /// 4. Adds the matching files to the `Tera` instance, named like Rocket names them: relative to the root,
+    ///    without the `.html.tera` extension (`blog/post.html.tera` is `blog/post`).
+    /// 5. Installs the [`TemplateExtensions`] and disables auto-escaping, like the server does.
+    /// 6. Updates the `tera` instance stored in the shared `tera` writeable reference with the newly loaded templates.
     ///
     /// # Returns
     /// * `Ok(())` - On successful loading of the templates.
@@ -293,15 +1063,51 @@ impl TemplateEngine for TeraEngine {
     fn load_all(&self) -> anyhow::Result<()> {
         // Load all *.html.tera in the root directory
         let glob = format!("{}/**/*.html.tera", self.root);
+        let root = Path::new(&self.root);
         let mut tera = Tera::default();
-        tera.add_template_files(
-            globwalk::glob(&glob)?
-                .filter_map(Result::ok)
-                .map(|e| (e.path().to_path_buf(), None::<&str>)),
-        )?;
+        tera.autoescape_on(vec![]);
+        self.extensions.apply(&mut tera);
+        tera.add_template_files(globwalk::glob(&glob)?.filter_map(Result::ok).map(|e| {
+            let path = e.path().to_path_buf();
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/")
+                .trim_end_matches(".html.tera")
+                .to_string();
+            (path, Some(name))
+        }))?;
         *self.tera.write() = tera;
         Ok(())
     }
+
+    fn render(&self, template: &str, ctx: &serde_json::Value) -> anyhow::Result<String> {
+        let mut ctx = ctx.clone();
+        self.extensions.merge_globals(&mut ctx);
+        let ctx = tera::Context::from_value(ctx)?;
+        Ok(self.tera.read().render(template, &ctx)?)
+    }
+
+    fn templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .tera
+            .read()
+            .get_template_names()
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn template_path(&self, name: &str) -> Option<std::path::PathBuf> {
+        let path = Path::new(&self.root).join(format!("{name}.html.tera"));
+        path.is_file().then_some(path)
+    }
+
+    fn extensions(&self) -> Option<&TemplateExtensions> {
+        Some(&self.extensions)
+    }
 }
 
 /// Represents the state of the application.
@@ -311,14 +1117,19 @@ impl TemplateEngine for TeraEngine {
 ///
 /// # Fields
 ///
-/// * `registry`: An `Arc` to a `CapsuleRegistry` instance.
-///   - This registry is likely responsible for managing and accessing various capsules,
-///     which may represent plugins, modules, or components within the application.
+/// * `registry`: An `Arc` to the [`LiveRegistry`] of the site.
+///   - Handlers take a snapshot of it on each request, so capsules can be added, replaced or removed
+///     while the server runs.
 ///
 /// * `engine`: An `Arc` to a trait object implementing the `TemplateEngine` trait.
 ///   - This provides the functionality for rendering templates, enabling dynamic content
 ///     generation based on templates and data.
 ///
+/// * `normalization`: The [`UrlNormalization`] enforced by `catch_all`.
+///
+/// * `search`: The [`SearchIndex`] of the site, queried by search capsules. Built when the server starts and
+///   rebuilt whenever the registry changes.
+///
 /// # Derives
 ///
 /// * `Clone`: The structure can be cloned, ensuring that the underlying `Arc`
@@ -330,7 +1141,7 @@ impl TemplateEngine for TeraEngine {
 /// ```rust
 /// use std::sync::Arc;
 ///
-/// let registry = Arc::new(CapsuleRegistry::new());
+/// let registry = Arc::new(LiveRegistry::new(CapsuleRegistry::default()));
 /// let engine = Arc::new(MyTemplateEngine::new());
 ///
 /// let app_state = AppState {
@@ -343,8 +1154,12 @@ impl TemplateEngine for TeraEngine {
 /// ```
 #[derive(Clone)]
 struct AppState {
-    registry: Arc<CapsuleRegistry>,
+    registry: Arc<LiveRegistry>,
     engine: Arc<dyn TemplateEngine>,
+    normalization: UrlNormalization,
+    search: Arc<parking_lot::RwLock<Arc<SearchIndex>>>,
+    seo_defaults: Option<SiteSeoDefaults>,
+    cards: Option<Arc<SocialCards>>,
 }
 /// Handler function for the "Not Found" (404) error page.
 ///
@@ -391,21 +1206,37 @@ fn not_found() -> Template {
 ///
 /// * `path` - The path of the request, represented as a `std::path::PathBuf`. It is automatically extracted from the URL by Rocket
 ///   and normalized to begin with a forward slash (e.g., "/example").
+/// * `origin` - The request URI as sent by the client, checked against the configured [`UrlNormalization`].
+/// * `visitor` - The [`Visitor`] making the request: its CSRF token, session and identity. The identity is checked
+///   against the capsule's [`Access`]; everything is exposed to the template (see [`render_capsule`]).
+/// * `auth` - The managed [`AuthConfig`], used for Basic challenges and login redirects.
 /// * `state` - A reference to the shared application state (`AppState`) that contains important resources such as the registry
 ///   for looking up capsules and the rendering engine.
 ///
 /// # Returns
 ///
-/// * `Template` - If a matching capsule is found in the `registry`, its content is rendered using the shared `engine`
-///   and returned as a `Template`. If no match is found, a "404 Not Found" page is rendered and returned.
+/// * `Reply` - If a matching capsule is found in the `registry` and the visitor may access it, its content is rendered
+///   using the shared `engine`. Denied visitors get a `401` challenge, a redirect to the login page, or a `403` page.
+///   If no match is found, a "404 Not Found" page is rendered and returned.
 ///
 /// # Behavior
 ///
-/// 1. Normalizes the incoming path to ensure it begins with a "/".
-/// 2. Checks the `registry` (a component of `AppState`) for a capsule corresponding to the requested path.
-/// 3. If a capsule is found:
+/// 1. Redirects (`301`) to the canonical URL when the request path differs from its [`UrlNormalization`]
+///    (trailing slash, case). The query string is kept.
+/// 2. Normalizes the incoming path to ensure it begins with a "/".
+/// 3. Answers with a redirect when the path belongs to a redirect capsule, a capsule alias or a redirect
+///    rule (see [`CapsuleRegistry::redirect_for`]).
+/// 4. At `/`, visitors whose `Accept-Language` prefers one of the home page translations are redirected
+///    (`302`) to it. Give the home capsule a `locale` so that visitors speaking its language stay.
+///    A language chosen explicitly with the [`LOCALE_COOKIE`](i18n::LOCALE_COOKIE) cookie replaces the
+///    header. These replies carry `Vary: Accept-Language, Cookie` so that caches keep them apart.
+/// 5. Checks the `registry` (a component of `AppState`) for a capsule corresponding to the requested path.
+/// 6. If a capsule is found (collection capsules are also found at `{uri}/page/N`):
+///     - Its [`Access`] requirement is checked against the visitor's [`Identity`].
 ///     - The capsule is passed to the `render_capsule` function along with the rendering engine to generate the response content.
-/// 4. If no capsule is found:
+///     - Collection capsules also get the `items` and `pagination` of the requested page (see [`Collection`]).
+///     - Search capsules also get the `query`, `total` and `results` of the `q` parameter (see [`SearchPage`]).
+/// 7. If no capsule is found:
 ///     - A "404 Not Found" template is served with the requested path included in the template context.
 ///
 /// # Route Details
@@ -444,32 +1275,167 @@ fn not_found() -> Template {
 /// Ensure that the `AppState` is properly initialized with a valid registry and rendering engine in the Rocket application
 /// for this route to function correctly.
 #[get("/<path..>", rank = 1)]
-fn catch_all(path: std::path::PathBuf, state: &State<AppState>) -> Template {
+async fn catch_all(
+    path: std::path::PathBuf,
+    origin: &Origin<'_>,
+    visitor: Visitor<'_>,
+    auth: &State<AuthConfig>,
+    state: &State<AppState>,
+) -> Reply {
+    let requested = origin.path().as_str();
+    let canonical = state.normalization.normalize(requested);
+    if canonical != requested {
+        let target = match origin.query() {
+            Some(query) => format!("{canonical}?{query}"),
+            None => canonical.into_owned(),
+        };
+        return Reply::Redirect(RedirectStatus::Moved.redirect(target));
+    }
     // Normalize to "/xyz"
     let path = format!("/{}", path.display());
-    let registry = state.registry.clone();
+    let registry = state.registry.snapshot();
     let engine = state.engine.clone();
-    if let Some(capsule) = registry.get(&path).cloned() {
-        render_capsule(&capsule, engine.as_ref())
+    if let Some(rule) = registry.redirect_for(&path) {
+        return Reply::Redirect(rule.status.redirect(rule.to));
+    }
+    // The home page depends on the visitor's language when it is translated
+    let negotiated = path == "/"
+        && registry
+            .get(&path)
+            .is_some_and(|capsule| !capsule.translations.is_empty());
+    let vary = |reply: Reply| {
+        if negotiated {
+            let header = Header::new("Vary", "Accept-Language, Cookie");
+            Reply::Negotiated(Box::new(reply), header)
+        } else {
+            reply
+        }
+    };
+    let chosen = visitor.request.cookies.get(LOCALE_COOKIE);
+    if negotiated
+        && let Some(preference) = chosen.or(visitor.request.headers.get("accept-language"))
+        && let Some(translation) = registry.negotiate(&path, preference)
+    {
+        return vary(Reply::Redirect(
+            RedirectStatus::Found.redirect(translation.to_string()),
+        ));
+    }
+    // Collections are also served at "{uri}/page/N"
+    let found = match registry.get(&path) {
+        Some(capsule) => Some((capsule.clone(), 1)),
+        None => split_page_uri(&path)
+            .filter(|(_, page)| *page > 1)
+            .and_then(|(base, page)| Some((registry.get(&base)?.clone(), page))),
+    };
+    let reply = if let Some((mut capsule, page)) = found {
+        if let Some(denied) = deny_access(&capsule, &path, &visitor.identity, auth).await {
+            return denied;
+        }
+        if let Some(site) = &state.seo_defaults {
+            capsule.seo = capsule.seo.map(|seo| seo.with_defaults(site));
+        }
+        if let (Some(cards), Some(seo)) = (&state.cards, &mut capsule.seo) {
+            cards.apply(&capsule.uri, seo);
+        }
+        match (
+            &capsule.collection,
+            registry.collection_page(&capsule.uri, page),
+        ) {
+            (None, _) if page == 1 => match &capsule.search {
+                Some(search) => {
+                    let query = visitor.request.query.get(QUERY_PARAM).cloned();
+                    let results = state.search.read().search(
+                        &query.unwrap_or_default(),
+                        capsule.locale.as_deref(),
+                        search.limit,
+                    );
+                    let mut ctx = capsule_context(&capsule, engine.as_ref(), &visitor);
+                    results.insert_into(&mut ctx);
+                    Reply::Page((Status::Ok, Template::render(capsule.template.clone(), ctx)))
+                }
+                None => Reply::Page((
+                    Status::Ok,
+                    render_capsule(&capsule, engine.as_ref(), &visitor),
+                )),
+            },
+            (Some(_), Some(listing)) => {
+                let mut ctx = capsule_context(&capsule, engine.as_ref(), &visitor);
+                listing.insert_into(&mut ctx);
+                Reply::Page((Status::Ok, Template::render(capsule.template.clone(), ctx)))
+            }
+            _ => Reply::Page((
+                Status::NotFound,
+                Template::render("404", context! { path, request: &visitor.request }),
+            )),
+        }
     } else {
-        Template::render("404", context! { path })
+        Reply::Page((
+            Status::Ok,
+            Template::render("404", context! { path, request: &visitor.request }),
+        ))
+    };
+    vary(reply)
+}
+/// Serves the generated social cards, mounted at [`CARDS_BASE`] with
+/// [`RocketTeraServer::with_social_cards`].
+///
+/// # Returns
+///
+/// The PNG named `file`, rendered on first request (see [`SocialCards::get`]), `404` for unknown
+/// cards and `500` when a card cannot be rendered.
+#[get("/<file>")]
+async fn social_card(
+    file: &str,
+    state: &State<AppState>,
+) -> Result<(ContentType, Vec<u8>), Status> {
+    let cards = state.cards.clone().ok_or(Status::NotFound)?;
+    let file_name = file.to_string();
+    // Rendering and caching a card is CPU and disk work
+    let card = tokio::task::spawn_blocking(move || cards.get(&file_name))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|card| card);
+    match card {
+        Ok(Some(png)) => Ok((ContentType::PNG, png)),
+        Ok(None) => Err(Status::NotFound),
+        Err(error) => {
+            log::error!("social card {file} failed: {error:#}");
+            Err(Status::InternalServerError)
+        }
     }
 }
 /// Handles POST requests to dynamic routes, parses the request body, and renders a template based on the request path.
 ///
 /// # Parameters
 /// - `path`: A `PathBuf` representing the dynamic route extracted from the URL.
-/// - `data`: A `String` containing the body of the POST request.
+/// - `body`: The [`Submission`]: the raw body of the POST request and its fields, parsed from JSON or
+///   `application/x-www-form-urlencoded` depending on the content type.
+/// - `visitor`: The [`Visitor`] making the request. Its CSRF token is checked against the submitted `_csrf` field or
+///   `X-CSRF-Token` header, its identity against the capsule's [`Access`], and its session is updated by the
+///   capsule's [`PostActions`].
+/// - `auth`: The managed [`AuthConfig`], used for Basic challenges and login redirects.
 /// - `state`: A reference to the application state (`State<AppState>`), which holds shared data like a registry and engine.
 ///
 /// # Returns
-/// A `Template` that is rendered based on the state and request data. If the path matches an entry in the registry,
-/// the corresponding template is rendered with the appropriate context. Otherwise, a "404" template is rendered.
+/// A [`Reply`]: either a `Template` rendered based on the state and request data, paired with the response
+/// status, or a redirect when the capsule is configured with one. If the path matches no entry in the
+/// registry, a "404" template is rendered.
+///
+/// This handler is `async` because form sinks (see [`FormSchema`]) may deliver submissions over the network.
 ///
 /// # Behavior
 /// - The function converts the request path into a string and looks it up in the `registry` stored in the application state.
+/// - Redirect capsules, capsule aliases and redirect rules answer with their redirect first. Use `307` or `308`
+///   for endpoints receiving submissions, so clients resend the body to the new location.
 /// - If a corresponding "capsule" (a unit of template and data) is found in the registry:
-///   - The capsule is cloned and the POST request body is embedded as JSON under the key `"body"`.
+///   - Its [`Access`] requirement is checked first, exactly like for `GET` requests.
+///   - Unless the capsule opted out with [`Capsule::without_csrf`], the submitted CSRF token is verified.
+///     A missing or invalid token renders the "403" template with a `403 Forbidden` status.
+///   - The capsule is cloned and the request body is embedded under the key `"body"` (raw) and `"form"` (parsed fields).
+///   - If the capsule declares a [`FormSchema`], the fields are validated. Invalid submissions re-render the template
+///     with `422 Unprocessable Entity`, `errors` and the submitted `values`. Valid ones are delivered to the form sink.
+///   - The capsule's [`PostActions`] are applied: session values are stored and the flash message is queued.
+///   - If the capsule has a redirect, the visitor is sent there with `303 See Other`.
 ///   - A rendering context is generated for the capsule using the rendering engine.
 ///   - The specified template is rendered with the constructed context.
 /// - If no matching capsule is found in the registry, it renders the "404" template, passing the requested path as part
@@ -478,26 +1444,296 @@ fn catch_all(path: std::path::PathBuf, state: &State<AppState>) -> Template {
 /// # Example
 /// ```
 /// // Route: POST /example/path
-/// // Request Body: "test data"
+/// // Request Body: "_csrf=<token>&message=test+data"
 ///
 /// // Assuming a registry with "/example/path" mapped to a template:
-/// // - Template will be rendered with the body "test data" injected into the context.
+/// // - Template will be rendered with the body injected into the context.
+///
+/// // Without a valid `_csrf` field or `X-CSRF-Token` header:
+/// // - The "403" template will be rendered with the path and the reason in the context.
 ///
 /// // If no entry exists for "/example/path":
 /// // - The "404" template will be rendered with the path in the context.
 /// ```
-#[post("/<path..>", data = "<data>")]
-fn handle_post(path: std::path::PathBuf, data: String, state: &State<AppState>) -> Template {
+#[post("/<path..>", data = "<body>")]
+async fn handle_post(
+    path: std::path::PathBuf,
+    body: Submission,
+    mut visitor: Visitor<'_>,
+    auth: &State<AuthConfig>,
+    state: &State<AppState>,
+) -> Reply {
+    handle_write(path, body, &mut visitor, auth, state).await
+}
+
+/// Handles PUT requests to dynamic routes. See [`handle_post`] for the behavior.
+#[put("/<path..>", data = "<body>")]
+async fn handle_put(
+    path: std::path::PathBuf,
+    body: Submission,
+    mut visitor: Visitor<'_>,
+    auth: &State<AuthConfig>,
+    state: &State<AppState>,
+) -> Reply {
+    handle_write(path, body, &mut visitor, auth, state).await
+}
+
+/// Handles DELETE requests to dynamic routes. See [`handle_post`] for the behavior.
+#[delete("/<path..>", data = "<body>")]
+async fn handle_delete(
+    path: std::path::PathBuf,
+    body: Submission,
+    mut visitor: Visitor<'_>,
+    auth: &State<AuthConfig>,
+    state: &State<AppState>,
+) -> Reply {
+    handle_write(path, body, &mut visitor, auth, state).await
+}
+
+/// Shared implementation of the state-changing handlers (`POST`, `PUT`, `DELETE`).
+async fn handle_write(
+    path: std::path::PathBuf,
+    body: Submission,
+    visitor: &mut Visitor<'_>,
+    auth: &AuthConfig,
+    state: &AppState,
+) -> Reply {
     let path_str = format!("/{}", path.display());
-    let registry = state.registry.clone();
+    let registry = state.registry.snapshot();
     let engine = state.engine.clone();
-    if let Some(capsule) = registry.get(&path_str).cloned() {
-        let mut capsule = capsule.clone();
-        capsule.data = serde_json::json!({ "body": data });
-        let ctx = engine.context_for(&capsule);
-        return Template::render(capsule.template, ctx);
+    if let Some(rule) = registry.redirect_for(&path_str) {
+        return Reply::Redirect(rule.status.redirect(rule.to));
+    }
+    let Some(mut capsule) = registry.get(&path_str).cloned() else {
+        return Reply::Page((
+            Status::Ok,
+            Template::render(
+                "404",
+                context! { path: path_str, request: &visitor.request },
+            ),
+        ));
+    };
+    if let Some(denied) = deny_access(&capsule, &path_str, &visitor.identity, auth).await {
+        return denied;
+    }
+    if capsule.csrf && !visitor.csrf.verify(body.field(CSRF_FIELD)) {
+        return Reply::Page((
+            Status::Forbidden,
+            Template::render(
+                "403",
+                context! { path: path_str, reason: "invalid or missing CSRF token" },
+            ),
+        ));
+    }
+    capsule.data = serde_json::json!({ "body": body.raw, "form": body.fields });
+
+    if let Some(form) = &capsule.form {
+        let values = match form.validate(&body.fields) {
+            Ok(values) => values,
+            Err(errors) => {
+                return Reply::Page((
+                    Status::UnprocessableEntity,
+                    render_form_errors(&capsule, engine.as_ref(), visitor, &body, &errors),
+                ));
+            }
+        };
+        if let Some(sink) = &form.sink
+            && let Err(error) = sink.deliver(&capsule.uri, &values).await
+        {
+            log::error!("form sink of {} failed: {error:#}", capsule.uri);
+            let errors = FormErrors::from([(
+                "_form".to_string(),
+                vec!["Your submission could not be processed, please try again later.".to_string()],
+            )]);
+            return Reply::Page((
+                Status::InternalServerError,
+                render_form_errors(&capsule, engine.as_ref(), visitor, &body, &errors),
+            ));
+        }
+    }
+
+    capsule.actions.apply(&mut visitor.session, &body.fields);
+    if let Some(target) = &capsule.actions.redirect {
+        return Reply::Redirect(Redirect::to(target.clone()));
+    }
+    Reply::Page((
+        Status::Ok,
+        render_capsule(&capsule, engine.as_ref(), visitor),
+    ))
+}
+
+/// Re-renders a capsule whose form submission was rejected, with `errors` and the submitted `values`.
+///
+/// The CSRF field is left out of `values`; the template gets a fresh `csrf_token` anyway. The
+/// values are HTML-escaped, as auto-escaping is off, so `value="{{ values.email }}"` is safe.
+fn render_form_errors(
+    capsule: &Capsule,
+    engine: &dyn TemplateEngine,
+    visitor: &Visitor<'_>,
+    body: &Submission,
+    errors: &FormErrors,
+) -> Template {
+    let mut ctx = capsule_context(capsule, engine, visitor);
+    if let Some(map) = ctx.as_object_mut() {
+        let mut values = body.fields.clone();
+        values.remove(CSRF_FIELD);
+        let mut values = serde_json::Value::Object(values);
+        escape_strings(&mut values);
+        map.insert("values".into(), values);
+        map.insert(
+            "errors".into(),
+            serde_json::to_value(errors).unwrap_or_default(),
+        );
+    }
+    Template::render(capsule.template.clone(), ctx)
+}
+
+/// What the capsule handlers send back to the client.
+#[derive(Responder)]
+enum Reply {
+    /// A rendered template with its response status.
+    Page((Status, Template)),
+    /// A redirect, e.g. after a successful form submission or for a redirect capsule.
+    Redirect(Redirect),
+    /// An HTTP Basic authentication challenge.
+    Challenge(Challenge),
+    /// A reply chosen by language, with the `Vary` header telling caches so.
+    Negotiated(Box<Reply>, Header<'static>),
+}
+
+/// A `401 Unauthorized` page carrying the `WWW-Authenticate` header that makes browsers prompt for credentials.
+#[derive(Responder)]
+#[response(status = 401)]
+struct Challenge {
+    page: Template,
+    authenticate: Header<'static>,
+}
+
+/// Checks the capsule's [`Access`] requirement, returning the reply to send when access is denied.
+///
+/// - Missing or wrong HTTP Basic credentials get a `401` challenge rendering the "401" template.
+/// - Visitors who are not logged in are redirected to the login page, with `next` pointing back to `path`.
+/// - Authenticated visitors lacking the required role get the "403" template with a `403 Forbidden` status.
+async fn deny_access(
+    capsule: &Capsule,
+    path: &str,
+    identity: &Identity,
+    auth: &AuthConfig,
+) -> Option<Reply> {
+    match capsule.access.check(identity, auth).await {
+        AccessDecision::Granted => None,
+        AccessDecision::Challenge => Some(Reply::Challenge(Challenge {
+            page: Template::render("401", context! { path, reason: "authentication required" }),
+            authenticate: Header::new(
+                "WWW-Authenticate",
+                format!(r#"Basic realm="{}", charset="UTF-8""#, auth.realm),
+            ),
+        })),
+        AccessDecision::LoginRequired => Some(Reply::Redirect(Redirect::to(format!(
+            "{}?next={}",
+            auth.login_uri,
+            RawStr::new(path).percent_encode()
+        )))),
+        AccessDecision::Forbidden => Some(Reply::Page((
+            Status::Forbidden,
+            Template::render("403", context! { path, reason: "insufficient role" }),
+        ))),
+    }
+}
+
+/// Renders the built-in login page.
+///
+/// The page is mounted at [`AuthConfig::login_uri`] and renders [`AuthConfig::login_template`] with
+/// `next` (where to go after logging in), `error`, `csrf_token` and `request`. The form must post
+/// `username`, `password`, `next` and `_csrf` back to the same URI.
+#[get("/?<next>")]
+fn login_page(
+    next: Option<String>,
+    csrf: CsrfToken,
+    request: RequestInfo,
+    auth: &State<AuthConfig>,
+) -> Template {
+    Template::render(
+        auth.login_template.clone(),
+        context! {
+            next: safe_next(next.as_deref()),
+            error: Option::<&str>::None,
+            csrf_token: csrf.value(),
+            request,
+        },
+    )
+}
+
+/// Handles the login form: on success the username is stored in the session and the visitor
+/// is redirected to `next`; otherwise the login page is rendered again with an `error`.
+#[post("/", data = "<body>")]
+async fn login_submit(
+    body: Submission,
+    csrf: CsrfToken,
+    request: RequestInfo,
+    mut session: Session<'_>,
+    auth: &State<AuthConfig>,
+) -> Reply {
+    let field = |name: &str| body.field(name);
+    let next = safe_next(field("next"));
+    if !csrf.verify(field(CSRF_FIELD)) {
+        return Reply::Page((
+            Status::Forbidden,
+            Template::render(
+                "403",
+                context! { path: auth.login_uri.clone(), reason: "invalid or missing CSRF token" },
+            ),
+        ));
+    }
+    let user = auth
+        .authenticate_async(
+            field("username").unwrap_or_default(),
+            field("password").unwrap_or_default(),
+        )
+        .await;
+    match user {
+        Some(user) => {
+            session.set(SESSION_USER_KEY, user.username);
+            Reply::Redirect(Redirect::to(next))
+        }
+        None => Reply::Page((
+            Status::Unauthorized,
+            Template::render(
+                auth.login_template.clone(),
+                context! {
+                    next,
+                    error: "invalid username or password",
+                    csrf_token: csrf.value(),
+                    request,
+                },
+            ),
+        )),
     }
-    Template::render("404", context! { path: path_str })
+}
+
+/// Logs the visitor out and redirects them to the home page.
+///
+/// Mounted at [`AuthConfig::logout_uri`] for `POST` only, with the `_csrf` field or the
+/// `X-CSRF-Token` header, so other sites cannot log visitors out. Templates use a form:
+/// `<form method="post" action="/logout"><input type="hidden" name="_csrf" value="{{ csrf_token }}"><button>Log out</button></form>`.
+#[post("/", data = "<body>")]
+fn logout(
+    body: Submission,
+    csrf: CsrfToken,
+    mut session: Session<'_>,
+    auth: &State<AuthConfig>,
+) -> Reply {
+    if !csrf.verify(body.field(CSRF_FIELD)) {
+        return Reply::Page((
+            Status::Forbidden,
+            Template::render(
+                "403",
+                context! { path: auth.logout_uri.clone(), reason: "invalid or missing CSRF token" },
+            ),
+        ));
+    }
+    session.remove(SESSION_USER_KEY);
+    Reply::Redirect(Redirect::to("/"))
 }
 
 /// Renders a capsule using the provided template engine.
@@ -514,6 +1750,16 @@ fn handle_post(path: std::path::PathBuf, data: String, state: &State<AppState>)
 /// * `engine` - A dynamic reference to an object implementing the `TemplateEngine`
 ///   trait, which provides the necessary functionality to create a rendering context
 ///   and render templates.
+/// * `visitor` - The [`Visitor`] making the request. The context gets:
+///   - `request`: the [`RequestInfo`] (query, selected headers, cookies, client IP, host and scheme).
+///   - `csrf_token`: the visitor's CSRF token.
+///   - `session`: the values of the visitor's session.
+///   - `flash`: the pending flash message, consumed by this render, or `null`.
+///   - `user`: the authenticated visitor, or `null` for anonymous visitors.
+///   - `errors` and `values`: empty objects when the capsule declares a form, so templates can
+///     use them before anything was submitted.
+///
+///   The globals of the engine's [`TemplateExtensions`] are added too, unless the context has a value with the same name.
 ///
 /// # Returns
 ///
@@ -524,7 +1770,7 @@ fn handle_post(path: std::path::PathBuf, data: String, state: &State<AppState>)
 /// ```rust
 /// let capsule = Capsule::new("example_template", some_data);
 /// let engine = MyTemplateEngine::new();
-/// let rendered_template = render_capsule(&capsule, &engine);
+/// let rendered_template = render_capsule(&capsule, &engine, &visitor);
 /// println!("{}", rendered_template.content());
 /// ```
 ///
@@ -541,9 +1787,65 @@ fn handle_post(path: std::path::PathBuf, data: String, state: &State<AppState>)
 /// * `TemplateEngine` - The trait that must be implemented by the template engine used
 ///   for rendering.
 /// * `Template::render` - The method used to render a template with a given context.
-fn render_capsule(capsule: &Capsule, engine: &dyn TemplateEngine) -> Template {
-    let ctx = engine.context_for(capsule);
-    Template::render(capsule.template.clone(), ctx)
+fn render_capsule(
+    capsule: &Capsule,
+    engine: &dyn TemplateEngine,
+    visitor: &Visitor<'_>,
+) -> Template {
+    Template::render(
+        capsule.template.clone(),
+        capsule_context(capsule, engine, visitor),
+    )
+}
+
+/// Builds the context of [`render_capsule`]: the engine's context plus the visitor-specific values.
+fn capsule_context(
+    capsule: &Capsule,
+    engine: &dyn TemplateEngine,
+    visitor: &Visitor<'_>,
+) -> serde_json::Value {
+    let mut ctx = engine.context_for(capsule, &visitor.request);
+    if let Some(extensions) = engine.extensions() {
+        extensions.merge_globals(&mut ctx);
+    }
+    if let Some(map) = ctx.as_object_mut() {
+        map.insert("csrf_token".into(), visitor.csrf.value().into());
+        map.insert("session".into(), visitor.session.values().clone().into());
+        map.insert(
+            "flash".into(),
+            serde_json::to_value(visitor.session.take_flash()).unwrap_or_default(),
+        );
+        map.insert(
+            "user".into(),
+            serde_json::to_value(visitor.identity.user()).unwrap_or_default(),
+        );
+        if capsule.form.is_some() {
+            map.insert("errors".into(), serde_json::json!({}));
+            map.insert("values".into(), serde_json::json!({}));
+        }
+    }
+    ctx
+}
+/// Builds the context a capsule renders with outside of a request, e.g. when checking or exporting a site.
+///
+/// It holds the same keys as the context of [`render_capsule`], as an anonymous visitor with an empty
+/// session would get them: `request` has default values, `csrf_token` is empty, `flash` and `user` are `null`.
+pub fn static_context(capsule: &Capsule, engine: &dyn TemplateEngine) -> serde_json::Value {
+    let mut ctx = engine.context_for(capsule, &RequestInfo::default());
+    if let Some(extensions) = engine.extensions() {
+        extensions.merge_globals(&mut ctx);
+    }
+    if let Some(map) = ctx.as_object_mut() {
+        map.insert("csrf_token".into(), "".into());
+        map.insert("session".into(), serde_json::json!({}));
+        map.insert("flash".into(), serde_json::Value::Null);
+        map.insert("user".into(), serde_json::Value::Null);
+        if capsule.form.is_some() {
+            map.insert("errors".into(), serde_json::json!({}));
+            map.insert("values".into(), serde_json::json!({}));
+        }
+    }
+    ctx
 }
 /// A structure representing a server configuration for Rocket with Tera templates.
 ///
@@ -555,17 +1857,32 @@ fn render_capsule(capsule: &Capsule, engine: &dyn TemplateEngine) -> Template {
 /// * `templates_dir` - A `String` specifying the directory where the Tera templates are stored.
 ///   By default, this can be customized to match the specific path to the templates directory
 ///   (e.g., "templates").
+/// * `session` - The [`SessionConfig`] used for visitor sessions and flash messages.
+/// * `auth` - The [`AuthConfig`] holding the users and the built-in login/logout pages.
+/// * `normalization` - The [`UrlNormalization`] enforced with canonical redirects on `GET` requests.
+/// * `admin` - The [`AdminConfig`] of the admin REST API, which is only mounted when set.
+/// * `seo_defaults` - The [`SiteSeoDefaults`] applied to the `seo` of every page, when set.
+/// * `cards` - The [`SocialCards`] of the pages without an `og_image`, served when set.
 ///
 /// # Example
 ///
 /// ```rust
 /// let server = RocketTeraServer {
 ///     templates_dir: String::from("custom_templates"),
+///     session: SessionConfig::default(),
+///     auth: AuthConfig::default(),
+///     normalization: UrlNormalization::default(),
 /// };
 /// println!("Templates directory: {}", server.templates_dir);
 /// ```
 pub struct RocketTeraServer {
     templates_dir: String,
+    session: SessionConfig,
+    auth: AuthConfig,
+    normalization: UrlNormalization,
+    admin: Option<AdminConfig>,
+    seo_defaults: Option<SiteSeoDefaults>,
+    cards: Option<Arc<SocialCards>>,
 }
 
 impl RocketTeraServer {
@@ -588,15 +1905,72 @@ impl RocketTeraServer {
     pub fn new(templates_dir: impl Into<String>) -> Self {
         Self {
             templates_dir: templates_dir.into(),
+            session: SessionConfig::default(),
+            auth: AuthConfig::default(),
+            normalization: UrlNormalization::default(),
+            admin: None,
+            seo_defaults: None,
+            cards: None,
         }
     }
+
+    /// Sets the lifetime and cookie attributes of visitor sessions.
+    pub fn with_session(mut self, session: SessionConfig) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Sets the users allowed to authenticate and where the login and logout pages live.
+    ///
+    /// The built-in login and logout routes are only mounted when at least one user is configured.
+    pub fn with_auth(mut self, auth: AuthConfig) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sets the trailing-slash and case rules of canonical URLs.
+    ///
+    /// `GET` requests whose path is not canonical are redirected (`301`) to the canonical path.
+    /// With `lowercase`, the server refuses to start when a capsule URI holds uppercase letters,
+    /// and the admin API refuses such capsules: they could never be reached.
+    pub fn with_normalization(mut self, normalization: UrlNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Mounts the admin REST API at `/__admin/api`, to manage capsules while the server runs.
+    ///
+    /// Only users of the [`AuthConfig`] holding the configured role can use it (see [`AdminConfig`]).
+    pub fn with_admin(mut self, admin: AdminConfig) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
+    /// Completes the `seo` of every page with site-wide settings before it is rendered.
+    ///
+    /// See [`Seo::with_defaults`].
+    pub fn with_seo_defaults(mut self, defaults: SiteSeoDefaults) -> Self {
+        self.seo_defaults = Some(defaults);
+        self
+    }
+
+    /// Gives the pages without an `og_image` a generated card, served at `/__cards/{hash}.png`.
+    ///
+    /// Pages fall back to the `og_image` of [`RocketTeraServer::with_seo_defaults`] first.
+    ///
+    /// See [`SocialCards`].
+    pub fn with_social_cards(mut self, cards: SocialCards) -> Self {
+        self.cards = Some(Arc::new(cards));
+        self
+    }
 }
 
 impl HttpServer for RocketTeraServer {
     /// Serves the application by setting up and launching a Rocket web server.
     ///
     /// # Parameters
-    /// - `registry`: An `Arc` wrapped instance of `CapsuleRegistry` used to manage application data.
+    /// - `registry`: The [`LiveRegistry`] of the capsules served. Changes made to it while the server runs are
+    ///   served from the next request on.
     /// - `engine`: An `Arc` wrapped trait object implementing `TemplateEngine` used to manage and render templates.
     ///
     /// # Returns
@@ -607,8 +1981,16 @@ impl HttpServer for RocketTeraServer {
     /// 2. Builds a Rocket instance with customized Tera template handling:
     ///    - Configures Rocket to load templates from the specified directory (`templates_dir`) with filenames ending in `.html.tera`.
     ///    - Disables auto-escaping for template rendering.
-    /// 3. Manages application state using `AppState`, allowing access to the registry and template engine during request handling.
-    /// 4. Mounts the Rocket instance to the root path (`"/"`) with predefined routes (`catch_all`, `handle_post`, `not_found`) and catchers (`default_catcher`).
+    ///    - Installs the engine's [`TemplateExtensions`] (filters and functions), and the `url_for` function
+    ///      resolving capsule names against the registry (see [`UrlFor`]).
+    /// 3. Manages application state using `AppState`, allowing access to the registry and template engine during request handling,
+    ///    along with the [`SessionConfig`] read by the [`Session`] request guard and the [`AuthConfig`] read by
+    ///    the [`Identity`] request guard.
+    /// 4. Mounts the Rocket instance to the root path (`"/"`) with predefined routes (`catch_all`, `handle_post`, `handle_put`,
+    ///    `handle_delete`, `not_found`) and catchers (`default_catcher`). When users are configured, the login and logout
+    ///    pages are mounted at `login_uri` and `logout_uri`. With [`RocketTeraServer::with_admin`], the admin REST API
+    ///    is mounted at `/__admin/api`. With [`RocketTeraServer::with_social_cards`], the generated cards are
+    ///    served at `/__cards/{hash}.png`.
     /// 5. Initiates and launches the Rocket server asynchronously.
     /// 6. Returns a `Result` indicating whether the Rocket server launched successfully or encountered an error.
     ///
@@ -624,7 +2006,7 @@ impl HttpServer for RocketTeraServer {
     /// # Example Usage
     /// ```rust
     /// let app = MyApp { templates_dir: "templates".to_string() };
-    /// let registry = Arc::new(CapsuleRegistry::new());
+    /// let registry = Arc::new(LiveRegistry::new(CapsuleRegistry::default()));
     /// let engine = Arc::new(MyTemplateEngine::new());
     ///
     /// let server_future = app.serve(registry, engine);
@@ -632,23 +2014,77 @@ impl HttpServer for RocketTeraServer {
     /// ```
     fn serve<'a>(
         &'a self,
-        registry: Arc<CapsuleRegistry>,
+        registry: Arc<LiveRegistry>,
         engine: Arc<dyn TemplateEngine>,
     ) -> std::pin::Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
         Box::pin(async move {
             // Load templates once
             engine.load_all()?;
 
+            let unreachable: Vec<String> = registry
+                .snapshot()
+                .all()
+                .map(|(uri, _)| uri)
+                .filter(|uri| !self.normalization.reaches(uri))
+                .cloned()
+                .collect();
+            if !unreachable.is_empty() {
+                anyhow::bail!(
+                    "URLs are lowercased, so these capsules cannot be reached: {}",
+                    unreachable.join(", ")
+                );
+            }
+
+            // Index the site, and index it again whenever the registry changes
+            let search = Arc::new(parking_lot::RwLock::new(Arc::new(SearchIndex::build(
+                &registry.snapshot(),
+            ))));
+            let mut events = registry.subscribe();
+            tokio::spawn({
+                let registry = registry.clone();
+                let search = search.clone();
+                let cards = self.cards.clone();
+                async move {
+                    use tokio::sync::broadcast::error::RecvError;
+                    // Missed events only mean the index is rebuilt fewer times
+                    while let Ok(_) | Err(RecvError::Lagged(_)) = events.recv().await {
+                        let snapshot = registry.snapshot();
+                        *search.write() = Arc::new(SearchIndex::build(&snapshot));
+                        if let Some(cards) = &cards {
+                            cards.retain(|page| snapshot.get(page).is_some());
+                        }
+                    }
+                }
+            });
+
             // Build rocket with a custom Tera (so changes from engine.load_all are used)
-            let state = AppState { registry, engine };
+            let state = AppState {
+                registry,
+                engine,
+                normalization: self.normalization,
+                search,
+                seo_defaults: self.seo_defaults.clone(),
+                cards: self.cards.clone(),
+            };
+            let engine = state.engine.clone();
+            let registry = state.registry.clone();
 
             let rocket = rocket::build()
                 .manage(state)
+                .manage(self.session.clone())
+                .manage(self.auth.clone())
                 .attach(Template::custom({
                     let templates_dir = self.templates_dir.clone();
                     move |engines| {
                         // Tell Rocket to load *.html.tera from templates_dir
                         engines.tera.autoescape_on(vec![]);
+                        // Same filters and functions as the engine's own Tera
+                        if let Some(extensions) = engine.extensions() {
+                            extensions.apply(&mut engines.tera);
+                        }
+                        engines
+                            .tera
+                            .register_function("url_for", UrlFor::new(registry.clone()));
                         engines
                             .tera
                             .add_template_files(
@@ -661,8 +2097,39 @@ impl HttpServer for RocketTeraServer {
                     }
                 }))
                 // You can mount once at "/" and let `catch_all` dispatch
-                .mount("/", routes![catch_all, handle_post, not_found])
+                .mount(
+                    "/",
+                    routes![catch_all, handle_post, handle_put, handle_delete, not_found],
+                )
                 .register("/", catchers![default_catcher]);
+            let rocket = if self.auth.users.is_empty() {
+                rocket
+            } else {
+                rocket
+                    .mount(
+                        self.auth.login_uri.as_str(),
+                        routes![login_page, login_submit],
+                    )
+                    .mount(self.auth.logout_uri.as_str(), routes![logout])
+            };
+
+            let rocket = match &self.admin {
+                Some(admin) => rocket.manage(admin.clone()).mount(
+                    ADMIN_API_BASE,
+                    routes![
+                        admin::list_capsules,
+                        admin::get_capsule,
+                        admin::create_capsule,
+                        admin::update_capsule,
+                        admin::delete_capsule
+                    ],
+                ),
+                None => rocket,
+            };
+            let rocket = match &self.cards {
+                Some(_) => rocket.mount(CARDS_BASE, routes![social_card]),
+                None => rocket,
+            };
 
             rocket
                 .ignite()
@@ -724,3 +2191,142 @@ impl HttpServer for RocketTeraServer {
 fn default_catcher(_status: rocket::http::Status, _req: &Request<'_>) -> Template {
     Template::render("404", context! {})
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn about() -> Capsule {
+        let mut seo = Seo::new();
+        seo.title = "About".into();
+        seo.canonical_url = Some("https://example.com/about".into());
+        seo.og_url = Some("/about".into());
+        let mut capsule = Capsule::new("about", "About us", "/about", "about", Method::GET);
+        capsule
+            .with_locale("en")
+            .with_seo(seo)
+            .with_translation("fr", Translation::default());
+        capsule
+    }
+
+    #[test]
+    fn escape_strings_escapes_nested_values() {
+        let mut values = serde_json::json!({
+            "email": "\"><script>",
+            "tags": ["<b>", 3],
+        });
+        escape_strings(&mut values);
+        assert_eq!(
+            values,
+            serde_json::json!({ "email": "&quot;&gt;&lt;script&gt;", "tags": ["&lt;b&gt;", 3] })
+        );
+    }
+
+    #[test]
+    fn translated_localizes_inherited_seo() {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(about());
+
+        let source = registry.get("/about").unwrap().seo.as_ref().unwrap();
+        assert_eq!(source.lang.as_deref(), Some("en"));
+        let french = registry.get("/fr/about").unwrap();
+        let seo = french.seo.as_ref().unwrap();
+        assert_eq!(seo.lang.as_deref(), Some("fr"));
+        assert_eq!(
+            seo.canonical_url.as_deref(),
+            Some("https://example.com/fr/about")
+        );
+        assert_eq!(seo.og_url.as_deref(), Some("/fr/about"));
+        for alternates in [&source.alternates, &seo.alternates] {
+            assert_eq!(
+                *alternates,
+                vec![
+                    Alternate::new("en", "/about"),
+                    Alternate::new("fr", "/fr/about"),
+                    Alternate::new("x-default", "/about"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn translated_uses_the_translation_seo() {
+        let mut seo = Seo::new();
+        seo.title = "À propos".into();
+        let translation = Translation {
+            seo: Some(seo),
+            ..Translation::default()
+        };
+        let french = about().translated("fr", &translation);
+        let seo = french.seo.unwrap();
+        assert_eq!(seo.title, "À propos");
+        assert_eq!(seo.lang.as_deref(), Some("fr"));
+        assert_eq!(seo.canonical_url, None);
+    }
+
+    #[test]
+    fn redirect_for_prefers_capsules_and_exact_rules_over_patterns() {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(about());
+        registry.add_redirect(RedirectRule::new(
+            "/*",
+            "/new/:splat",
+            RedirectStatus::Found,
+        ));
+        registry.add_redirect(RedirectRule::new("/old", "/about", RedirectStatus::Moved));
+
+        assert_eq!(registry.redirect_for("/about"), None);
+        assert_eq!(registry.redirect_for("/old").unwrap().to, "/about");
+        assert_eq!(registry.redirect_for("/x/y").unwrap().to, "/new/x/y");
+        let sources: Vec<String> = registry.redirects().into_iter().map(|r| r.from).collect();
+        assert_eq!(sources, ["/old", "/*"]);
+    }
+
+    #[test]
+    fn add_replaces_the_capsule_at_the_same_uri() {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(about());
+        let mut renamed = Capsule::new("team", "Team", "/about", "about", Method::GET);
+        renamed.aliases = vec!["/team".into()];
+        registry.add(renamed);
+
+        assert!(registry.get_by_name("about").is_none());
+        assert_eq!(registry.get_by_name("team").unwrap().uri, "/about");
+        assert!(registry.get("/fr/about").is_none());
+        assert_eq!(registry.redirect_for("/team").unwrap().to, "/about");
+        assert_eq!(registry.all().count(), 1);
+    }
+
+    #[test]
+    fn url_for_resolves_names_and_translations() {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(about());
+        registry.add(Capsule::new(
+            "post",
+            "Post",
+            "/blog/<slug>",
+            "post",
+            Method::GET,
+        ));
+        let params = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let post = registry.url_for("post", &params(&[("slug", "hello world")]));
+        assert_eq!(post.unwrap(), "/blog/hello%20world");
+        let french = registry.url_for("about", &params(&[("locale", "fr")]));
+        assert_eq!(french.unwrap(), "/fr/about");
+        let error = registry.url_for("about", &params(&[("locale", "de")]));
+        assert!(
+            error
+                .unwrap_err()
+                .to_string()
+                .contains("no `de` translation")
+        );
+        assert!(registry.url_for("post", &params(&[])).is_err());
+        assert!(registry.url_for("missing", &params(&[])).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use super::auth::{AuthConfig, Identity};
+use super::csrf::{CSRF_HEADER, CsrfToken};
+use super::{AppState, Capsule};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::Json;
+use rocket::{State, delete, get, post, put};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// Where the admin API is mounted.
+pub const ADMIN_API_BASE: &str = "/__admin/api";
+
+/// Configuration of the admin REST API.
+///
+/// The API is mounted at [`ADMIN_API_BASE`] when the server is given an `AdminConfig` (see
+/// [`RocketTeraServer::with_admin`](super::RocketTeraServer::with_admin)):
+///
+/// | Method   | URI                            | Does                      |
+/// |----------|--------------------------------|---------------------------|
+/// | `GET`    | `/__admin/api/capsules`        | Lists the capsules        |
+/// | `GET`    | `/__admin/api/capsules/<name>` | Returns a capsule         |
+/// | `POST`   | `/__admin/api/capsules`        | Creates a capsule (`201`) |
+/// | `PUT`    | `/__admin/api/capsules/<name>` | Replaces a capsule        |
+/// | `DELETE` | `/__admin/api/capsules/<name>` | Deletes a capsule (`204`) |
+///
+/// Capsules are sent and returned as JSON, in the manifest format (`uri`, `template`, `method`,
+/// `data`, `seo`…). Changes are served at once and written back to the manifest files: the file a
+/// capsule was loaded from, or `{manifests_dir}/{name}.toml` for new capsules. Deleting a capsule
+/// deletes its manifest.
+///
+/// Capsules whose template does not exist are refused with `422 Unprocessable Entity`. Errors
+/// are returned as `{"error": "…"}`.
+///
+/// # Authentication
+///
+/// Requests must come from a user of [`AuthConfig`](super::auth::AuthConfig) holding `role`,
+/// authenticated with HTTP Basic credentials or logged in. Logged-in users must also send their
+/// CSRF token in the `X-CSRF-Token` header to make changes.
+///
+/// # Fields
+///
+/// * `manifests_dir` - The directory new capsule manifests are written to.
+/// * `role` - The role required to use the API (`admin` by default).
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub manifests_dir: PathBuf,
+    pub role: String,
+}
+
+impl AdminConfig {
+    /// Creates a configuration writing new manifests to `manifests_dir`.
+    pub fn new(manifests_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manifests_dir: manifests_dir.into(),
+            role: "admin".to_string(),
+        }
+    }
+
+    /// Sets the role required to use the API.
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.role = role.into();
+        self
+    }
+}
+
+/// An error answered by the admin API, as `{"error": "…"}`.
+type ApiError = (Status, Json<Value>);
+type ApiResult<T> = Result<T, ApiError>;
+
+fn api_error(status: Status, message: impl std::fmt::Display) -> ApiError {
+    (status, Json(json!({ "error": message.to_string() })))
+}
+
+/// Request guard admitting the users allowed to use the admin API.
+///
+/// Never fails: refusals are carried as an [`ApiError`] so that they are answered in JSON.
+pub struct Admin(ApiResult<()>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(config) = req.rocket().state::<AdminConfig>() else {
+            return Outcome::Success(Admin(Err(api_error(Status::NotFound, "not found"))));
+        };
+        let identity = match req.guard::<Identity>().await {
+            Outcome::Success(identity) => identity,
+            _ => Identity::default(),
+        };
+        if let Some(auth) = req.rocket().state::<AuthConfig>() {
+            identity.basic(auth).await;
+        }
+        let verdict = match identity.user() {
+            None => Err(api_error(Status::Unauthorized, "authentication required")),
+            Some(user) if !user.has_role(&config.role) => {
+                Err(api_error(Status::Forbidden, "insufficient role"))
+            }
+            Some(_) if identity.is_basic() || req.method() == rocket::http::Method::Get => Ok(()),
+            Some(_) => {
+                let token = req.headers().get_one(CSRF_HEADER);
+                match req.guard::<CsrfToken>().await {
+                    Outcome::Success(csrf) if csrf.verify(token) => Ok(()),
+                    _ => Err(api_error(
+                        Status::Forbidden,
+                        "invalid or missing CSRF token",
+                    )),
+                }
+            }
+        };
+        Outcome::Success(Admin(verdict))
+    }
+}
+
+/// Lists the capsules, translations excepted.
+#[get("/capsules")]
+pub(super) fn list_capsules(
+    admin: Admin,
+    state: &State<AppState>,
+) -> ApiResult<Json<Vec<Capsule>>> {
+    admin.0?;
+    let registry = state.registry.snapshot();
+    Ok(Json(
+        registry
+            .all()
+            .map(|(_, capsule)| capsule)
+            .filter(|capsule| {
+                registry
+                    .get_by_name(&capsule.name)
+                    .is_some_and(|named| named.uri == capsule.uri)
+            })
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Returns the capsule named `name`.
+#[get("/capsules/<name>")]
+pub(super) fn get_capsule(
+    name: &str,
+    admin: Admin,
+    state: &State<AppState>,
+) -> ApiResult<Json<Capsule>> {
+    admin.0?;
+    state
+        .registry
+        .snapshot()
+        .get_by_name(name)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| no_capsule(name))
+}
+
+/// Creates a capsule and writes its manifest.
+#[post("/capsules", data = "<capsule>")]
+pub(super) fn create_capsule(
+    capsule: Json<Capsule>,
+    admin: Admin,
+    config: &State<AdminConfig>,
+    state: &State<AppState>,
+) -> ApiResult<(Status, Json<Capsule>)> {
+    admin.0?;
+    let mut capsule = validate(capsule.into_inner(), state)?;
+    if state
+        .registry
+        .snapshot()
+        .get_by_name(&capsule.name)
+        .is_some()
+    {
+        return Err(api_error(
+            Status::Conflict,
+            format!("there already is a capsule named `{}`", capsule.name),
+        ));
+    }
+    let path = config
+        .manifests_dir
+        .join(format!("{}.toml", slug::slugify(&capsule.name)));
+    if path.exists() {
+        return Err(api_error(
+            Status::Conflict,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    capsule.source = Some(path.clone());
+    state
+        .registry
+        .add(capsule.clone())
+        .map_err(|error| api_error(Status::Conflict, error))?;
+    if let Err(error) = write_manifest(&capsule, &path) {
+        state.registry.remove(&capsule.uri);
+        return Err(error);
+    }
+    Ok((Status::Created, Json(capsule)))
+}
+
+/// Replaces the capsule named `name`, possibly moving it to another URI, and rewrites its manifest.
+#[put("/capsules/<name>", data = "<capsule>")]
+pub(super) fn update_capsule(
+    name: &str,
+    capsule: Json<Capsule>,
+    admin: Admin,
+    config: &State<AdminConfig>,
+    state: &State<AppState>,
+) -> ApiResult<Json<Capsule>> {
+    admin.0?;
+    let mut capsule = validate(capsule.into_inner(), state)?;
+    let registry = state.registry.snapshot();
+    let previous = registry
+        .get_by_name(name)
+        .cloned()
+        .ok_or_else(|| no_capsule(name))?;
+    if capsule.uri != previous.uri && registry.get(&capsule.uri).is_some() {
+        return Err(api_error(
+            Status::Conflict,
+            format!("there already is a capsule at {}", capsule.uri),
+        ));
+    }
+    let path = previous.source.clone().unwrap_or_else(|| {
+        config
+            .manifests_dir
+            .join(format!("{}.toml", slug::slugify(&capsule.name)))
+    });
+    capsule.source = Some(path.clone());
+
+    // The manifest goes first: a capsule served without one would vanish on the next reload
+    let backup = std::fs::read(&path).ok();
+    write_manifest(&capsule, &path)?;
+    if let Err(error) = state.registry.update(name, capsule.clone()) {
+        match backup {
+            Some(manifest) => {
+                let _ = write_atomically(&path, &manifest);
+            }
+            None => {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+        return Err(api_error(Status::Conflict, error));
+    }
+    Ok(Json(capsule))
+}
+
+/// Deletes the capsule named `name` and its manifest.
+#[delete("/capsules/<name>")]
+pub(super) fn delete_capsule(
+    name: &str,
+    admin: Admin,
+    state: &State<AppState>,
+) -> ApiResult<Status> {
+    admin.0?;
+    let snapshot = state.registry.snapshot();
+    let capsule = snapshot.get_by_name(name).ok_or_else(|| no_capsule(name))?;
+    // The manifest goes first: when it cannot be deleted, the capsule keeps being served
+    if let Some(path) = &capsule.source
+        && path.exists()
+    {
+        std::fs::remove_file(path).map_err(|error| {
+            api_error(
+                Status::InternalServerError,
+                format!("cannot delete {}: {error}", path.display()),
+            )
+        })?;
+    }
+    state
+        .registry
+        .remove(&capsule.uri)
+        .ok_or_else(|| no_capsule(name))?;
+    Ok(Status::NoContent)
+}
+
+fn no_capsule(name: &str) -> ApiError {
+    api_error(Status::NotFound, format!("no capsule is named `{name}`"))
+}
+
+/// Checks a capsule sent to the API before it is served.
+fn validate(mut capsule: Capsule, state: &AppState) -> ApiResult<Capsule> {
+    let invalid = |message: String| Err(api_error(Status::UnprocessableEntity, message));
+    if capsule.name.trim().is_empty() {
+        return invalid("the capsule `name` is empty".to_string());
+    }
+    if let Err(message) = check_uri(&capsule.uri) {
+        return invalid(message);
+    }
+    if !state.normalization.reaches(&capsule.uri) {
+        return invalid(format!(
+            "URLs are lowercased, so the capsule `uri` cannot hold uppercase letters: {}",
+            capsule.uri
+        ));
+    }
+    if capsule.redirect.is_none() && !state.engine.templates().contains(&capsule.template) {
+        return invalid(format!("template `{}` does not exist", capsule.template));
+    }
+    // The registry computes the alternates of translated capsules
+    if !capsule.translations.is_empty() {
+        capsule.alternates.clear();
+    }
+    Ok(capsule)
+}
+
+/// Checks that `uri` is a path the capsule can be served and exported at: it starts with `/` and,
+/// the root `/` excepted, has no empty, `.` or `..` segment.
+fn check_uri(uri: &str) -> Result<(), String> {
+    let Some(path) = uri.strip_prefix('/') else {
+        return Err(format!("the capsule `uri` must start with `/`: {uri}"));
+    };
+    if !path.is_empty()
+        && path
+            .split('/')
+            .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        return Err(format!(
+            "the capsule `uri` cannot hold empty, `.` or `..` segments: {uri}"
+        ));
+    }
+    Ok(())
+}
+
+/// Writes a capsule manifest (see [`Capsule::write_manifest`]).
+fn write_manifest(capsule: &Capsule, path: &Path) -> ApiResult<()> {
+    let manifest = capsule
+        .to_manifest(path)
+        .map_err(|error| api_error(Status::UnprocessableEntity, format!("{error:#}")))?;
+    let failed = |error: std::io::Error| {
+        api_error(
+            Status::InternalServerError,
+            format!("cannot write {}: {error}", path.display()),
+        )
+    };
+    write_atomically(path, manifest.as_bytes()).map_err(failed)
+}
+
+/// Writes `contents` to a temporary file next to `path`, then renames it over `path`, so that
+/// `path` is never left half written.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temporary);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_uri_rejects_traversal_and_empty_segments() {
+        for uri in ["/", "/about", "/blog/<slug>", "/files/<path..>"] {
+            assert_eq!(check_uri(uri), Ok(()), "{uri}");
+        }
+        for uri in [
+            "about", "", "/about/", "//about", "/a//b", "/../etc", "/a/./b", "/a/..",
+        ] {
+            assert!(check_uri(uri).is_err(), "{uri}");
+        }
+    }
+
+    #[test]
+    fn write_atomically_replaces_the_file() {
+        let dir = std::env::temp_dir().join(format!("jigi-admin-{}", std::process::id()));
+        let path = dir.join("capsules/about.toml");
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        let names: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["about.toml"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
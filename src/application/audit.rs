@@ -0,0 +1,839 @@
+use super::redirects::{RedirectRule, load_redirects};
+use super::{CapsuleRegistry, Method};
+use crate::seo::Seo;
+use crate::seo::validate::Severity;
+use anyhow::Context;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
+
+/// `<a href>`, with the value double-quoted, single-quoted or bare.
+const LINK: &str = r#"(?is)<a\b[^>]*?\shref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#;
+/// The target of a `<meta http-equiv="refresh">`, as written by the static export.
+const META_REFRESH: &str = r#"(?is)<meta\b[^>]*http-equiv\s*=\s*["']?refresh["']?[^>]*content\s*=\s*["']?\d+\s*;\s*url=([^"'>]+)"#;
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 10;
+
+/// What [`audit_site`] crawls.
+///
+/// # Variants
+///
+/// - `Site`: A running site, given by its origin, e.g. `http://localhost:8000`.
+/// - `Directory`: A static export (see [`export_site`](super::export::export_site)). Redirects
+///   are read from the `_redirects` file with [`load_redirects`], patterns included.
+///
+/// In both, meta refresh pages count as redirects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditSource {
+    Site(String),
+    Directory(PathBuf),
+}
+
+impl FromStr for AuditSource {
+    type Err = String;
+
+    /// Takes `http(s)://` URLs as sites and anything else as a directory.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("empty audit source".to_string()),
+            s if s.starts_with("http://") || s.starts_with("https://") => {
+                Ok(AuditSource::Site(s.trim_end_matches('/').to_string()))
+            }
+            s => Ok(AuditSource::Directory(PathBuf::from(s))),
+        }
+    }
+}
+
+/// How [`audit_site`] crawls.
+///
+/// # Fields
+///
+/// * `base_url` - The public origin of the site, e.g. `https://example.org`. Links and canonical
+///   URLs under it are treated as internal, so a local copy can be audited.
+/// * `max_pages` - The number of URIs fetched before the crawl stops.
+#[derive(Debug, Clone)]
+pub struct AuditOptions {
+    pub base_url: Option<String>,
+    pub max_pages: usize,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            max_pages: 5000,
+        }
+    }
+}
+
+/// The kind of an [`AuditFinding`].
+///
+/// # Variants
+///
+/// - `BrokenLink`: A linked URI answers with an error, or cannot be fetched.
+/// - `RedirectChain`: A linked URI goes through several redirects, or a redirect loop.
+/// - `DuplicateTitle`: Several pages share a title.
+/// - `DuplicateDescription`: Several pages share a description.
+/// - `MissingCanonical`: An indexable page has no canonical URL.
+/// - `CanonicalConflict`: The canonical URL of a page is broken, redirects, points to a page
+///   with another canonical, or disagrees with `og:url`.
+/// - `OrphanCapsule`: A capsule no crawled page links to.
+/// - `Seo`: An issue found by [`Seo::validate`].
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    BrokenLink,
+    RedirectChain,
+    DuplicateTitle,
+    DuplicateDescription,
+    MissingCanonical,
+    CanonicalConflict,
+    OrphanCapsule,
+    Seo,
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditKind::BrokenLink => "broken link",
+            AuditKind::RedirectChain => "redirect chain",
+            AuditKind::DuplicateTitle => "duplicate title",
+            AuditKind::DuplicateDescription => "duplicate description",
+            AuditKind::MissingCanonical => "missing canonical",
+            AuditKind::CanonicalConflict => "canonical conflict",
+            AuditKind::OrphanCapsule => "orphan capsule",
+            AuditKind::Seo => "seo",
+        })
+    }
+}
+
+/// A problem found by [`audit_site`].
+///
+/// # Fields
+///
+/// * `severity` - See [`Severity`].
+/// * `kind` - See [`AuditKind`].
+/// * `uri` - The URI at fault: the page, or the target of a broken link.
+/// * `message` - What is wrong, with the pages linking to `uri` when it helps.
+#[derive(Debug, Clone, Serialize, Tabled, PartialEq, Eq)]
+pub struct AuditFinding {
+    pub severity: Severity,
+    pub kind: AuditKind,
+    pub uri: String,
+    pub message: String,
+}
+
+/// The outcome of [`audit_site`].
+///
+/// # Fields
+///
+/// * `pages` - The number of HTML pages crawled.
+/// * `findings` - The problems found, errors first.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    pub pages: usize,
+    pub findings: Vec<AuditFinding>,
+}
+
+impl AuditReport {
+    /// The number of findings of `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// Returns `true` when a finding is at least as serious as `threshold`.
+    pub fn fails(&self, threshold: Severity) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.severity >= threshold)
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.findings.is_empty() {
+            writeln!(f, "{}", render_audit_table(&self.findings))?;
+        }
+        write!(
+            f,
+            "audited {} pages: {} error(s), {} warning(s)",
+            self.pages,
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+/// Renders findings as a table, like the route table.
+pub fn render_audit_table(findings: &[AuditFinding]) -> String {
+    Table::new(findings).with(Style::rounded()).to_string()
+}
+
+/// What a URI answered.
+struct Response {
+    status: u16,
+    location: Option<String>,
+    html: Option<String>,
+}
+
+/// Where a URI led once its redirects are followed.
+///
+/// # Fields
+///
+/// * `chain` - The URIs fetched, the requested one first.
+/// * `status` - The status of the last one, `None` when it could not be fetched.
+/// * `error` - Why the last URI could not be fetched, or why the redirects were not followed.
+/// * `looped` - Whether the redirects loop, or are too many to follow.
+/// * `html` - The page, when the last URI is an HTML page.
+struct Visit {
+    chain: Vec<String>,
+    status: Option<u16>,
+    error: Option<String>,
+    looped: bool,
+    html: Option<String>,
+}
+
+impl Visit {
+    fn target(&self) -> &str {
+        self.chain.last().map_or("", String::as_str)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.error.is_some() || self.status.is_none_or(|status| status >= 400)
+    }
+}
+
+enum Fetcher {
+    Site {
+        // Its own runtime, so that no Tokio runtime is needed to audit
+        runtime: tokio::runtime::Runtime,
+        client: reqwest::Client,
+        origin: String,
+    },
+    Directory {
+        root: PathBuf,
+        redirects: Vec<RedirectRule>,
+    },
+}
+
+impl Fetcher {
+    fn new(source: &AuditSource) -> anyhow::Result<Self> {
+        Ok(match source {
+            AuditSource::Site(origin) => Fetcher::Site {
+                runtime: tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?,
+                client: reqwest::Client::builder()
+                    .redirect(reqwest::redirect::Policy::none())
+                    .timeout(std::time::Duration::from_secs(10))
+                    .user_agent("jigi-audit")
+                    .build()?,
+                origin: origin.clone(),
+            },
+            AuditSource::Directory(root) => {
+                if !root.is_dir() {
+                    anyhow::bail!("{} is not a directory", root.display());
+                }
+                let file = root.join("_redirects");
+                let redirects = if file.is_file() {
+                    load_redirects(&file)?
+                } else {
+                    Vec::new()
+                };
+                Fetcher::Directory {
+                    root: root.clone(),
+                    redirects,
+                }
+            }
+        })
+    }
+
+    fn fetch(&self, path: &str) -> anyhow::Result<Response> {
+        match self {
+            Fetcher::Site {
+                runtime,
+                client,
+                origin,
+            } => runtime.block_on(async {
+                let response = client
+                    .get(format!("{origin}{path}"))
+                    .send()
+                    .await
+                    .with_context(|| format!("cannot fetch {path}"))?;
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                };
+                let status = response.status().as_u16();
+                let location = header(reqwest::header::LOCATION);
+                let is_html = header(reqwest::header::CONTENT_TYPE)
+                    .is_some_and(|content_type| content_type.contains("text/html"));
+                let html = match is_html && (200..300).contains(&status) {
+                    true => Some(response.text().await?),
+                    false => None,
+                };
+                Ok(Response {
+                    status,
+                    location,
+                    html,
+                })
+            }),
+            Fetcher::Directory { root, redirects } => {
+                // Exact rules first, as the server does
+                let redirect = redirects
+                    .iter()
+                    .find(|rule| !rule.is_pattern() && rule.from == path)
+                    .cloned()
+                    .or_else(|| redirects.iter().find_map(|rule| rule.apply(path)));
+                if let Some(rule) = redirect {
+                    return Ok(redirect_response(rule));
+                }
+                let Some(file) = export_file(root, path) else {
+                    return Ok(Response {
+                        status: 404,
+                        location: None,
+                        html: None,
+                    });
+                };
+                let is_html = file
+                    .extension()
+                    .is_some_and(|extension| extension == "html" || extension == "htm");
+                if !is_html {
+                    return Ok(Response {
+                        status: 200,
+                        location: None,
+                        html: None,
+                    });
+                }
+                let html = std::fs::read_to_string(&file)
+                    .with_context(|| format!("cannot read {}", file.display()))?;
+                Ok(Response {
+                    status: 200,
+                    location: None,
+                    html: Some(html),
+                })
+            }
+        }
+    }
+}
+
+fn redirect_response(rule: RedirectRule) -> Response {
+    Response {
+        status: rule.status.code(),
+        location: Some(rule.to),
+        html: None,
+    }
+}
+
+/// The file of a static export serving `path`: the file itself, `{path}/index.html` or
+/// `{path}.html`.
+fn export_file(root: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let path = root.join(relative);
+    [
+        path.clone(),
+        path.join("index.html"),
+        path.with_extension("html"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+}
+
+/// A crawled HTML page.
+struct Page {
+    seo: Seo,
+    canonical: Option<String>,
+}
+
+/// Crawls a site from `/`, following its internal links, and reports what search engines
+/// would stumble on.
+///
+/// # Behavior
+///
+/// 1. Fetches `/`, then every internal `<a href>` of every HTML page, redirects included, up to
+///    [`AuditOptions::max_pages`] URIs. Links to other sites are not fetched.
+/// 2. Reports links to URIs answering an error (`BrokenLink`), and going through more than one
+///    redirect or a loop (`RedirectChain`).
+/// 3. Reads every page with [`Seo::from_html`], and reports titles and descriptions shared by
+///    several indexable pages, indexable pages without a canonical URL, canonical conflicts and
+///    the issues found by [`Seo::validate`].
+/// 4. Given the `registry` of the site, reports the public `GET` capsules nothing links to
+///    (`OrphanCapsule`). Capsules with parameters and redirects are left out.
+///
+/// # Errors
+///
+/// Fails when the directory does not exist or the HTTP client cannot be built. URIs that cannot
+/// be fetched are reported as broken links.
+///
+/// # Panics
+///
+/// Crawling a running site blocks on its own Tokio runtime, which panics inside an async task.
+/// From async code, call it through `tokio::task::spawn_blocking`.
+pub fn audit_site(
+    source: &AuditSource,
+    registry: Option<&CapsuleRegistry>,
+    options: &AuditOptions,
+) -> anyhow::Result<AuditReport> {
+    let fetcher = Fetcher::new(source)?;
+    let origins: Vec<&str> = match source {
+        AuditSource::Site(origin) => vec![origin.as_str()],
+        AuditSource::Directory(_) => vec![],
+    }
+    .into_iter()
+    .chain(
+        options
+            .base_url
+            .as_deref()
+            .map(|url| url.trim_end_matches('/')),
+    )
+    .collect();
+    let link = Regex::new(LINK)?;
+    let meta_refresh = Regex::new(META_REFRESH)?;
+
+    let mut visits: BTreeMap<String, Visit> = BTreeMap::new();
+    let mut pages: BTreeMap<String, Page> = BTreeMap::new();
+    let mut referrers: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut queue = VecDeque::from(["/".to_string()]);
+    let mut canonicals = Vec::new();
+
+    while let Some(path) = queue.pop_front().or_else(|| canonicals.pop()) {
+        if visits.contains_key(&path) || visits.len() >= options.max_pages {
+            continue;
+        }
+        let visit = follow(&fetcher, &path, &origins, &meta_refresh);
+        let target = visit.target().to_string();
+        if let Some(html) = &visit.html
+            && !pages.contains_key(normalized(&target))
+        {
+            for href in link.captures_iter(html) {
+                let href = href
+                    .get(1)
+                    .or(href.get(2))
+                    .or(href.get(3))
+                    .map_or("", |h| h.as_str());
+                if let Some(linked) = resolve(&target, &href.replace("&amp;", "&"), &origins) {
+                    referrers
+                        .entry(linked.clone())
+                        .or_default()
+                        .insert(target.clone());
+                    queue.push_back(linked);
+                }
+            }
+            let seo = Seo::from_html(html);
+            let canonical = seo
+                .canonical_url
+                .as_deref()
+                .and_then(|url| resolve(&target, url, &origins));
+            canonicals.extend(canonical.clone());
+            // `/blog` and `/blog/` are the same page
+            pages.insert(normalized(&target).to_string(), Page { seo, canonical });
+        }
+        visits.insert(path, visit);
+    }
+
+    let mut findings = Vec::new();
+    let mut finding = |severity, kind, uri: &str, message: String| {
+        findings.push(AuditFinding {
+            severity,
+            kind,
+            uri: uri.to_string(),
+            message,
+        })
+    };
+    let linked_from = |uri: &str| match referrers.get(uri) {
+        Some(from) if !from.is_empty() => format!(
+            ", linked from {}",
+            from.iter().cloned().collect::<Vec<_>>().join(", ")
+        ),
+        _ => String::new(),
+    };
+
+    for (uri, visit) in &visits {
+        if !referrers.contains_key(uri) && uri != "/" {
+            continue;
+        }
+        if visit.looped {
+            finding(
+                Severity::Error,
+                AuditKind::RedirectChain,
+                uri,
+                format!(
+                    "{}: {}{}",
+                    visit.error.as_deref().unwrap_or_default(),
+                    visit.chain.join(" → "),
+                    linked_from(uri)
+                ),
+            );
+        } else if visit.is_broken() {
+            let why = match (&visit.error, visit.status) {
+                (Some(error), _) => error.clone(),
+                (None, Some(status)) => format!("answers {status}"),
+                (None, None) => "cannot be fetched".to_string(),
+            };
+            finding(
+                Severity::Error,
+                AuditKind::BrokenLink,
+                uri,
+                format!("{why}{}", linked_from(uri)),
+            );
+        } else if visit.chain.len() > 2 {
+            finding(
+                Severity::Warning,
+                AuditKind::RedirectChain,
+                uri,
+                format!(
+                    "{} redirects: {}{}",
+                    visit.chain.len() - 1,
+                    visit.chain.join(" → "),
+                    linked_from(uri)
+                ),
+            );
+        }
+    }
+
+    let indexable: Vec<(&String, &Page)> =
+        pages.iter().filter(|(_, page)| !page.seo.noindex).collect();
+    for kind in [AuditKind::DuplicateTitle, AuditKind::DuplicateDescription] {
+        let mut shared: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (uri, page) in &indexable {
+            let text = match kind {
+                AuditKind::DuplicateTitle => page.seo.title.trim(),
+                _ => page.seo.description.trim(),
+            };
+            if !text.is_empty() {
+                shared.entry(text).or_default().push(uri);
+            }
+        }
+        for uris in shared.values().filter(|uris| uris.len() > 1) {
+            for uri in uris {
+                let others: Vec<&str> = uris.iter().filter(|o| *o != uri).copied().collect();
+                finding(
+                    Severity::Warning,
+                    kind,
+                    uri,
+                    format!("also on {}", others.join(", ")),
+                );
+            }
+        }
+    }
+
+    for (uri, page) in &pages {
+        let seo = &page.seo;
+        match (&seo.canonical_url, &page.canonical) {
+            (None, _) if !seo.noindex => finding(
+                Severity::Warning,
+                AuditKind::MissingCanonical,
+                uri,
+                "no canonical URL".to_string(),
+            ),
+            (None, _) => {}
+            (Some(url), None) => {
+                if !origins.is_empty()
+                    && (url.starts_with("http://") || url.starts_with("https://"))
+                {
+                    finding(
+                        Severity::Warning,
+                        AuditKind::CanonicalConflict,
+                        uri,
+                        format!("canonical {url} is not under the audited origin or base URL"),
+                    );
+                }
+            }
+            (Some(url), Some(canonical)) => {
+                if let Some(visit) = visits.get(canonical) {
+                    if visit.is_broken() {
+                        finding(
+                            Severity::Error,
+                            AuditKind::CanonicalConflict,
+                            uri,
+                            format!("canonical {url} is broken"),
+                        );
+                    } else if visit.chain.len() > 1 {
+                        finding(
+                            Severity::Error,
+                            AuditKind::CanonicalConflict,
+                            uri,
+                            format!("canonical {url} redirects to {}", visit.target()),
+                        );
+                    } else if let Some(other) = pages.get(normalized(canonical))
+                        && let Some(next) = &other.canonical
+                        && normalized(next) != normalized(canonical)
+                    {
+                        finding(
+                            Severity::Warning,
+                            AuditKind::CanonicalConflict,
+                            uri,
+                            format!("canonical {url} has its own canonical, {next}"),
+                        );
+                    }
+                }
+                let og_url = seo
+                    .og_url
+                    .as_deref()
+                    .and_then(|og_url| resolve(uri, og_url, &origins));
+                if let Some(og_url) =
+                    og_url.filter(|og_url| normalized(og_url) != normalized(canonical))
+                {
+                    finding(
+                        Severity::Warning,
+                        AuditKind::CanonicalConflict,
+                        uri,
+                        format!("og:url {og_url} differs from canonical {url}"),
+                    );
+                }
+            }
+        }
+        for issue in seo.validate() {
+            finding(
+                issue.severity,
+                AuditKind::Seo,
+                uri,
+                format!("seo.{}: {}", issue.field, issue.message),
+            );
+        }
+    }
+
+    if let Some(registry) = registry {
+        // Linked URIs and where their redirects lead
+        let mut reached = BTreeSet::from(["/"]);
+        for (uri, visit) in &visits {
+            if uri == "/" || referrers.contains_key(uri) {
+                reached.extend(visit.chain.iter().map(|uri| normalized(uri)));
+            }
+        }
+        let mut orphans: Vec<&str> = registry
+            .all()
+            .map(|(_, capsule)| capsule)
+            .filter(|c| c.method == Method::GET && c.redirect.is_none() && c.access.is_public())
+            .filter(|c| !c.uri.contains('<') && !reached.contains(normalized(&c.uri)))
+            .map(|c| c.uri.as_str())
+            .collect();
+        orphans.sort();
+        for uri in orphans {
+            finding(
+                Severity::Warning,
+                AuditKind::OrphanCapsule,
+                uri,
+                "no crawled page links to it".to_string(),
+            );
+        }
+    }
+
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| (a.kind, &a.uri, &a.message).cmp(&(b.kind, &b.uri, &b.message)))
+    });
+    Ok(AuditReport {
+        pages: pages.len(),
+        findings,
+    })
+}
+
+/// Fetches `path`, following its internal redirects, meta refresh pages included.
+fn follow(fetcher: &Fetcher, path: &str, origins: &[&str], meta_refresh: &Regex) -> Visit {
+    let mut visit = Visit {
+        chain: vec![path.to_string()],
+        status: None,
+        error: None,
+        looped: false,
+        html: None,
+    };
+    loop {
+        let current = visit.target().to_string();
+        let response = match fetcher.fetch(&current) {
+            Ok(response) => response,
+            Err(error) => {
+                visit.error = Some(format!("{error:#}"));
+                return visit;
+            }
+        };
+        visit.status = Some(response.status);
+        let next = match (&response.location, response.status) {
+            (Some(location), 300..=399) => resolve(&current, location, origins),
+            _ => response
+                .html
+                .as_deref()
+                .and_then(|html| meta_refresh.captures(html))
+                .and_then(|refresh| resolve(&current, &refresh[1].replace("&amp;", "&"), origins)),
+        };
+        let Some(next) = next else {
+            visit.html = response.html;
+            return visit;
+        };
+        if visit.chain.contains(&next) {
+            visit.chain.push(next);
+            visit.error = Some("redirect loop".to_string());
+            visit.looped = true;
+            return visit;
+        }
+        visit.chain.push(next);
+        if visit.chain.len() > MAX_REDIRECTS {
+            visit.error = Some(format!("more than {MAX_REDIRECTS} redirects"));
+            visit.looped = true;
+            return visit;
+        }
+    }
+}
+
+/// The internal path `href` points to from the page at `base`, without query nor fragment.
+///
+/// # Returns
+///
+/// `None` for links to other sites, other schemes (`mailto:`…) and anchors of the same page.
+fn resolve(base: &str, href: &str, origins: &[&str]) -> Option<String> {
+    let href = href.trim();
+    let href = href.split(['#', '?']).next().unwrap_or_default();
+    if href.is_empty() {
+        return None;
+    }
+    let path = if let Some(rest) = origins.iter().find_map(|origin| href.strip_prefix(origin)) {
+        match rest {
+            "" => "/".to_string(),
+            rest if rest.starts_with('/') => rest.to_string(),
+            _ => return None,
+        }
+    } else if href.starts_with("//") {
+        return None;
+    } else if href.starts_with('/') {
+        href.to_string()
+    } else if href
+        .split('/')
+        .next()
+        .is_some_and(|first| first.contains(':'))
+    {
+        return None;
+    } else {
+        let directory = &base[..base.rfind('/').map_or(0, |index| index + 1)];
+        format!("{directory}{href}")
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let trailing = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    let mut path = format!("/{}", segments.join("/"));
+    if trailing && !path.ends_with('/') {
+        path.push('/');
+    }
+    Some(path.replace("//", "/"))
+}
+
+/// `uri` without its trailing slash, to compare crawled URIs with capsule URIs.
+fn normalized(uri: &str) -> &str {
+    match uri.trim_end_matches('/') {
+        "" => "/",
+        uri => uri,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_keeps_internal_paths() {
+        let origins = ["https://example.com"];
+        let resolve = |base, href| resolve(base, href, &origins);
+        assert_eq!(resolve("/blog/post", "/about").as_deref(), Some("/about"));
+        assert_eq!(
+            resolve("/blog/post", "/a:b?x=1#top").as_deref(),
+            Some("/a:b")
+        );
+        assert_eq!(
+            resolve("/blog/post", "other").as_deref(),
+            Some("/blog/other")
+        );
+        assert_eq!(resolve("/blog/", "../about/").as_deref(), Some("/about/"));
+        assert_eq!(resolve("/", "tags/a:b").as_deref(), Some("/tags/a:b"));
+        assert_eq!(
+            resolve("/", "https://example.com/contact").as_deref(),
+            Some("/contact")
+        );
+        assert_eq!(resolve("/", "https://example.com").as_deref(), Some("/"));
+    }
+
+    #[test]
+    fn resolve_skips_other_sites_and_schemes() {
+        let origins = ["https://example.com"];
+        let resolve = |base, href| resolve(base, href, &origins);
+        for href in [
+            "https://other.org/",
+            "//cdn.example.com/app.js",
+            "mailto:team@example.com",
+            "tel:+33123456789",
+            "#section",
+            "https://example.com.evil.org/",
+        ] {
+            assert_eq!(resolve("/", href), None, "{href}");
+        }
+    }
+
+    #[test]
+    fn link_requires_an_href_attribute() {
+        let link = Regex::new(LINK).unwrap();
+        let hrefs: Vec<&str> = link
+            .captures_iter(
+                r#"<a data-href="/no">x</a><a class="c" href='/yes'>y</a><A  HREF=/bare>z</a>"#,
+            )
+            .map(|c| c.get(1).or(c.get(2)).or(c.get(3)).unwrap().as_str())
+            .collect();
+        assert_eq!(hrefs, ["/yes", "/bare"]);
+    }
+
+    #[test]
+    fn directory_fetcher_follows_the_redirects_file() {
+        let root = std::env::temp_dir().join(format!("jigi-audit-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("blog")).unwrap();
+        std::fs::write(root.join("blog/index.html"), "<p>Blog</p>").unwrap();
+        std::fs::write(
+            root.join("_redirects"),
+            "# moved pages\n/old /blog/ 302\n/posts/:slug /blog/:slug\n/posts/new /blog/\n",
+        )
+        .unwrap();
+        let source = AuditSource::Directory(root.clone());
+        let fetcher = Fetcher::new(&source).unwrap();
+
+        let response = fetcher.fetch("/old").unwrap();
+        assert_eq!(response.status, 302);
+        assert_eq!(response.location.as_deref(), Some("/blog/"));
+        let response = fetcher.fetch("/posts/hello").unwrap();
+        assert_eq!(response.status, 301);
+        assert_eq!(response.location.as_deref(), Some("/blog/hello"));
+        // The exact rule wins over the earlier pattern
+        let response = fetcher.fetch("/posts/new").unwrap();
+        assert_eq!(response.location.as_deref(), Some("/blog/"));
+        let response = fetcher.fetch("/blog").unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.html.as_deref(), Some("<p>Blog</p>"));
+        assert_eq!(fetcher.fetch("/missing").unwrap().status, 404);
+
+        std::fs::write(root.join("_redirects"), "/old /new 200\n").unwrap();
+        assert!(Fetcher::new(&source).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
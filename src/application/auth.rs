@@ -0,0 +1,417 @@
+use super::csrf::constant_time_eq;
+use super::session::Session;
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use base64::Engine;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::OnceCell;
+
+/// Session key under which the username of the logged-in user is stored.
+pub const SESSION_USER_KEY: &str = "user";
+
+/// Access requirement declared by a capsule.
+///
+/// Serialized as a tagged table, so manifests can write `access = { type = "login", roles = ["editor"] }`.
+///
+/// # Variants
+///
+/// - `Public`: Anyone can reach the capsule (the default).
+/// - `Basic`: HTTP Basic authentication against the users of [`AuthConfig`]. Browsers show their
+///   own credentials prompt. Handy for staging previews.
+/// - `Login`: The visitor must be logged in through the built-in login page.
+///
+/// For `Basic` and `Login`, a non-empty `roles` list restricts access to users holding at least
+/// one of those roles.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Access {
+    #[default]
+    Public,
+    Basic {
+        #[serde(default)]
+        roles: Vec<String>,
+    },
+    Login {
+        #[serde(default)]
+        roles: Vec<String>,
+    },
+}
+
+/// Outcome of checking an [`Access`] requirement against an [`Identity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// The visitor may see the capsule.
+    Granted,
+    /// HTTP Basic credentials are missing or wrong: answer `401` with a `WWW-Authenticate` challenge.
+    Challenge,
+    /// The visitor is not logged in: send them to the login page.
+    LoginRequired,
+    /// The visitor is known but lacks the required role.
+    Forbidden,
+}
+
+impl Access {
+    /// Returns `true` for [`Access::Public`].
+    pub fn is_public(&self) -> bool {
+        matches!(self, Access::Public)
+    }
+
+    /// Decides whether `identity` satisfies this requirement.
+    ///
+    /// HTTP Basic credentials are only verified for [`Access::Basic`] (see [`Identity::basic`]).
+    pub async fn check(&self, identity: &Identity, auth: &AuthConfig) -> AccessDecision {
+        let (user, roles, missing) = match self {
+            Access::Public => return AccessDecision::Granted,
+            Access::Basic { roles } => {
+                (identity.basic(auth).await, roles, AccessDecision::Challenge)
+            }
+            Access::Login { roles } => (
+                identity.session.as_ref(),
+                roles,
+                AccessDecision::LoginRequired,
+            ),
+        };
+        match user {
+            None => missing,
+            Some(user) if roles.is_empty() || roles.iter().any(|r| user.has_role(r)) => {
+                AccessDecision::Granted
+            }
+            Some(_) => AccessDecision::Forbidden,
+        }
+    }
+}
+
+/// A user allowed to authenticate, as declared in the configuration.
+///
+/// `password_hash` is an Argon2 hash in PHC format (`$argon2id$v=19$…`), salt included, as
+/// produced by [`AuthConfig::hash_password`]. Plain passwords are never stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserCredentials {
+    pub username: String,
+    pub password_hash: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// Users and built-in pages of the access control layer.
+///
+/// ```toml
+/// [auth]
+/// realm = "Staging"
+///
+/// [[auth.users]]
+/// username = "reviewer"
+/// password_hash = "$argon2id$v=19$m=19456,t=2,p=1$…"
+/// roles = ["staff"]
+/// ```
+///
+/// # Fields
+///
+/// * `users` - The accounts accepted by both HTTP Basic and the login page.
+/// * `realm` - The realm announced in HTTP Basic challenges.
+/// * `login_uri` - Where the built-in login page is mounted.
+/// * `logout_uri` - Where the built-in logout endpoint is mounted.
+/// * `login_template` - The template rendered by the login page. It receives `next`, `error` and `csrf_token`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub users: Vec<UserCredentials>,
+    pub realm: String,
+    pub login_uri: String,
+    pub logout_uri: String,
+    pub login_template: String,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            users: Vec::new(),
+            realm: "jigi".into(),
+            login_uri: "/login".into(),
+            logout_uri: "/logout".into(),
+            login_template: "login".into(),
+        }
+    }
+}
+
+/// Verified when the username is unknown, so that answering takes as long as for a known user.
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| AuthConfig::hash_password("jigi-dummy-password").unwrap_or_default());
+
+impl AuthConfig {
+    /// Hashes `password` with Argon2id and a random salt, in the PHC format `password_hash`
+    /// is stored in.
+    ///
+    /// # Errors
+    ///
+    /// Fails when Argon2 rejects the password, e.g. when it is too long.
+    pub fn hash_password(password: &str) -> anyhow::Result<String> {
+        let salt = SaltString::generate(&mut rand::rngs::OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|error| anyhow::anyhow!("cannot hash the password: {error}"))
+    }
+
+    /// Returns the user matching `username` and `password`, if any.
+    ///
+    /// Every user is compared and a password is always verified, so the time taken does not
+    /// tell whether `username` exists. Verifying takes tens of milliseconds of CPU and some
+    /// memory: from async code, use [`AuthConfig::authenticate_async`].
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<AuthUser> {
+        let found = self.find(username);
+        let stored = found.map_or(DUMMY_HASH.as_str(), |u| u.password_hash.as_str());
+        found
+            .filter(|_| verify_hash(stored, password))
+            .map(AuthUser::from)
+    }
+
+    /// Like [`AuthConfig::authenticate`], verifying the password on the blocking thread pool.
+    pub async fn authenticate_async(&self, username: &str, password: &str) -> Option<AuthUser> {
+        let found = self.find(username);
+        let stored = found
+            .map_or(DUMMY_HASH.as_str(), |u| u.password_hash.as_str())
+            .to_string();
+        let password = password.to_string();
+        let verified = tokio::task::spawn_blocking(move || verify_hash(&stored, &password))
+            .await
+            .unwrap_or(false);
+        found.filter(|_| verified).map(AuthUser::from)
+    }
+
+    /// Returns the user named `username`, with the roles it has now.
+    pub fn user(&self, username: &str) -> Option<AuthUser> {
+        self.users
+            .iter()
+            .find(|user| user.username == username)
+            .map(AuthUser::from)
+    }
+
+    /// Finds the user named `username`, comparing every user in constant time.
+    fn find(&self, username: &str) -> Option<&UserCredentials> {
+        let mut found = None;
+        for user in &self.users {
+            if constant_time_eq(user.username.as_bytes(), username.as_bytes()) && found.is_none() {
+                found = Some(user);
+            }
+        }
+        found
+    }
+}
+
+/// Checks `password` against a PHC-format Argon2 hash.
+fn verify_hash(stored: &str, password: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// An authenticated visitor, exposed to templates as `user`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthUser {
+    pub username: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl AuthUser {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+impl From<&UserCredentials> for AuthUser {
+    fn from(user: &UserCredentials) -> Self {
+        Self {
+            username: user.username.clone(),
+            roles: user.roles.clone(),
+        }
+    }
+}
+
+/// Who the current visitor is, as far as the access control layer can tell.
+///
+/// `Identity` is a Rocket request guard that never fails, cached for the request. It reads the
+/// username stored in the [`Session`] by the login page and looks the user up in the managed
+/// [`AuthConfig`], so removed users are logged out and role changes apply at once. HTTP Basic
+/// credentials are only verified when asked, with [`Identity::basic`], as hashing them is costly.
+#[derive(Clone, Default)]
+pub struct Identity {
+    session: Option<AuthUser>,
+    credentials: Option<(String, String)>,
+    basic: Arc<OnceCell<Option<AuthUser>>>,
+}
+
+impl std::fmt::Debug for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Identity")
+            .field("session", &self.session)
+            .field("basic", &self.basic.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Identity {
+    /// Returns the authenticated user, preferring verified HTTP Basic credentials over the session.
+    pub fn user(&self) -> Option<&AuthUser> {
+        self.basic
+            .get()
+            .and_then(Option::as_ref)
+            .or(self.session.as_ref())
+    }
+
+    /// Returns the user of the HTTP Basic `Authorization` header, verifying the credentials
+    /// against `auth` on the first call of the request.
+    pub async fn basic(&self, auth: &AuthConfig) -> Option<&AuthUser> {
+        self.basic
+            .get_or_init(|| async {
+                let (username, password) = self.credentials.as_ref()?;
+                auth.authenticate_async(username, password).await
+            })
+            .await
+            .as_ref()
+    }
+
+    /// Returns `true` when the visitor sent valid HTTP Basic credentials with this request, as
+    /// verified by [`Identity::basic`].
+    ///
+    /// Such requests cannot be forged by another site, unlike those authenticated by the session cookie.
+    pub fn is_basic(&self) -> bool {
+        self.basic.get().is_some_and(Option::is_some)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Identity {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let identity = req
+            .local_cache_async(async {
+                let Some(config) = req.rocket().state::<AuthConfig>() else {
+                    return Identity::default();
+                };
+                let session = match req.guard::<Session<'r>>().await {
+                    Outcome::Success(session) => session
+                        .get(SESSION_USER_KEY)
+                        .and_then(|username| config.user(username.as_str()?)),
+                    _ => None,
+                };
+                let credentials = req
+                    .headers()
+                    .get_one("Authorization")
+                    .and_then(basic_credentials);
+                Identity {
+                    session,
+                    credentials,
+                    basic: Arc::default(),
+                }
+            })
+            .await;
+        Outcome::Success(identity.clone())
+    }
+}
+
+/// Decodes the username and password of an HTTP Basic `Authorization` header value.
+fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let encoded = header
+        .strip_prefix("Basic ")
+        .or_else(|| header.strip_prefix("basic "))?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+/// Returns `next` when it is a safe local path to redirect to after login, `/` otherwise.
+///
+/// Anything that could leave the site (`//host`, `https://…`) is rejected to avoid open redirects.
+pub fn safe_next(next: Option<&str>) -> String {
+    match next {
+        Some(next) if next.starts_with('/') && !next.starts_with("//") && !next.contains('\\') => {
+            next.to_string()
+        }
+        _ => "/".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            users: vec![UserCredentials {
+                username: "ada".into(),
+                password_hash: AuthConfig::hash_password("correct horse").unwrap(),
+                roles: vec!["staff".into()],
+            }],
+            ..AuthConfig::default()
+        }
+    }
+
+    #[test]
+    fn hash_password_is_salted_phc() {
+        let first = AuthConfig::hash_password("secret").unwrap();
+        let second = AuthConfig::hash_password("secret").unwrap();
+        assert!(first.starts_with("$argon2id$"));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn user_has_the_configured_roles() {
+        let config = config();
+        assert_eq!(config.user("ada").unwrap().roles, ["staff"]);
+        assert!(config.user("bob").is_none());
+    }
+
+    #[test]
+    fn basic_credentials_decodes_the_header() {
+        assert_eq!(
+            basic_credentials("Basic YWRhOmNvcnJlY3Q6aG9yc2U="),
+            Some(("ada".to_string(), "correct:horse".to_string()))
+        );
+        assert_eq!(basic_credentials("Bearer YWRhOnB3"), None);
+        assert_eq!(basic_credentials("Basic !!!"), None);
+    }
+
+    #[tokio::test]
+    async fn basic_is_only_verified_when_asked() {
+        let config = config();
+        let identity = Identity {
+            credentials: Some(("ada".into(), "correct horse".into())),
+            ..Identity::default()
+        };
+        assert!(identity.user().is_none());
+        let access = Access::Basic {
+            roles: vec!["staff".into()],
+        };
+        assert_eq!(
+            access.check(&identity, &config).await,
+            AccessDecision::Granted
+        );
+        assert!(identity.is_basic());
+        assert_eq!(identity.user().unwrap().username, "ada");
+
+        let login = Access::Login { roles: vec![] };
+        assert_eq!(
+            login.check(&Identity::default(), &config).await,
+            AccessDecision::LoginRequired
+        );
+    }
+
+    #[test]
+    fn authenticate_checks_username_and_password() {
+        let config = config();
+        let user = config.authenticate("ada", "correct horse").unwrap();
+        assert_eq!(user.roles, ["staff"]);
+        assert!(config.authenticate("ada", "wrong").is_none());
+        assert!(config.authenticate("bob", "correct horse").is_none());
+    }
+}
@@ -0,0 +1,363 @@
+use super::auth::Access;
+use super::search::SearchResults;
+use super::{CapsuleRegistry, Method, TemplateEngine, static_context};
+use crate::seo::SiteSeoDefaults;
+use crate::seo::validate::{SeoIssue, Severity};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A problem found by [`check_site`].
+///
+/// # Fields
+///
+/// * `template` - The template involved, if any.
+/// * `line` - The line of the template where the problem is, when it can be told.
+/// * `capsule` - The URI of the capsule involved, if any.
+/// * `message` - What went wrong, with the causes reported by the template engine.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CheckIssue {
+    pub template: Option<String>,
+    pub line: Option<usize>,
+    pub capsule: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for CheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.template, self.line) {
+            (Some(template), Some(line)) => write!(f, "{template}:{line}: ")?,
+            (Some(template), None) => write!(f, "{template}: ")?,
+            _ => {}
+        }
+        if let Some(capsule) = &self.capsule {
+            write!(f, "[{capsule}] ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// An [`SeoIssue`] of a capsule, found by [`lint_seo`].
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SeoLint {
+    /// The URI of the capsule.
+    pub capsule: String,
+    #[serde(flatten)]
+    pub issue: SeoIssue,
+}
+
+impl fmt::Display for SeoLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SeoIssue {
+            field,
+            severity,
+            message,
+        } = &self.issue;
+        write!(f, "{severity}: [{}] seo.{field}: {message}", self.capsule)
+    }
+}
+
+/// The outcome of [`check_site`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckReport {
+    /// The number of templates loaded.
+    pub templates: usize,
+    /// The number of capsules rendered.
+    pub capsules: usize,
+    pub issues: Vec<CheckIssue>,
+    /// The SEO problems of the pages (see [`lint_seo`]).
+    pub seo: Vec<SeoLint>,
+}
+
+impl CheckReport {
+    /// Returns `true` when no issue and no SEO error was found: the site is safe to deploy.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+            && !self
+                .seo
+                .iter()
+                .any(|lint| lint.issue.severity == Severity::Error)
+    }
+
+    /// Returns `true` when SEO warnings were found. CI can treat them as failures too.
+    pub fn has_warnings(&self) -> bool {
+        self.seo
+            .iter()
+            .any(|lint| lint.issue.severity == Severity::Warning)
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "error: {issue}")?;
+        }
+        for lint in &self.seo {
+            writeln!(f, "{lint}")?;
+        }
+        write!(
+            f,
+            "checked {} templates and {} capsules: {} issue(s), {} SEO issue(s)",
+            self.templates,
+            self.capsules,
+            self.issues.len(),
+            self.seo.len()
+        )
+    }
+}
+
+/// Checks that every page of a site can be rendered, before it is deployed.
+///
+/// # Behavior
+///
+/// 1. Loads all templates with [`TemplateEngine::load_all`]. Syntax errors are reported with their line.
+/// 2. Confirms the `404` template exists, as well as `403` and `401` when capsules may need them
+///    (CSRF-protected submissions, restricted access, HTTP Basic), and `login` when capsules require
+///    visitors to log in (the default [`AuthConfig::login_template`](super::auth::AuthConfig::login_template)).
+/// 3. Confirms the `template` of every capsule exists. Redirect capsules are skipped.
+/// 4. Renders every capsule with its data, as an anonymous visitor would see it (see
+///    [`static_context`]), collections with their first page and search pages without a query. Undefined variables, failing filters and the like are reported with
+///    the template name and, when the engine does not tell, the first line mentioning the culprit.
+/// 5. Lints the SEO metadata of every page with [`lint_seo`], completed with the `site` defaults
+///    when given.
+///
+/// # Returns
+///
+/// A [`CheckReport`] listing every issue found, not only the first one.
+pub fn check_site(
+    registry: &CapsuleRegistry,
+    engine: &dyn TemplateEngine,
+    site: Option<&SiteSeoDefaults>,
+) -> CheckReport {
+    let mut report = CheckReport::default();
+    if let Err(error) = engine.load_all() {
+        let message = chain(&error);
+        report.issues.push(CheckIssue {
+            template: quoted_template(&message),
+            line: parse_error_line(&message),
+            capsule: None,
+            message,
+        });
+        return report;
+    }
+    let templates = engine.templates();
+    report.templates = templates.len();
+    let exists = |name: &str| templates.iter().any(|t| t == name);
+
+    let mut capsules: Vec<_> = registry.all().map(|(_, capsule)| capsule).collect();
+    capsules.sort_by(|a, b| a.uri.cmp(&b.uri));
+
+    let mut required = vec!["404"];
+    if capsules
+        .iter()
+        .any(|c| !c.access.is_public() || (c.csrf && c.method != Method::GET))
+    {
+        required.push("403");
+    }
+    if capsules
+        .iter()
+        .any(|c| matches!(c.access, Access::Basic { .. }))
+    {
+        required.push("401");
+    }
+    // Rendered by the built-in login page, under its default name
+    if capsules
+        .iter()
+        .any(|c| matches!(c.access, Access::Login { .. }))
+    {
+        required.push("login");
+    }
+    for name in required {
+        if !exists(name) {
+            report.issues.push(CheckIssue {
+                template: Some(name.to_string()),
+                line: None,
+                capsule: None,
+                message: format!("the `{name}` template is missing"),
+            });
+        }
+    }
+
+    for capsule in capsules.into_iter().filter(|c| c.redirect.is_none()) {
+        let issue = |line, message| CheckIssue {
+            template: Some(capsule.template.clone()),
+            line,
+            capsule: Some(capsule.uri.clone()),
+            message,
+        };
+        if !exists(&capsule.template) {
+            report.issues.push(issue(
+                None,
+                format!("template `{}` does not exist", capsule.template),
+            ));
+            continue;
+        }
+        report.capsules += 1;
+        let mut ctx = static_context(capsule, engine);
+        if let Some(listing) = registry.collection_page(&capsule.uri, 1) {
+            listing.insert_into(&mut ctx);
+        }
+        if capsule.search.is_some() {
+            SearchResults::default().insert_into(&mut ctx);
+        }
+        if let Err(error) = engine.render(&capsule.template, &ctx) {
+            let message = chain(&error);
+            let line = culprit(&message).and_then(|culprit| {
+                let source =
+                    std::fs::read_to_string(engine.template_path(&capsule.template)?).ok()?;
+                source
+                    .lines()
+                    .position(|line| line.contains(&culprit))
+                    .map(|index| index + 1)
+            });
+            report.issues.push(issue(line, message));
+        }
+    }
+    report.seo = lint_seo(registry, site);
+    report
+}
+
+/// Checks the SEO metadata of every page of a site.
+///
+/// Every public `GET` capsule with a `seo` table is checked with [`Seo::validate`](crate::seo::Seo::validate).
+/// Titles shared by several pages are reported too, as warnings on each of them: titles should be unique.
+///
+/// # Arguments
+///
+/// * `registry` - The capsules of the site.
+/// * `site` - The site-wide defaults the pages are served with, if any. The metadata is checked
+///   as rendered, after [`Seo::with_defaults`](crate::seo::Seo::with_defaults): a page without
+///   an image is fine when the site has a default one, and titles are checked with the title
+///   template applied.
+///
+/// # Returns
+///
+/// The issues found, by capsule URI.
+pub fn lint_seo(registry: &CapsuleRegistry, site: Option<&SiteSeoDefaults>) -> Vec<SeoLint> {
+    let pages: Vec<_> = registry
+        .all()
+        .map(|(_, capsule)| capsule)
+        .filter(|c| c.method == Method::GET && c.redirect.is_none() && c.access.is_public())
+        .filter_map(|c| {
+            let seo = c.seo.clone()?;
+            let seo = match site {
+                Some(site) => seo.with_defaults(site),
+                None => seo,
+            };
+            Some((c.uri.as_str(), seo))
+        })
+        .collect();
+    let mut titles: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (uri, seo) in &pages {
+        if !seo.title.trim().is_empty() {
+            titles.entry(seo.title.trim()).or_default().push(uri);
+        }
+    }
+    let mut lints = Vec::new();
+    for (uri, seo) in &pages {
+        let issues = seo.validate();
+        let shared = titles
+            .get(seo.title.trim())
+            .filter(|uris| uris.len() > 1)
+            .map(|uris| SeoIssue {
+                field: "title",
+                severity: Severity::Warning,
+                message: format!(
+                    "also the title of {}",
+                    uris.iter()
+                        .filter(|other| *other != uri)
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        lints.extend(issues.into_iter().chain(shared).map(|issue| SeoLint {
+            capsule: uri.to_string(),
+            issue,
+        }));
+    }
+    lints
+}
+
+/// Joins an error and its causes, which is where template engines put the useful part.
+fn chain(error: &anyhow::Error) -> String {
+    error
+        .chain()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// The first `'name'` or `"path"` quoted in an error message, which Tera uses for templates.
+fn quoted_template(message: &str) -> Option<String> {
+    let re = Regex::new(r#"['"]([^'"]+)['"]"#).ok()?;
+    re.captures(message).map(|c| c[1].to_string())
+}
+
+/// The line of a syntax error, reported by Tera as ` --> line:column`.
+fn parse_error_line(message: &str) -> Option<usize> {
+    let re = Regex::new(r"--> (\d+):\d+").ok()?;
+    re.captures(message).and_then(|c| c[1].parse().ok())
+}
+
+/// The last `` `name` `` quoted in a render error: the innermost cause, usually the variable,
+/// filter or argument at fault.
+fn culprit(message: &str) -> Option<String> {
+    let re = Regex::new(r"`([^`]+)`").ok()?;
+    re.captures_iter(message).last().map(|c| c[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::Capsule;
+
+    /// An engine knowing a fixed list of templates, rendering each as its name.
+    struct Templates(Vec<&'static str>);
+
+    impl TemplateEngine for Templates {
+        fn load_all(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn render(&self, template: &str, _ctx: &serde_json::Value) -> anyhow::Result<String> {
+            Ok(template.to_string())
+        }
+        fn templates(&self) -> Vec<String> {
+            self.0.iter().map(|name| name.to_string()).collect()
+        }
+    }
+
+    fn missing(report: &CheckReport) -> Vec<&str> {
+        report
+            .issues
+            .iter()
+            .filter_map(|issue| issue.template.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn check_site_requires_the_templates_of_restricted_pages() {
+        let mut registry = CapsuleRegistry::default();
+        let mut member = Capsule::new("member", "Members", "/member", "page", Method::GET);
+        member.access = Access::Login { roles: vec![] };
+        registry.add(member);
+        let report = check_site(&registry, &Templates(vec!["page", "404"]), None);
+        assert_eq!(missing(&report), ["403", "login"]);
+        assert_eq!(report.capsules, 1);
+
+        let all = Templates(vec!["page", "404", "403", "login"]);
+        assert!(check_site(&registry, &all, None).issues.is_empty());
+    }
+
+    #[test]
+    fn check_site_reports_missing_capsule_templates() {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(Capsule::new("home", "Home", "/", "home", Method::GET));
+        let report = check_site(&registry, &Templates(vec!["404"]), None);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].capsule.as_deref(), Some("/"));
+        assert_eq!(report.issues[0].message, "template `home` does not exist");
+        assert_eq!(report.capsules, 0);
+    }
+}
@@ -0,0 +1,267 @@
+use super::Capsule;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What collection items are sorted by.
+///
+/// # Variants
+///
+/// - `Date`: The `date` of the item's data, else its `seo.updated`. Items without a date come last.
+/// - `Title`: The `title` of the item's data, else its `seo.title`, else its name.
+/// - `Uri`: The item's URI.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionSort {
+    #[default]
+    Date,
+    Title,
+    Uri,
+}
+
+/// The direction of a [`CollectionSort`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Turns a capsule into a listing of other capsules: a blog index, a tag page, upcoming events…
+///
+/// Items are the `GET` capsules of the registry matching every criterion given, in the same
+/// locale as the collection. Redirects, search pages, other collections and capsules restricted
+/// by their `access` are never listed.
+///
+/// ```toml
+/// name = "blog"
+/// uri = "/blog"
+/// template = "blog"
+///
+/// [collection]
+/// content_type = "article"
+/// filter = { tags = "rust" }
+/// sort = "date"
+/// per_page = 10
+/// ```
+///
+/// Page 1 is served at the capsule URI, the next ones at `/blog/page/2`, `/blog/page/3`…
+/// The template gets `items` (each with `name`, `description`, `uri`, `data`, `seo` and `locale`)
+/// and `pagination` (`page`, `pages`, `per_page`, `total`, `prev` and `next`).
+///
+/// # Fields
+///
+/// * `content_type` - Only capsules whose `seo.content_type` is this value.
+/// * `prefix` - Only capsules whose URI starts with this prefix.
+/// * `filter` - Only capsules whose `data` has these values. When the data value is an array
+///   (e.g. `tags`), it must contain the filter value.
+/// * `sort` - The [`CollectionSort`] key.
+/// * `order` - Ascending or descending. Defaults to newest first for dates, A to Z otherwise.
+/// * `per_page` - The number of items per page (10 by default).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Collection {
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub filter: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub sort: CollectionSort,
+    #[serde(default)]
+    pub order: Option<SortOrder>,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+fn default_per_page() -> usize {
+    10
+}
+
+impl Default for Collection {
+    fn default() -> Self {
+        Self {
+            content_type: None,
+            prefix: None,
+            filter: BTreeMap::new(),
+            sort: CollectionSort::default(),
+            order: None,
+            per_page: default_per_page(),
+        }
+    }
+}
+
+/// One page of a collection, as exposed to templates.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CollectionPage {
+    pub items: Vec<Value>,
+    pub pagination: Pagination,
+}
+
+/// Where a [`CollectionPage`] stands among the pages of its collection.
+///
+/// `prev` and `next` are the URIs of the neighbour pages, `null` at both ends.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: usize,
+    pub pages: usize,
+    pub per_page: usize,
+    pub total: usize,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+}
+
+impl Collection {
+    /// Returns `true` when `item` belongs to the collection of `owner`.
+    pub fn selects(&self, owner: &Capsule, item: &Capsule) -> bool {
+        if item.uri == owner.uri
+            || item.collection.is_some()
+            || item.search.is_some()
+            || item.redirect.is_some()
+            || item.method != super::Method::GET
+            || !item.access.is_public()
+            || item.locale != owner.locale
+        {
+            return false;
+        }
+        if let Some(content_type) = &self.content_type
+            && item.seo.as_ref().and_then(|seo| seo.content_type.as_ref()) != Some(content_type)
+        {
+            return false;
+        }
+        if let Some(prefix) = &self.prefix
+            && !item.uri.starts_with(prefix.as_str())
+        {
+            return false;
+        }
+        self.filter
+            .iter()
+            .all(|(key, expected)| match item.data.get(key) {
+                Some(Value::Array(values)) => values.contains(expected),
+                Some(value) => value == expected,
+                None => false,
+            })
+    }
+
+    /// Sorts items in place according to `sort` and `order`.
+    pub fn sort(&self, items: &mut [&Capsule]) {
+        let text =
+            |c: &Capsule, key: &str| c.data.get(key).and_then(Value::as_str).map(str::to_string);
+        let key = |c: &Capsule| -> Option<String> {
+            match self.sort {
+                CollectionSort::Date => {
+                    text(c, "date").or_else(|| c.seo.as_ref().and_then(|seo| seo.updated.clone()))
+                }
+                CollectionSort::Title => text(c, "title")
+                    .or_else(|| {
+                        c.seo
+                            .as_ref()
+                            .map(|seo| seo.title.clone())
+                            .filter(|t| !t.is_empty())
+                    })
+                    .or_else(|| Some(c.name.clone()))
+                    .map(|t| t.to_lowercase()),
+                CollectionSort::Uri => Some(c.uri.clone()),
+            }
+        };
+        let order = self.order.unwrap_or(match self.sort {
+            CollectionSort::Date => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        });
+        items.sort_by(|a, b| {
+            let ordering = match (key(a), key(b)) {
+                (Some(a), Some(b)) if order == SortOrder::Asc => a.cmp(&b),
+                (Some(a), Some(b)) => b.cmp(&a),
+                // Undated or untitled items last, whatever the order
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            };
+            ordering.then_with(|| a.uri.cmp(&b.uri))
+        });
+    }
+
+    /// The number of pages needed for `total` items. An empty collection still has one page.
+    pub fn pages(&self, total: usize) -> usize {
+        total.div_ceil(self.per_page.max(1)).max(1)
+    }
+}
+
+/// The URI of page `page` of the collection at `uri`: page 1 is `uri` itself.
+pub fn page_uri(uri: &str, page: usize) -> String {
+    match (page, uri.trim_end_matches('/')) {
+        (1, _) => uri.to_string(),
+        (_, base) => format!("{base}/page/{page}"),
+    }
+}
+
+/// Splits a `…/page/N` URI into the collection URI and the page number.
+pub fn split_page_uri(uri: &str) -> Option<(String, usize)> {
+    let (base, page) = uri.trim_end_matches('/').rsplit_once("/page/")?;
+    let page = page.parse().ok().filter(|page| *page > 0)?;
+    Some((if base.is_empty() { "/" } else { base }.to_string(), page))
+}
+
+/// The part of an item exposed to collection templates.
+pub fn item_value(capsule: &Capsule) -> Value {
+    serde_json::json!({
+        "name": capsule.name,
+        "description": capsule.description,
+        "uri": capsule.uri,
+        "data": capsule.data,
+        "seo": capsule.seo,
+        "locale": capsule.locale,
+    })
+}
+
+impl CollectionPage {
+    /// Adds `items` and `pagination` to a template context.
+    pub fn insert_into(&self, ctx: &mut Value) {
+        if let Some(map) = ctx.as_object_mut() {
+            map.insert("items".into(), Value::Array(self.items.clone()));
+            map.insert(
+                "pagination".into(),
+                serde_json::to_value(&self.pagination).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::Method;
+    use crate::application::auth::Access;
+    use crate::seo::Seo;
+
+    fn article(uri: &str) -> Capsule {
+        let mut seo = Seo::new();
+        seo.content_type = Some("article".into());
+        let mut capsule = Capsule::new(uri, "", uri, "article", Method::GET);
+        capsule.with_seo(seo);
+        capsule.data = serde_json::json!({ "tags": ["rust", "web"] });
+        capsule
+    }
+
+    #[test]
+    fn selects_matching_public_items() {
+        let owner = Capsule::new("blog", "", "/blog", "blog", Method::GET);
+        let collection = Collection {
+            content_type: Some("article".into()),
+            prefix: Some("/blog/".into()),
+            filter: BTreeMap::from([("tags".into(), Value::from("rust"))]),
+            ..Collection::default()
+        };
+        assert!(collection.selects(&owner, &article("/blog/first")));
+        assert!(!collection.selects(&owner, &owner));
+        assert!(!collection.selects(&owner, &article("/news/first")));
+
+        let mut private = article("/blog/draft");
+        private.access = Access::Login { roles: Vec::new() };
+        assert!(!collection.selects(&owner, &private));
+
+        let mut french = article("/blog/premier");
+        french.with_locale("fr");
+        assert!(!collection.selects(&owner, &french));
+    }
+}
@@ -0,0 +1,137 @@
+use rocket::http::{Cookie, SameSite};
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Name of the private cookie holding the per-session CSRF token.
+pub const CSRF_COOKIE: &str = "jigi_csrf";
+/// Name of the form field expected to carry the token in submitted forms.
+pub const CSRF_FIELD: &str = "_csrf";
+/// Name of the header accepted as an alternative to the form field (e.g. for `fetch` calls).
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// The CSRF token bound to the current visitor session.
+///
+/// The token is generated on the first request of a session and stored in a
+/// private (encrypted and signed) cookie using Rocket's `secrets` feature. As the
+/// cookie has no expiry, it lives as long as the browser session does. It is sent on top-level
+/// navigations from other sites (`SameSite=Lax`), so following a link to the site keeps the
+/// token of the forms already open in other tabs.
+///
+/// Templates receive the token as the `csrf_token` context value and are expected
+/// to send it back either as the `_csrf` form field or the `X-CSRF-Token` header:
+///
+/// ```html
+/// <form method="post">
+///   <input type="hidden" name="_csrf" value="{{ csrf_token }}">
+/// </form>
+/// ```
+///
+/// # Request guard
+///
+/// `CsrfToken` is a Rocket request guard that never fails: when no valid cookie is
+/// present, a fresh token is generated and the cookie is (re)issued.
+#[derive(Debug, Clone)]
+pub struct CsrfToken {
+    token: String,
+    header: Option<String>,
+}
+
+impl CsrfToken {
+    /// Returns the token expected from the visitor.
+    pub fn value(&self) -> &str {
+        &self.token
+    }
+
+    /// Checks a submitted token against the session token.
+    ///
+    /// The `X-CSRF-Token` header wins over `form_value` when both are present.
+    /// The comparison runs in constant time to avoid leaking the token through timing.
+    pub fn verify(&self, form_value: Option<&str>) -> bool {
+        match self.header.as_deref().or(form_value) {
+            Some(submitted) => constant_time_eq(submitted.as_bytes(), self.token.as_bytes()),
+            None => false,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfToken {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let jar = req.cookies();
+        let token = match jar.get_private(CSRF_COOKIE) {
+            Some(cookie) if !cookie.value().is_empty() => cookie.value().to_string(),
+            _ => {
+                let token = generate_token();
+                jar.add_private(
+                    Cookie::build((CSRF_COOKIE, token.clone()))
+                        .path("/")
+                        .http_only(true)
+                        .same_site(SameSite::Lax),
+                );
+                token
+            }
+        };
+        let header = req.headers().get_one(CSRF_HEADER).map(str::to_string);
+        Outcome::Success(CsrfToken { token, header })
+    }
+}
+
+/// Generates a random 256-bit token, hex encoded.
+fn generate_token() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Compares two byte strings without short-circuiting on the first difference.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(header: Option<&str>) -> CsrfToken {
+        CsrfToken {
+            token: "a1b2c3".to_string(),
+            header: header.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn verify_accepts_the_session_token() {
+        assert!(token(None).verify(Some("a1b2c3")));
+        assert!(token(Some("a1b2c3")).verify(None));
+    }
+
+    #[test]
+    fn verify_rejects_missing_or_wrong_tokens() {
+        assert!(!token(None).verify(None));
+        assert!(!token(None).verify(Some("a1b2c4")));
+        assert!(!token(None).verify(Some("")));
+        // The header wins over the form field
+        assert!(!token(Some("wrong")).verify(Some("a1b2c3")));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_bytes() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token!"));
+    }
+
+    #[test]
+    fn generate_token_is_random_hex() {
+        let (a, b) = (generate_token(), generate_token());
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+}
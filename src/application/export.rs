@@ -0,0 +1,226 @@
+use super::collections::page_uri;
+use super::redirects::{RedirectExport, export_redirects};
+use super::search::{SearchIndex, SearchResults};
+use super::{Capsule, CapsuleRegistry, Method, TemplateEngine, static_context};
+use crate::seo::SiteSeoDefaults;
+use crate::seo::social_card::SocialCards;
+use anyhow::Context;
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// How [`export_site`] writes a site.
+///
+/// # Fields
+///
+/// * `redirects` - How redirects are written (see [`RedirectExport`]).
+/// * `base_url` - The site origin, e.g. `https://example.org`. A `sitemap.xml` is written when set.
+/// * `seo_defaults` - The site-wide SEO settings the pages are rendered with (see
+///   [`Seo::with_defaults`](crate::seo::Seo::with_defaults)).
+/// * `social_cards` - Gives the pages without an `og_image`, the site-wide one included, a
+///   generated card, written to `__cards/{hash}.png` (see [`SocialCards`]).
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub redirects: RedirectExport,
+    pub base_url: Option<String>,
+    pub seo_defaults: Option<SiteSeoDefaults>,
+    pub social_cards: Option<Arc<SocialCards>>,
+}
+
+/// The outcome of [`export_site`].
+///
+/// # Fields
+///
+/// * `pages` - The number of HTML pages written, collection pages included.
+/// * `redirects` - The number of redirects written.
+/// * `cards` - The number of social cards written.
+/// * `skipped` - The URIs that cannot be exported: dynamic (`<param>`) or restricted capsules.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportReport {
+    pub pages: usize,
+    pub redirects: usize,
+    pub cards: usize,
+    pub skipped: Vec<String>,
+}
+
+/// Renders a site into static files under `out_dir`.
+///
+/// # Behavior
+///
+/// 1. Loads all templates with [`TemplateEngine::load_all`].
+/// 2. Writes every public `GET` capsule to `{uri}/index.html`, rendered as an anonymous visitor
+///    would see it (see [`static_context`]). Collections get one file per page: `/blog/index.html`,
+///    `/blog/page/2/index.html`… The [`ExportOptions::seo_defaults`] are applied first; then,
+///    with [`ExportOptions::social_cards`], the pages still without an `og_image` point at a card
+///    written to `__cards/{hash}.png`.
+/// 3. Writes `404.html` from the `404` template, when there is one.
+/// 4. Writes redirect capsules, aliases and rules with [`export_redirects`].
+/// 5. Writes `sitemap.xml` when [`ExportOptions::base_url`] is set.
+/// 6. Writes the [`SearchIndex`] of the site to `search-index.json`, for client-side search. Search
+///    capsules are exported without results.
+///
+/// # Errors
+///
+/// Fails on the first template that does not render and on I/O errors. Run
+/// [`check_site`](super::check::check_site) first for a full report.
+pub fn export_site(
+    registry: &CapsuleRegistry,
+    engine: &dyn TemplateEngine,
+    out_dir: impl AsRef<Path>,
+    options: &ExportOptions,
+) -> anyhow::Result<ExportReport> {
+    let out_dir = out_dir.as_ref();
+    engine.load_all()?;
+    let mut report = ExportReport::default();
+
+    for (_, capsule) in registry.all() {
+        if capsule.redirect.is_some() || capsule.method != Method::GET {
+            continue;
+        }
+        if capsule.uri.contains('<') || !capsule.access.is_public() {
+            report.skipped.push(capsule.uri.clone());
+            continue;
+        }
+        let mut capsule = capsule.clone();
+        if let Some(site) = &options.seo_defaults {
+            capsule.seo = capsule.seo.map(|seo| seo.with_defaults(site));
+        }
+        if let (Some(cards), Some(seo)) = (&options.social_cards, &mut capsule.seo)
+            && let Some(uri) = cards.apply(&capsule.uri, seo)
+        {
+            let file_name = uri.rsplit('/').next().unwrap_or_default();
+            let png = cards
+                .get(file_name)?
+                .with_context(|| format!("no social card {uri}"))?;
+            write_bytes(&out_dir.join(uri.trim_start_matches('/')), &png)?;
+            report.cards += 1;
+        }
+        report.pages += export_capsule(registry, engine, &capsule, out_dir)?;
+    }
+
+    if engine.templates().iter().any(|name| name == "404") {
+        let mut ctx = serde_json::json!({ "path": "", "request": {} });
+        if let Some(extensions) = engine.extensions() {
+            extensions.merge_globals(&mut ctx);
+        }
+        write_file(&out_dir.join("404.html"), &engine.render("404", &ctx)?)?;
+    }
+
+    report.redirects = export_redirects(&registry.redirects(), out_dir, options.redirects)?;
+
+    if let Some(base_url) = &options.base_url {
+        write_file(&out_dir.join("sitemap.xml"), &registry.sitemap(base_url))?;
+    }
+    let index = serde_json::to_string(&SearchIndex::build(registry))?;
+    write_file(&out_dir.join("search-index.json"), &index)?;
+    Ok(report)
+}
+
+/// Writes the pages of one capsule, returning how many were written.
+fn export_capsule(
+    registry: &CapsuleRegistry,
+    engine: &dyn TemplateEngine,
+    capsule: &Capsule,
+    out_dir: &Path,
+) -> anyhow::Result<usize> {
+    let mut page = 1;
+    loop {
+        let mut ctx = static_context(capsule, engine);
+        let listing = registry.collection_page(&capsule.uri, page);
+        if let Some(listing) = &listing {
+            listing.insert_into(&mut ctx);
+        }
+        if capsule.search.is_some() {
+            SearchResults::default().insert_into(&mut ctx);
+        }
+        let html = engine
+            .render(&capsule.template, &ctx)
+            .with_context(|| format!("cannot render {}", capsule.uri))?;
+        write_file(&page_path(out_dir, &page_uri(&capsule.uri, page))?, &html)?;
+        match listing {
+            Some(listing) if listing.pagination.next.is_some() => page += 1,
+            _ => return Ok(page),
+        }
+    }
+}
+
+/// The `index.html` file serving `uri` in `out_dir`.
+///
+/// # Errors
+///
+/// Fails when `uri` would escape `out_dir`, e.g. with `..` segments.
+fn page_path(out_dir: &Path, uri: &str) -> anyhow::Result<PathBuf> {
+    let relative = Path::new(uri.trim_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        anyhow::bail!("refusing to export the page at `{uri}`");
+    }
+    Ok(out_dir.join(relative).join("index.html"))
+}
+
+fn write_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    write_bytes(path, contents.as_bytes())
+}
+
+fn write_bytes(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("cannot create directory {}", dir.display()))?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("cannot write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An engine rendering every template as its name.
+    struct Names;
+
+    impl TemplateEngine for Names {
+        fn load_all(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn render(&self, template: &str, _ctx: &serde_json::Value) -> anyhow::Result<String> {
+            Ok(template.to_string())
+        }
+    }
+
+    #[test]
+    fn page_path_stays_in_the_output_directory() {
+        let out = Path::new("site");
+        assert_eq!(page_path(out, "/").unwrap(), out.join("index.html"));
+        assert_eq!(
+            page_path(out, "/blog/page/2/").unwrap(),
+            out.join("blog/page/2/index.html")
+        );
+        assert!(page_path(out, "/../etc").is_err());
+        assert!(page_path(out, "/a/../../b").is_err());
+    }
+
+    #[test]
+    fn export_site_writes_pages_and_refuses_traversal() {
+        let out_dir = std::env::temp_dir().join(format!("jigi-export-{}", std::process::id()));
+        let mut registry = CapsuleRegistry::default();
+        registry.add(Capsule::new("home", "Home", "/", "home", Method::GET));
+        registry.add(Capsule::new(
+            "about",
+            "About",
+            "/about",
+            "about",
+            Method::GET,
+        ));
+        let report = export_site(&registry, &Names, &out_dir, &ExportOptions::default()).unwrap();
+        assert_eq!(report.pages, 2);
+        let about = std::fs::read_to_string(out_dir.join("about/index.html")).unwrap();
+        assert_eq!(about, "about");
+
+        registry.add(Capsule::new("up", "Up", "/../up", "up", Method::GET));
+        let error = export_site(&registry, &Names, &out_dir, &ExportOptions::default());
+        assert!(error.unwrap_err().to_string().contains("`/../up`"));
+        assert!(!out_dir.join("../up").exists());
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+}
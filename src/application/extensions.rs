@@ -0,0 +1,328 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rocket_dyn_templates::tera::{self, Filter, Function, Tera};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Global holding the site origin used by the `url` function, e.g. `https://example.org`.
+pub const BASE_URL_GLOBAL: &str = "base_url";
+
+/// Filters, functions and global variables added to the Tera templates of a site.
+///
+/// The same extensions are installed in every [`Tera`] instance the site renders with: the one
+/// of the HTTP server and the one of the [`TeraEngine`](super::TeraEngine) used for offline
+/// rendering, so templates behave the same when served and when exported.
+///
+/// # Built-ins
+///
+/// [`TemplateExtensions::with_builtins`] provides:
+///
+/// - `slugify` filter: `{{ title | slugify }}`, using the `slug` crate.
+/// - `markdown` filter: `{{ body | markdown | safe }}`, CommonMark plus tables, strikethrough,
+///   autolinks and task lists, using `comrak`. Raw HTML in the source is not rendered.
+/// - `date` filter: `{{ updated | date(format="%d %B %Y") }}`, using `chrono`. Accepts RFC 3339
+///   and ISO8601 strings (`2025-05-20`, `2025-05-20T12:30:00`) and Unix timestamps.
+/// - `emojify` filter: `{{ "Ship it :rocket:" | emojify }}` replaces `:shortcode:`s (CLDR names,
+///   spaces written as `_`) with their emoji, using the `emoji` crate.
+/// - `url` function: `{{ url(path="/about") }}`, or `{{ url(path="/about", absolute=true) }}` to
+///   prefix the `base_url` global.
+/// - `now` function: `{{ now(format="%Y") }}`, the current UTC time.
+///
+/// Filters and functions registered later with the same name replace the built-ins.
+#[derive(Clone, Default)]
+pub struct TemplateExtensions {
+    filters: HashMap<String, Arc<dyn Filter>>,
+    functions: HashMap<String, Arc<dyn Function>>,
+    globals: Map<String, Value>,
+}
+
+impl TemplateExtensions {
+    /// Creates an empty set of extensions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a set of extensions holding the built-in filters and functions.
+    pub fn with_builtins() -> Self {
+        let mut extensions = Self::new();
+        extensions
+            .register_filter("slugify", slugify_filter)
+            .register_filter("markdown", markdown_filter)
+            .register_filter("date", date_filter)
+            .register_filter("emojify", emojify_filter)
+            .register_function("now", now_function);
+        extensions
+    }
+
+    /// Registers a filter, used in templates as `{{ value | name(arg=…) }}`.
+    pub fn register_filter<F: Filter + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        filter: F,
+    ) -> &mut Self {
+        self.filters.insert(name.into(), Arc::new(filter));
+        self
+    }
+
+    /// Registers a function, used in templates as `{{ name(arg=…) }}`.
+    pub fn register_function<F: Function + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        function: F,
+    ) -> &mut Self {
+        self.functions.insert(name.into(), Arc::new(function));
+        self
+    }
+
+    /// Registers a variable available in every capsule template, unless the capsule context
+    /// already has a value with the same name.
+    pub fn register_global(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> &mut Self {
+        self.globals.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the global variables.
+    pub fn globals(&self) -> &Map<String, Value> {
+        &self.globals
+    }
+
+    /// Installs the filters and functions in `tera`, along with the `url` function.
+    pub fn apply(&self, tera: &mut Tera) {
+        let base_url = self
+            .globals
+            .get(BASE_URL_GLOBAL)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim_end_matches('/')
+            .to_string();
+        tera.register_function("url", move |args: &HashMap<String, Value>| {
+            url_function(&base_url, args)
+        });
+        for (name, filter) in &self.filters {
+            let filter = filter.clone();
+            tera.register_filter(name, move |value: &Value, args: &HashMap<String, Value>| {
+                filter.filter(value, args)
+            });
+        }
+        for (name, function) in &self.functions {
+            let function = function.clone();
+            tera.register_function(name, move |args: &HashMap<String, Value>| {
+                function.call(args)
+            });
+        }
+    }
+
+    /// Adds the globals to `ctx`, without replacing the values it already has.
+    pub fn merge_globals(&self, ctx: &mut Value) {
+        if let Some(map) = ctx.as_object_mut() {
+            for (name, value) in &self.globals {
+                map.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+/// Returns the string argument `name`, or `default`.
+fn str_arg<'a>(args: &'a HashMap<String, Value>, name: &str, default: &'a str) -> &'a str {
+    args.get(name).and_then(Value::as_str).unwrap_or(default)
+}
+
+/// Returns the value as a string, failing with a message naming `filter` otherwise.
+fn expect_str<'a>(value: &'a Value, filter: &str) -> tera::Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg(format!("filter `{filter}` expects a string, got {value}")))
+}
+
+fn slugify_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    Ok(slug::slugify(expect_str(value, "slugify")?).into())
+}
+
+fn markdown_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let mut options = comrak::Options::default();
+    options.extension.table = true;
+    options.extension.strikethrough = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    Ok(comrak::markdown_to_html(expect_str(value, "markdown")?, &options).into())
+}
+
+fn date_filter(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let format = str_arg(args, "format", "%Y-%m-%d");
+    let date: NaiveDateTime = match value {
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| tera::Error::msg(format!("filter `date`: invalid timestamp {n}")))?,
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|dt| dt.naive_local())
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(Default::default()))
+            })
+            .map_err(|_| tera::Error::msg(format!("filter `date`: cannot parse `{s}`")))?,
+        other => {
+            return Err(tera::Error::msg(format!(
+                "filter `date` expects a string or a timestamp, got {other}"
+            )));
+        }
+    };
+    formatted(date.format(format), "filter `date`", format)
+}
+
+/// Renders a chrono date format, failing instead of panicking on invalid format strings.
+fn formatted(date: impl std::fmt::Display, what: &str, format: &str) -> tera::Result<Value> {
+    use std::fmt::Write;
+    let mut out = String::new();
+    write!(out, "{date}")
+        .map_err(|_| tera::Error::msg(format!("{what}: invalid format `{format}`")))?;
+    Ok(out.into())
+}
+
+fn emojify_filter(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let text = expect_str(value, "emojify")?;
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let emoji = after.find(':').and_then(|end| {
+            let code = &after[..end];
+            let valid = !code.is_empty() && !code.contains(char::is_whitespace);
+            valid
+                .then(|| emoji::lookup_by_name::lookup(&code.replace('_', " ")))
+                .flatten()
+                .map(|emoji| (emoji.glyph, end))
+        });
+        match emoji {
+            Some((glyph, end)) => {
+                out.push_str(glyph);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out.into())
+}
+
+fn url_function(base_url: &str, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let path = args
+        .get("path")
+        .and_then(Value::as_str)
+        .ok_or_else(|| tera::Error::msg("function `url` expects a `path` string"))?;
+    let path = format!("/{}", path.trim_start_matches('/'));
+    let absolute = args
+        .get("absolute")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    Ok(if absolute {
+        format!("{base_url}{path}")
+    } else {
+        path
+    }
+    .into())
+}
+
+fn now_function(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let format = str_arg(args, "format", "%Y-%m-%dT%H:%M:%SZ");
+    formatted(Utc::now().format(format), "function `now`", format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn emojify_replaces_known_shortcodes_only() {
+        let text = json!("Ship it :rocket: at 10:30 :no_such_emoji: :");
+        assert_eq!(
+            emojify_filter(&text, &args(&[])).unwrap(),
+            "Ship it 🚀 at 10:30 :no_such_emoji: :"
+        );
+        assert_eq!(
+            emojify_filter(&json!(":thumbs_up:"), &args(&[])).unwrap(),
+            "👍"
+        );
+        assert!(emojify_filter(&json!(3), &args(&[])).is_err());
+    }
+
+    #[test]
+    fn date_formats_strings_and_timestamps() {
+        let long = args(&[("format", json!("%d %B %Y"))]);
+        assert_eq!(
+            date_filter(&json!("2025-05-20"), &args(&[])).unwrap(),
+            "2025-05-20"
+        );
+        assert_eq!(date_filter(&json!(0), &long).unwrap(), "01 January 1970");
+        let time = args(&[("format", json!("%H:%M"))]);
+        assert_eq!(
+            date_filter(&json!("2025-05-20T12:30:00+02:00"), &time).unwrap(),
+            "12:30"
+        );
+        assert_eq!(
+            date_filter(&json!("2025-05-20T08:15:00"), &time).unwrap(),
+            "08:15"
+        );
+    }
+
+    #[test]
+    fn date_rejects_bad_input_and_formats() {
+        assert!(date_filter(&json!("soon"), &args(&[])).is_err());
+        assert!(date_filter(&json!(true), &args(&[])).is_err());
+        let error = date_filter(&json!(0), &args(&[("format", json!("%Q"))])).unwrap_err();
+        assert!(error.to_string().contains("invalid format `%Q`"));
+    }
+
+    #[test]
+    fn url_prefixes_the_base_url_when_absolute() {
+        let relative = args(&[("path", json!("about"))]);
+        assert_eq!(
+            url_function("https://example.org", &relative).unwrap(),
+            "/about"
+        );
+        let absolute = args(&[("path", json!("/about")), ("absolute", json!(true))]);
+        assert_eq!(
+            url_function("https://example.org", &absolute).unwrap(),
+            "https://example.org/about"
+        );
+        assert!(url_function("", &args(&[])).is_err());
+    }
+
+    #[test]
+    fn apply_installs_builtins_and_globals() {
+        let mut extensions = TemplateExtensions::with_builtins();
+        extensions.register_global(BASE_URL_GLOBAL, "https://example.org/");
+        let mut tera = Tera::default();
+        extensions.apply(&mut tera);
+        let mut ctx = json!({ "title": "Hello World" });
+        extensions.merge_globals(&mut ctx);
+        let ctx = tera::Context::from_value(ctx).unwrap();
+        let rendered = tera
+            .render_str(
+                "{{ title | slugify }} {{ url(path='/a', absolute=true) }} {{ base_url }}",
+                &ctx,
+            )
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "hello-world https://example.org/a https://example.org/"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How long a webhook may take to answer before the submission is reported as failed.
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The kind of value a form field accepts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    #[default]
+    Text,
+    Email,
+    Number,
+    Integer,
+    Checkbox,
+}
+
+/// A regular expression the whole value of a field must match.
+///
+/// Written as a string in manifests and compiled when they are loaded, so an invalid pattern
+/// makes the manifest invalid instead of failing submissions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FieldPattern {
+    source: String,
+    regex: Regex,
+}
+
+impl FieldPattern {
+    /// Compiles `pattern`, anchored to match the whole value.
+    ///
+    /// # Errors
+    ///
+    /// Fails when `pattern` is not a valid regular expression.
+    pub fn new(pattern: impl Into<String>) -> Result<Self, regex::Error> {
+        let source = pattern.into();
+        let regex = Regex::new(&format!("^(?:{source})$"))?;
+        Ok(Self { source, regex })
+    }
+
+    /// The pattern as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl TryFrom<String> for FieldPattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Self::new(pattern)
+    }
+}
+
+impl From<FieldPattern> for String {
+    fn from(pattern: FieldPattern) -> Self {
+        pattern.source
+    }
+}
+
+/// One field of a [`FormSchema`].
+///
+/// # Fields
+///
+/// * `name` - The name of the submitted field.
+/// * `label` - Human-readable name used in error messages (defaults to `name`).
+/// * `kind` - The [`FieldKind`], written `type` in manifests.
+/// * `required` - Whether an empty or missing value is an error.
+/// * `min_length` / `max_length` - Bounds on the number of characters.
+/// * `pattern` - A regular expression the whole value must match (see [`FieldPattern`]).
+/// * `min` / `max` - Bounds for `number` and `integer` fields.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FormField {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    #[serde(default)]
+    pub pattern: Option<FieldPattern>,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl FormField {
+    pub fn new<S: Into<String>>(name: S, kind: FieldKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            ..Self::default()
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn with_label<S: Into<String>>(mut self, label: S) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_length(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_length = min;
+        self.max_length = max;
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: FieldPattern) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Validates the raw submitted value, returning the typed value or the error messages.
+    fn validate(&self, raw: Option<&Value>) -> Result<Value, Vec<String>> {
+        let label = self.label();
+        if self.kind == FieldKind::Checkbox {
+            let checked = match raw {
+                Some(Value::Bool(b)) => *b,
+                Some(Value::String(s)) => !s.is_empty() && s != "false" && s != "0",
+                Some(Value::Null) | None => false,
+                Some(_) => true,
+            };
+            if self.required && !checked {
+                return Err(vec![format!("{label} must be checked")]);
+            }
+            return Ok(Value::Bool(checked));
+        }
+
+        let text = match raw {
+            Some(Value::String(s)) => s.trim().to_string(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        if text.is_empty() {
+            return if self.required {
+                Err(vec![format!("{label} is required")])
+            } else {
+                Ok(Value::Null)
+            };
+        }
+
+        let mut errors = Vec::new();
+        let length = text.chars().count();
+        if let Some(min) = self.min_length.filter(|min| length < *min) {
+            errors.push(format!("{label} must be at least {min} characters long"));
+        }
+        if let Some(max) = self.max_length.filter(|max| length > *max) {
+            errors.push(format!("{label} must be at most {max} characters long"));
+        }
+        if self.pattern.as_ref().is_some_and(|p| !p.is_match(&text)) {
+            errors.push(format!("{label} has an invalid format"));
+        }
+
+        let value = match self.kind {
+            FieldKind::Email if !is_email(&text) => {
+                errors.push(format!("{label} must be a valid email address"));
+                Value::Null
+            }
+            FieldKind::Number | FieldKind::Integer => {
+                let parsed = if self.kind == FieldKind::Integer {
+                    text.parse::<i64>().ok().map(|n| n as f64)
+                } else {
+                    text.parse::<f64>().ok().filter(|n| n.is_finite())
+                };
+                match parsed {
+                    None if self.kind == FieldKind::Integer => {
+                        errors.push(format!("{label} must be a whole number"));
+                        Value::Null
+                    }
+                    None => {
+                        errors.push(format!("{label} must be a number"));
+                        Value::Null
+                    }
+                    Some(n) => {
+                        if let Some(min) = self.min.filter(|min| n < *min) {
+                            errors.push(format!("{label} must be at least {min}"));
+                        }
+                        if let Some(max) = self.max.filter(|max| n > *max) {
+                            errors.push(format!("{label} must be at most {max}"));
+                        }
+                        if self.kind == FieldKind::Integer {
+                            Value::from(n as i64)
+                        } else {
+                            Value::from(n)
+                        }
+                    }
+                }
+            }
+            _ => Value::String(text),
+        };
+
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A pragmatic email check: one `@`, a non-empty local part and a dotted domain without spaces.
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && !s.chars().any(char::is_whitespace)
+                && domain
+                    .split_once('.')
+                    .is_some_and(|(host, tld)| !host.is_empty() && !tld.is_empty())
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Error messages of an invalid submission, keyed by field name.
+pub type FormErrors = BTreeMap<String, Vec<String>>;
+
+/// Where valid submissions are delivered.
+///
+/// # Variants
+///
+/// - `JsonLines`: Appends one JSON object per submission to the file at `path`.
+/// - `Webhook`: `POST`s the submission as JSON to `url`, failing after [`WEBHOOK_TIMEOUT`].
+///
+/// Both receive `{ "capsule": <uri>, "submitted_at": <RFC 3339>, "values": {…} }`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FormSink {
+    JsonLines { path: PathBuf },
+    Webhook { url: String },
+}
+
+impl FormSink {
+    /// Delivers a validated submission.
+    pub async fn deliver(&self, uri: &str, values: &Map<String, Value>) -> anyhow::Result<()> {
+        let record = serde_json::json!({
+            "capsule": uri,
+            "submitted_at": chrono::Utc::now().to_rfc3339(),
+            "values": values,
+        });
+        match self {
+            FormSink::JsonLines { path } => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?;
+                file.write_all(format!("{record}\n").as_bytes()).await?;
+            }
+            FormSink::Webhook { url } => {
+                reqwest::Client::builder()
+                    .timeout(WEBHOOK_TIMEOUT)
+                    .build()?
+                    .post(url)
+                    .json(&record)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A declarative form attached to a capsule.
+///
+/// When a capsule with a form receives a submission, the body is validated against `fields`.
+/// Invalid submissions re-render the capsule template with `422 Unprocessable Entity`, an
+/// `errors` map (field name to messages) and the submitted `values`, HTML-escaped. Valid submissions are
+/// handed to the `sink`, if any, before the capsule's [`PostActions`](super::session::PostActions)
+/// run (typically a flash message and a redirect).
+///
+/// ```toml
+/// [form]
+/// fields = [
+///     { name = "name", required = true, max_length = 80 },
+///     { name = "email", type = "email", required = true },
+///     { name = "age", type = "integer", min = 18, max = 120 },
+/// ]
+/// sink = { type = "json_lines", path = "data/contact.jsonl" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FormSchema {
+    #[serde(default)]
+    pub fields: Vec<FormField>,
+    #[serde(default)]
+    pub sink: Option<FormSink>,
+}
+
+impl FormSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, field: FormField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn with_sink(mut self, sink: FormSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Validates a parsed submission.
+    ///
+    /// Returns the typed values of the declared fields (undeclared fields are dropped), or
+    /// the error messages of every invalid field.
+    pub fn validate(
+        &self,
+        submitted: &Map<String, Value>,
+    ) -> Result<Map<String, Value>, FormErrors> {
+        let mut values = Map::new();
+        let mut errors = FormErrors::new();
+        for field in &self.fields {
+            match field.validate(submitted.get(&field.name)) {
+                Ok(value) => {
+                    values.insert(field.name.clone(), value);
+                }
+                Err(messages) => {
+                    errors.insert(field.name.clone(), messages);
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> FormSchema {
+        FormSchema::new()
+            .field(
+                FormField::new("name", FieldKind::Text)
+                    .required()
+                    .with_length(Some(2), Some(5)),
+            )
+            .field(FormField::new("email", FieldKind::Email).required())
+            .field(FormField::new("age", FieldKind::Integer).with_range(Some(18.0), None))
+            .field(FormField::new("terms", FieldKind::Checkbox).required())
+            .field(
+                FormField::new("code", FieldKind::Text)
+                    .with_label("Code")
+                    .with_pattern(FieldPattern::new("[A-Z]{3}").unwrap()),
+            )
+    }
+
+    fn submit(value: Value) -> Result<Map<String, Value>, FormErrors> {
+        schema().validate(value.as_object().unwrap())
+    }
+
+    #[test]
+    fn validate_types_valid_submissions() {
+        let values = submit(json!({
+            "name": " Ada ",
+            "email": "ada@example.com",
+            "age": "36",
+            "terms": "on",
+            "extra": "dropped",
+        }))
+        .unwrap();
+        assert_eq!(
+            Value::Object(values),
+            json!({ "name": "Ada", "email": "ada@example.com", "age": 36, "terms": true, "code": null })
+        );
+    }
+
+    #[test]
+    fn validate_reports_every_invalid_field() {
+        let errors = submit(json!({
+            "name": "A",
+            "email": "ada@",
+            "age": "12.5",
+            "code": "abcd",
+        }))
+        .unwrap_err();
+        assert_eq!(errors["name"], ["name must be at least 2 characters long"]);
+        assert_eq!(errors["email"], ["email must be a valid email address"]);
+        assert_eq!(errors["age"], ["age must be a whole number"]);
+        assert_eq!(errors["terms"], ["terms must be checked"]);
+        assert_eq!(errors["code"], ["Code has an invalid format"]);
+    }
+
+    #[test]
+    fn validate_checks_numeric_ranges() {
+        let errors = submit(json!({ "name": "Ada", "email": "a@b.c", "age": "17", "terms": true }))
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors["age"], ["age must be at least 18"]);
+    }
+
+    #[test]
+    fn field_pattern_matches_whole_values() {
+        let pattern = FieldPattern::new("[0-9]+|x").unwrap();
+        assert!(pattern.is_match("123") && pattern.is_match("x"));
+        assert!(!pattern.is_match("12a") && !pattern.is_match("ax"));
+        assert!(FieldPattern::new("(").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::seo::Seo;
+use serde::{Deserialize, Serialize};
+
+/// A translation of a capsule, served under a locale prefix.
+///
+/// Every field left out falls back to the capsule being translated; `data` is merged key by
+/// key over the capsule's data.
+///
+/// ```toml
+/// name = "about"
+/// uri = "/about"
+/// template = "about"
+/// locale = "en"
+///
+/// [translations.fr]
+/// description = "À propos"
+/// data = { title = "Qui sommes-nous ?" }
+///
+/// [translations.fr.seo]
+/// title = "À propos"
+/// description = "Qui nous sommes et ce que nous faisons."
+///
+/// [translations.de]
+/// template = "about_de"
+/// ```
+///
+/// # Fields
+///
+/// * `template` - The template of the translation, if it differs from the capsule's.
+/// * `description` - The translated description.
+/// * `data` - Values overriding those of the capsule's `data`.
+/// * `seo` - The SEO metadata of the translation, replacing the capsule's. When left out, the
+///   capsule's metadata is reused with its canonical and shared URLs moved under the locale.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Translation {
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub data: Option<serde_json::Value>,
+    #[serde(default)]
+    pub seo: Option<Seo>,
+}
+
+/// Returns the URI of `uri` in `locale`: `/about` becomes `/fr/about`, `/` becomes `/fr`.
+pub fn localized_uri(locale: &str, uri: &str) -> String {
+    let locale = locale.trim_matches('/');
+    match uri.trim_start_matches('/') {
+        "" => format!("/{locale}"),
+        rest => format!("/{locale}/{rest}"),
+    }
+}
+
+/// Returns `url` moved under `locale`, like [`localized_uri`], keeping the scheme and host of
+/// absolute URLs: `https://example.com/about` becomes `https://example.com/fr/about`.
+pub fn localized_url(locale: &str, url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            format!("{scheme}://{host}{}", localized_uri(locale, path))
+        }
+        None => localized_uri(locale, url),
+    }
+}
+
+/// The cookie holding the language a visitor chose, e.g. with a language switcher.
+///
+/// At `/`, it takes precedence over the `Accept-Language` header: `locale=en` keeps visitors on
+/// an English home page even though their browser prefers French.
+pub const LOCALE_COOKIE: &str = "locale";
+
+/// Picks the best of `available` locales for an `Accept-Language` header value.
+///
+/// Language ranges are tried by decreasing quality (`q`). A range matches a locale with the same
+/// tag, ignoring case, or with the same primary language (`fr-CA` matches `fr`, `en` matches
+/// `en-GB`). `*` and ranges with `q=0` never match.
+///
+/// # Returns
+///
+/// The matching entry of `available`, or `None` when the visitor accepts none of them.
+pub fn negotiate<'a>(accept_language: &str, available: &[&'a str]) -> Option<&'a str> {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable sort: among equal qualities, the header order wins.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let primary = |tag: &str| {
+        tag.split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    ranges.iter().find_map(|(tag, _)| {
+        available
+            .iter()
+            .find(|locale| locale.eq_ignore_ascii_case(tag))
+            .or_else(|| {
+                available
+                    .iter()
+                    .find(|locale| primary(locale) == primary(tag))
+            })
+            .copied()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localized_uri_prefixes_the_locale() {
+        assert_eq!(localized_uri("fr", "/about"), "/fr/about");
+        assert_eq!(localized_uri("fr", "/"), "/fr");
+        assert_eq!(localized_uri("/de/", "/a/b"), "/de/a/b");
+    }
+
+    #[test]
+    fn localized_url_keeps_scheme_and_host() {
+        assert_eq!(
+            localized_url("fr", "https://example.com/about"),
+            "https://example.com/fr/about"
+        );
+        assert_eq!(
+            localized_url("fr", "https://example.com"),
+            "https://example.com/fr"
+        );
+        assert_eq!(localized_url("fr", "/about"), "/fr/about");
+    }
+}
@@ -0,0 +1,171 @@
+use super::{Capsule, Method};
+use crate::seo::Seo;
+use anyhow::Context;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+const BODY: &str = r"(?is)<body\b[^>]*>(.*)</body\s*>";
+
+/// How [`import_html`] turns pages into capsules.
+///
+/// # Fields
+///
+/// * `template` - The template of the capsules created. It gets the body of the page as
+///   `data.html`, to render with `{{ data.html | safe }}`.
+/// * `overwrite` - Replace the manifests that already exist instead of skipping their pages.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub template: String,
+    pub overwrite: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            template: "page".to_string(),
+            overwrite: false,
+        }
+    }
+}
+
+/// The outcome of [`import_html`].
+///
+/// # Fields
+///
+/// * `imported` - The URIs of the capsules created.
+/// * `skipped` - The pages not imported, with the reason.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Turns a directory of HTML pages into capsule manifests, keeping their metadata.
+///
+/// Every `*.html`/`*.htm` file under `source_dir` becomes a `GET` capsule written to
+/// `{manifests_dir}/{name}.toml`:
+///
+/// - `uri`: the path of the file, without extension and with each segment slugified;
+///   `index.html` stands for its directory (`blog/index.html` is served at `/blog`,
+///   `Blog/My Post.html` at `/blog/my-post`). Files with a segment that slugifies to nothing
+///   are skipped.
+/// - `name`: the slugified URI (`blog-post`), `index` for `/`.
+/// - `seo`: read with [`Seo::from_html`].
+/// - `data.html`: the content of the `<body>`.
+///
+/// # Errors
+///
+/// Fails when `source_dir` cannot be listed. Pages that cannot be read or written are skipped.
+pub fn import_html(
+    source_dir: impl AsRef<Path>,
+    manifests_dir: impl AsRef<Path>,
+    options: &ImportOptions,
+) -> anyhow::Result<ImportReport> {
+    let source_dir = source_dir.as_ref();
+    let manifests_dir = manifests_dir.as_ref();
+    let mut paths: Vec<PathBuf> =
+        globwalk::GlobWalkerBuilder::from_patterns(source_dir, &["**/*.{html,htm}"])
+            .build()
+            .with_context(|| format!("cannot list {}", source_dir.display()))?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
+    paths.sort();
+
+    let body = Regex::new(BODY)?;
+    let mut report = ImportReport::default();
+    let mut names = BTreeSet::new();
+    for path in paths {
+        let relative = path.strip_prefix(source_dir).unwrap_or(&path);
+        let Some(uri) = file_uri(relative) else {
+            report
+                .skipped
+                .push((path, "cannot make a URI from its path".to_string()));
+            continue;
+        };
+        let name = match uri.as_str() {
+            "/" => "index".to_string(),
+            uri => slug::slugify(uri),
+        };
+        let manifest = manifests_dir.join(format!("{name}.toml"));
+        if !names.insert(name.clone()) {
+            report
+                .skipped
+                .push((path, format!("another page is named `{name}`")));
+            continue;
+        }
+        if manifest.exists() && !options.overwrite {
+            report
+                .skipped
+                .push((path, format!("{} already exists", manifest.display())));
+            continue;
+        }
+        let html = match std::fs::read_to_string(&path) {
+            Ok(html) => html,
+            Err(error) => {
+                report.skipped.push((path, error.to_string()));
+                continue;
+            }
+        };
+
+        let seo = Seo::from_html(&html);
+        let mut capsule = Capsule::new(
+            name,
+            seo.title.clone(),
+            uri.clone(),
+            options.template.clone(),
+            Method::GET,
+        );
+        capsule.with_seo(seo);
+        let content = body.captures(&html).map_or(html.as_str(), |b| {
+            b.get(1).map_or("", |content| content.as_str())
+        });
+        capsule.data = serde_json::json!({ "html": content.trim() });
+        match capsule.write_manifest(&manifest) {
+            Ok(()) => report.imported.push(uri),
+            Err(error) => report.skipped.push((path, format!("{error:#}"))),
+        }
+    }
+    Ok(report)
+}
+
+/// The URI a page is served at, from its path relative to the site root.
+///
+/// # Returns
+///
+/// `None` when a segment of the path has nothing to keep once slugified.
+fn file_uri(relative: &Path) -> Option<String> {
+    let mut segments: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .map(|c| slug::slugify(c.as_os_str().to_string_lossy()))
+        .collect();
+    if segments.iter().any(String::is_empty) {
+        return None;
+    }
+    if segments.last().is_some_and(|last| last == "index") {
+        segments.pop();
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_uri_slugifies_segments() {
+        assert_eq!(file_uri(Path::new("index.html")).as_deref(), Some("/"));
+        assert_eq!(
+            file_uri(Path::new("Blog/index.html")).as_deref(),
+            Some("/blog")
+        );
+        assert_eq!(
+            file_uri(Path::new("Blog/My Post?.html")).as_deref(),
+            Some("/blog/my-post")
+        );
+        assert_eq!(file_uri(Path::new("blog/!!!.html")), None);
+    }
+}
@@ -0,0 +1,300 @@
+use super::{Capsule, CapsuleRegistry};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// How many events a slow subscriber may fall behind before missing some.
+const EVENT_CAPACITY: usize = 256;
+
+/// A change made to a [`LiveRegistry`].
+///
+/// # Variants
+///
+/// - `Added`: A capsule was added at `uri`.
+/// - `Replaced`: The capsule at `uri` was replaced.
+/// - `Removed`: The capsule at `uri` was removed.
+/// - `Reloaded`: The whole registry was replaced, e.g. after the manifests changed on disk.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RegistryEvent {
+    Added { uri: String },
+    Replaced { uri: String },
+    Removed { uri: String },
+    Reloaded,
+}
+
+/// A [`CapsuleRegistry`] that can change while the server runs.
+///
+/// Readers take a [`snapshot`](LiveRegistry::snapshot): an `Arc` to the registry as it is now,
+/// which later changes never alter. Writers copy the registry, change the copy and publish it, so
+/// readers never wait for a change to complete, and a request sees the same registry from start to
+/// end. Changes are made one at a time.
+///
+/// Every change is announced to the [`subscribe`](LiveRegistry::subscribe)rs, so that what is
+/// derived from the registry (search index, sitemap, caches) can be refreshed.
+///
+/// ```ignore
+/// let registry = Arc::new(LiveRegistry::new(registry));
+/// let mut events = registry.subscribe();
+/// tokio::spawn(async move {
+///     while let Ok(event) = events.recv().await {
+///         println!("{event:?}");
+///     }
+/// });
+/// registry.replace(Capsule::new("about", "About us", "/about", "about", Method::GET))?;
+/// ```
+pub struct LiveRegistry {
+    current: RwLock<Arc<CapsuleRegistry>>,
+    writer: Mutex<()>,
+    events: broadcast::Sender<RegistryEvent>,
+}
+
+impl Default for LiveRegistry {
+    fn default() -> Self {
+        Self::new(CapsuleRegistry::default())
+    }
+}
+
+impl From<CapsuleRegistry> for LiveRegistry {
+    fn from(registry: CapsuleRegistry) -> Self {
+        Self::new(registry)
+    }
+}
+
+impl LiveRegistry {
+    /// Wraps `registry`, as the first version of the live registry.
+    pub fn new(registry: CapsuleRegistry) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(registry)),
+            writer: Mutex::new(()),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Returns the registry as it is now.
+    pub fn snapshot(&self) -> Arc<CapsuleRegistry> {
+        self.current.read().clone()
+    }
+
+    /// Returns a receiver of the [`RegistryEvent`]s of the changes made from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
+    /// Adds a capsule, with its translations.
+    ///
+    /// # Errors
+    ///
+    /// Fails when there already is a capsule at its URI, or when its name is taken (see
+    /// [`CapsuleRegistry::try_add`]).
+    pub fn add(&self, capsule: Capsule) -> anyhow::Result<()> {
+        let uri = capsule.uri.clone();
+        self.change(|registry| {
+            if registry.get(&capsule.uri).is_some() {
+                anyhow::bail!("there already is a capsule at {}", capsule.uri);
+            }
+            registry.try_add(capsule)
+        })?;
+        self.announce(RegistryEvent::Added { uri });
+        Ok(())
+    }
+
+    /// Adds a capsule, replacing the one at the same URI if any.
+    ///
+    /// # Returns
+    ///
+    /// The capsule replaced, if any.
+    ///
+    /// # Errors
+    ///
+    /// Fails when its name is taken by a capsule at another URI.
+    pub fn replace(&self, capsule: Capsule) -> anyhow::Result<Option<Capsule>> {
+        let uri = capsule.uri.clone();
+        let previous = self.change(|registry| {
+            let previous = registry.remove(&capsule.uri);
+            registry.try_add(capsule)?;
+            Ok(previous)
+        })?;
+        self.announce(match previous {
+            Some(_) => RegistryEvent::Replaced { uri },
+            None => RegistryEvent::Added { uri },
+        });
+        Ok(previous)
+    }
+
+    /// Replaces the capsule named `name` with `capsule`, which may live at another URI, in a
+    /// single change: readers see either the old capsule or the new one, never neither.
+    ///
+    /// # Returns
+    ///
+    /// The capsule replaced.
+    ///
+    /// # Errors
+    ///
+    /// Fails when no capsule is named `name`, when another capsule is at the new URI, or when the
+    /// new name is taken by a capsule at another URI.
+    pub fn update(&self, name: &str, capsule: Capsule) -> anyhow::Result<Capsule> {
+        let uri = capsule.uri.clone();
+        let previous = self.change(|registry| {
+            let Some(previous_uri) = registry.get_by_name(name).map(|c| c.uri.clone()) else {
+                anyhow::bail!("no capsule is named `{name}`");
+            };
+            if capsule.uri != previous_uri && registry.get(&capsule.uri).is_some() {
+                anyhow::bail!("there already is a capsule at {}", capsule.uri);
+            }
+            let previous = registry
+                .remove(&previous_uri)
+                .expect("the capsule named `name` is in the registry");
+            registry.try_add(capsule)?;
+            Ok(previous)
+        })?;
+        if previous.uri == uri {
+            self.announce(RegistryEvent::Replaced { uri });
+        } else {
+            self.announce(RegistryEvent::Removed {
+                uri: previous.uri.clone(),
+            });
+            self.announce(RegistryEvent::Added { uri });
+        }
+        Ok(previous)
+    }
+
+    /// Removes the capsule at `uri`, with its translations and aliases.
+    ///
+    /// # Returns
+    ///
+    /// The capsule removed, or `None` when there was none at `uri`.
+    pub fn remove(&self, uri: &str) -> Option<Capsule> {
+        let removed = self
+            .change(|registry| Ok(registry.remove(uri)))
+            .ok()
+            .flatten()?;
+        self.announce(RegistryEvent::Removed {
+            uri: uri.to_string(),
+        });
+        Some(removed)
+    }
+
+    /// Replaces the whole registry, e.g. with the manifests reloaded from disk.
+    pub fn reload(&self, registry: CapsuleRegistry) {
+        {
+            let _writer = self.writer.lock();
+            *self.current.write() = Arc::new(registry);
+        }
+        self.announce(RegistryEvent::Reloaded);
+    }
+
+    /// Applies `change` to a copy of the registry and publishes the copy, unless `change` fails.
+    fn change<T>(
+        &self,
+        change: impl FnOnce(&mut CapsuleRegistry) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let _writer = self.writer.lock();
+        let mut next = CapsuleRegistry::clone(&self.snapshot());
+        let outcome = change(&mut next)?;
+        *self.current.write() = Arc::new(next);
+        Ok(outcome)
+    }
+
+    fn announce(&self, event: RegistryEvent) {
+        // No subscriber is not an error
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::Method;
+
+    fn capsule(name: &str, uri: &str) -> Capsule {
+        Capsule::new(name, "", uri, "page", Method::GET)
+    }
+
+    fn events(receiver: &mut broadcast::Receiver<RegistryEvent>) -> Vec<RegistryEvent> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
+
+    fn added(uri: &str) -> RegistryEvent {
+        RegistryEvent::Added { uri: uri.into() }
+    }
+
+    #[test]
+    fn add_rejects_taken_uris_and_names() {
+        let live = LiveRegistry::default();
+        let mut receiver = live.subscribe();
+        live.add(capsule("about", "/about")).unwrap();
+        assert!(live.add(capsule("other", "/about")).is_err());
+        assert!(live.add(capsule("about", "/team")).is_err());
+        assert_eq!(events(&mut receiver), [added("/about")]);
+        assert_eq!(live.snapshot().all().count(), 1);
+    }
+
+    #[test]
+    fn replace_announces_added_then_replaced() {
+        let live = LiveRegistry::default();
+        let mut receiver = live.subscribe();
+        assert!(live.replace(capsule("about", "/about")).unwrap().is_none());
+        let previous = live.replace(capsule("team", "/about")).unwrap();
+        assert_eq!(previous.unwrap().name, "about");
+        assert_eq!(
+            events(&mut receiver),
+            [
+                added("/about"),
+                RegistryEvent::Replaced {
+                    uri: "/about".into()
+                }
+            ]
+        );
+        assert!(live.snapshot().get_by_name("about").is_none());
+    }
+
+    #[test]
+    fn update_moves_a_capsule_in_one_change() {
+        let live = LiveRegistry::new({
+            let mut registry = CapsuleRegistry::default();
+            registry.add(capsule("about", "/about"));
+            registry.add(capsule("team", "/team"));
+            registry
+        });
+        let before = live.snapshot();
+        let mut receiver = live.subscribe();
+        assert!(live.update("about", capsule("about", "/team")).is_err());
+        assert!(live.update("missing", capsule("missing", "/x")).is_err());
+        live.update("about", capsule("about", "/about-us")).unwrap();
+        assert_eq!(
+            events(&mut receiver),
+            [
+                RegistryEvent::Removed {
+                    uri: "/about".into()
+                },
+                added("/about-us")
+            ]
+        );
+        let after = live.snapshot();
+        assert_eq!(after.get_by_name("about").unwrap().uri, "/about-us");
+        assert!(after.get("/about").is_none());
+        // Snapshots taken before a change never see it
+        assert!(before.get("/about").is_some());
+    }
+
+    #[test]
+    fn remove_and_reload_announce_changes() {
+        let live = LiveRegistry::default();
+        live.add(capsule("about", "/about")).unwrap();
+        let mut receiver = live.subscribe();
+        assert!(live.remove("/missing").is_none());
+        assert_eq!(live.remove("/about").unwrap().name, "about");
+        live.reload(CapsuleRegistry::default());
+        assert_eq!(
+            events(&mut receiver),
+            [
+                RegistryEvent::Removed {
+                    uri: "/about".into()
+                },
+                RegistryEvent::Reloaded
+            ]
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use anyhow::Context;
+use rocket::response::Redirect;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Component, Path};
+
+/// The HTTP status of a redirect.
+///
+/// Serialized as the bare status code (`301`, `302`, `307` or `308`).
+///
+/// # Variants
+///
+/// - `Moved` (`301`): permanent, the default. Search engines transfer ranking to the target.
+/// - `Found` (`302`): temporary.
+/// - `Temporary` (`307`): temporary, and the method and body are preserved.
+/// - `Permanent` (`308`): permanent, and the method and body are preserved.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, Hash)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum RedirectStatus {
+    #[default]
+    Moved,
+    Found,
+    Temporary,
+    Permanent,
+}
+
+impl RedirectStatus {
+    pub fn code(self) -> u16 {
+        match self {
+            RedirectStatus::Moved => 301,
+            RedirectStatus::Found => 302,
+            RedirectStatus::Temporary => 307,
+            RedirectStatus::Permanent => 308,
+        }
+    }
+
+    /// Builds the Rocket responder redirecting to `to` with this status.
+    pub fn redirect(self, to: impl Into<String>) -> Redirect {
+        let to = to.into();
+        match self {
+            RedirectStatus::Moved => Redirect::moved(to),
+            RedirectStatus::Found => Redirect::found(to),
+            RedirectStatus::Temporary => Redirect::temporary(to),
+            RedirectStatus::Permanent => Redirect::permanent(to),
+        }
+    }
+}
+
+impl TryFrom<u16> for RedirectStatus {
+    type Error = String;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        match code {
+            301 => Ok(RedirectStatus::Moved),
+            302 => Ok(RedirectStatus::Found),
+            307 => Ok(RedirectStatus::Temporary),
+            308 => Ok(RedirectStatus::Permanent),
+            other => Err(format!(
+                "unsupported redirect status {other} (expected 301, 302, 307 or 308)"
+            )),
+        }
+    }
+}
+
+impl From<RedirectStatus> for u16 {
+    fn from(status: RedirectStatus) -> Self {
+        status.code()
+    }
+}
+
+/// A single redirect from one URI to another.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedirectRule {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub status: RedirectStatus,
+}
+
+impl RedirectRule {
+    pub fn new<F: Into<String>, T: Into<String>>(from: F, to: T, status: RedirectStatus) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            status,
+        }
+    }
+
+    /// Whether `from` is a pattern rather than a path: a `*` segment (the splat) matches the
+    /// rest of the path, a `:name` segment matches any one non-empty segment.
+    pub fn is_pattern(&self) -> bool {
+        let mut segments = self.from.split('/');
+        segments.any(|segment| segment == "*" || segment.starts_with(':'))
+    }
+
+    /// Returns the redirect of `uri` when it matches `from`.
+    ///
+    /// Placeholders of `to` are filled with what their segments matched: `:splat` with the rest of
+    /// the path, `:name` with the `:name` segment. Unknown placeholders are kept as they are.
+    ///
+    /// ```text
+    /// /blog/*          /articles/:splat   matches /blog/2024/hello -> /articles/2024/hello
+    /// /posts/:id/edit  /p/:id             matches /posts/42/edit   -> /p/42
+    /// ```
+    pub fn apply(&self, uri: &str) -> Option<RedirectRule> {
+        let mut captures: Vec<(&str, &str)> = Vec::new();
+        let mut segments = uri.split('/');
+        let mut consumed = 0;
+        for part in self.from.split('/') {
+            if part == "*" {
+                // "/blog/*" also matches "/blog"
+                captures.push(("splat", uri.get(consumed..).unwrap_or_default()));
+                return Some(self.to_target(uri, &captures));
+            }
+            let segment = segments.next()?;
+            consumed += segment.len() + 1;
+            match part.strip_prefix(':') {
+                Some(name) if !segment.is_empty() => captures.push((name, segment)),
+                None if part == segment => {}
+                _ => return None,
+            }
+        }
+        match segments.next() {
+            Some(_) => None,
+            None => Some(self.to_target(uri, &captures)),
+        }
+    }
+
+    fn to_target(&self, uri: &str, captures: &[(&str, &str)]) -> RedirectRule {
+        RedirectRule::new(uri, fill_placeholders(&self.to, captures), self.status)
+    }
+}
+
+/// Replaces the `:name` placeholders of `target` with their capture, in a single pass.
+fn fill_placeholders(target: &str, captures: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(target.len());
+    let mut rest = target;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(after.len());
+        match captures.iter().find(|(name, _)| *name == &after[..end]) {
+            Some((_, value)) if end > 0 => out.push_str(value),
+            _ => {
+                out.push(':');
+                out.push_str(&after[..end]);
+            }
+        }
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Loads redirect rules in bulk.
+///
+/// The format is picked from the extension:
+///
+/// - `.toml`: an array of tables, `[[redirect]]` with `from`, `to` and an optional `status`.
+/// - `.json`: an array of `{ "from", "to", "status" }` objects.
+/// - anything else (typically `_redirects`): one rule per line, `from to [status]`,
+///   blank lines and `#` comments ignored. This is the format most static hosts understand.
+///
+/// # Errors
+///
+/// Fails when the file cannot be read or a rule is malformed; the error names the offending line.
+pub fn load_redirects(path: impl AsRef<Path>) -> anyhow::Result<Vec<RedirectRule>> {
+    #[derive(Deserialize)]
+    struct TomlRedirects {
+        #[serde(default)]
+        redirect: Vec<RedirectRule>,
+    }
+
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read redirects file {}", path.display()))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => Ok(toml::from_str::<TomlRedirects>(&source)?.redirect),
+        Some("json") => Ok(serde_json::from_str(&source)?),
+        _ => parse_redirect_lines(&source)
+            .with_context(|| format!("invalid redirects file {}", path.display())),
+    }
+}
+
+/// Parses `_redirects`-style lines: `from to [status]`.
+///
+/// A `#` starts a comment only at the beginning of a line or after whitespace, so fragments in
+/// targets (`/old /new#section`) are kept.
+fn parse_redirect_lines(source: &str) -> anyhow::Result<Vec<RedirectRule>> {
+    let mut rules = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let status = match parts.get(2) {
+            Some(code) => code
+                .trim_end_matches('!')
+                .parse::<u16>()
+                .map_err(anyhow::Error::from)
+                .and_then(|code| RedirectStatus::try_from(code).map_err(anyhow::Error::msg))
+                .with_context(|| format!("line {}: invalid status `{code}`", index + 1))?,
+            None => RedirectStatus::default(),
+        };
+        match parts.as_slice() {
+            [from, to] | [from, to, _] => rules.push(RedirectRule::new(*from, *to, status)),
+            _ => anyhow::bail!("line {}: expected `from to [status]`", index + 1),
+        }
+    }
+    Ok(rules)
+}
+
+/// Cuts `line` at the first `#` that starts it or follows whitespace.
+fn strip_comment(line: &str) -> &str {
+    let mut previous = None;
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+/// Renders rules in the `_redirects` format understood by most static hosts.
+pub fn redirects_file<'a>(rules: impl IntoIterator<Item = &'a RedirectRule>) -> String {
+    rules
+        .into_iter()
+        .map(|r| format!("{} {} {}\n", r.from, r.to, r.status.code()))
+        .collect()
+}
+
+/// Renders a standalone HTML page sending browsers (and crawlers) to `to`.
+///
+/// Used by the static export, where no server can answer with a real redirect.
+pub fn meta_refresh_page(to: &str) -> String {
+    let to = to
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Redirecting…</title>
+<link rel="canonical" href="{to}">
+<meta name="robots" content="noindex">
+<meta http-equiv="refresh" content="0; url={to}">
+</head>
+<body>
+<p>This page has moved to <a href="{to}">{to}</a>.</p>
+</body>
+</html>
+"#
+    )
+}
+
+/// What to do with the trailing slash of request paths.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    /// Leave paths alone (the default).
+    #[default]
+    Keep,
+    /// `/about/` redirects to `/about`.
+    Strip,
+    /// `/about` redirects to `/about/`.
+    Always,
+}
+
+/// Canonical form of request paths.
+///
+/// When a `GET` request path differs from its normalized form, the visitor is redirected
+/// (`301`) to the normalized path, so each page is reachable at a single URL.
+///
+/// ```toml
+/// [normalization]
+/// trailing_slash = "strip"
+/// lowercase = true
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct UrlNormalization {
+    pub trailing_slash: TrailingSlash,
+    pub lowercase: bool,
+}
+
+impl UrlNormalization {
+    /// Whether a page served at `uri` can be reached. With `lowercase`, requests for a path
+    /// holding uppercase letters are redirected to its lowercase form, so such pages cannot.
+    pub fn reaches(&self, uri: &str) -> bool {
+        !self.lowercase || !uri.chars().any(char::is_uppercase)
+    }
+
+    /// Returns the canonical form of `path`. The root path `/` is never changed.
+    pub fn normalize<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(path);
+        if self.lowercase && out.chars().any(char::is_uppercase) {
+            out = Cow::Owned(out.to_lowercase());
+        }
+        if out == "/" {
+            return out;
+        }
+        match self.trailing_slash {
+            TrailingSlash::Keep => out,
+            TrailingSlash::Strip if out.ends_with('/') => {
+                let trimmed = out.trim_end_matches('/');
+                Cow::Owned(if trimmed.is_empty() { "/" } else { trimmed }.to_string())
+            }
+            TrailingSlash::Always if !out.ends_with('/') => Cow::Owned(format!("{out}/")),
+            _ => out,
+        }
+    }
+}
+
+/// Where a redirect capsule sends its visitors.
+///
+/// ```toml
+/// name = "old-blog"
+/// uri = "/blog"
+/// redirect = { to = "/articles", status = 308 }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedirectTarget {
+    pub to: String,
+    #[serde(default)]
+    pub status: RedirectStatus,
+}
+
+/// How redirects are written by [`export_redirects`].
+///
+/// # Variants
+///
+/// - `MetaRefresh`: One `index.html` per source URI, made with [`meta_refresh_page`]. Works on any host.
+/// - `RedirectsFile`: A single `_redirects` file (see [`redirects_file`]), for hosts that understand it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectExport {
+    #[default]
+    MetaRefresh,
+    RedirectsFile,
+}
+
+/// Writes `rules` into the static export directory `out_dir`.
+///
+/// With [`RedirectExport::MetaRefresh`], rules whose source contains a wildcard (`*`) or a
+/// placeholder (`:name`) cannot be expressed as files and are skipped.
+///
+/// # Returns
+///
+/// The number of rules written.
+///
+/// # Errors
+///
+/// Fails when a rule source contains `..` or other components that would write outside
+/// `out_dir`, or when a file cannot be written.
+pub fn export_redirects(
+    rules: &[RedirectRule],
+    out_dir: impl AsRef<Path>,
+    style: RedirectExport,
+) -> anyhow::Result<usize> {
+    let out_dir = out_dir.as_ref();
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("cannot create export directory {}", out_dir.display()))?;
+    match style {
+        RedirectExport::RedirectsFile => {
+            std::fs::write(out_dir.join("_redirects"), redirects_file(rules))?;
+            Ok(rules.len())
+        }
+        RedirectExport::MetaRefresh => {
+            let mut written = 0;
+            for rule in rules {
+                if rule.is_pattern() {
+                    continue;
+                }
+                let relative = Path::new(rule.from.trim_matches('/'));
+                if relative
+                    .components()
+                    .any(|component| !matches!(component, Component::Normal(_)))
+                {
+                    anyhow::bail!("refusing to export redirect from `{}`", rule.from);
+                }
+                let dir = out_dir.join(relative);
+                std::fs::create_dir_all(&dir)?;
+                std::fs::write(dir.join("index.html"), meta_refresh_page(&rule.to))?;
+                written += 1;
+            }
+            Ok(written)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_redirect_lines_reads_rules_and_statuses() {
+        let rules = parse_redirect_lines("/a /b\n/c /d 302\n/e /f 308!\n").unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                RedirectRule::new("/a", "/b", RedirectStatus::default()),
+                RedirectRule::new("/c", "/d", RedirectStatus::Found),
+                RedirectRule::new("/e", "/f", RedirectStatus::Permanent),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_redirect_lines_skips_comments_but_keeps_fragments() {
+        let source = "# moved pages\n\n/old /new#section 301 # trailing note\n/x /y#top\n";
+        let rules = parse_redirect_lines(source).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                RedirectRule::new("/old", "/new#section", RedirectStatus::Moved),
+                RedirectRule::new("/x", "/y#top", RedirectStatus::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_redirect_lines_rejects_bad_lines() {
+        let error = parse_redirect_lines("/a /b 200").unwrap_err();
+        assert!(error.to_string().contains("line 1: invalid status `200`"));
+        let error = parse_redirect_lines("/a /b\n/lonely").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+        assert!(parse_redirect_lines("/a /b 301 extra").is_err());
+    }
+
+    #[test]
+    fn apply_matches_splats_and_placeholders() {
+        let splat = RedirectRule::new("/blog/*", "/articles/:splat", RedirectStatus::Found);
+        assert!(splat.is_pattern());
+        assert_eq!(
+            splat.apply("/blog/2024/hello"),
+            Some(RedirectRule::new(
+                "/blog/2024/hello",
+                "/articles/2024/hello",
+                RedirectStatus::Found
+            ))
+        );
+        assert_eq!(splat.apply("/blog").unwrap().to, "/articles/");
+        assert_eq!(splat.apply("/blogs/x"), None);
+
+        let named = RedirectRule::new("/posts/:id/edit", "/p/:id?from=:src", RedirectStatus::Moved);
+        assert_eq!(named.apply("/posts/42/edit").unwrap().to, "/p/42?from=:src");
+        assert_eq!(named.apply("/posts//edit"), None);
+        assert_eq!(named.apply("/posts/42"), None);
+        assert_eq!(named.apply("/posts/42/edit/more"), None);
+    }
+
+    #[test]
+    fn fill_placeholders_keeps_schemes_and_unknown_names() {
+        let captures = [("splat", "a/:b")];
+        assert_eq!(
+            fill_placeholders("https://example.com/:splat/:other", &captures),
+            "https://example.com/a/:b/:other"
+        );
+        assert!(!RedirectRule::new("/a", "/b/:splat", RedirectStatus::Moved).is_pattern());
+    }
+
+    #[test]
+    fn lowercase_normalization_cannot_reach_uppercase_uris() {
+        let lowercase = UrlNormalization {
+            lowercase: true,
+            ..Default::default()
+        };
+        assert_eq!(lowercase.normalize("/About"), "/about");
+        assert!(lowercase.reaches("/about"));
+        assert!(!lowercase.reaches("/About"));
+        assert!(UrlNormalization::default().reaches("/About"));
+    }
+}
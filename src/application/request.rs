@@ -0,0 +1,258 @@
+use super::auth::Identity;
+use super::csrf::{CSRF_COOKIE, CsrfToken};
+use super::session::{Session, SessionConfig};
+use crate::seo::html_escape;
+use rocket::data::{self, Data, FromData, ToByteUnit};
+use rocket::http::{ContentType, RawStr, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Request headers copied into [`RequestInfo::headers`], by lowercase name.
+pub const EXPOSED_HEADERS: &[&str] = &[
+    "accept",
+    "accept-language",
+    "dnt",
+    "referer",
+    "user-agent",
+    "x-requested-with",
+];
+
+/// Everything the capsule handlers know about the visitor making a request.
+///
+/// `Visitor` bundles the [`CsrfToken`], the [`Session`], the [`Identity`] and the [`RequestInfo`] request guards,
+/// none of which can fail, so it never fails either.
+pub struct Visitor<'r> {
+    pub csrf: CsrfToken,
+    pub session: Session<'r>,
+    pub identity: Identity,
+    pub request: RequestInfo,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Visitor<'r> {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let csrf = rocket::outcome::try_outcome!(req.guard::<CsrfToken>().await);
+        let session = rocket::outcome::try_outcome!(req.guard::<Session<'r>>().await);
+        let identity = rocket::outcome::try_outcome!(req.guard::<Identity>().await);
+        let request = rocket::outcome::try_outcome!(req.guard::<RequestInfo>().await);
+        Outcome::Success(Visitor {
+            csrf,
+            session,
+            identity,
+            request,
+        })
+    }
+}
+
+/// What templates may know about the current request, exposed to them as `request`.
+///
+/// The fields hold the values as the client sent them. Templates are rendered without
+/// autoescaping, so the client-controlled strings (`path`, `query`, `headers` and `cookies`)
+/// are HTML-escaped when serialized and can be printed as they are:
+///
+/// ```text
+/// {% if request.query.page %}Page {{ request.query.page }}{% endif %}
+/// {{ request.headers["user-agent"] }} from {{ request.client_ip }}
+/// ```
+///
+/// # Fields
+///
+/// * `method` - The request method, e.g. `"GET"`.
+/// * `path` - The request path, as sent by the client.
+/// * `query` - The URL-decoded query parameters. When a parameter repeats, the last value wins.
+/// * `headers` - The headers listed in [`EXPOSED_HEADERS`] that the client sent, by lowercase name.
+/// * `cookies` - The cookies sent by the client, except the session, flash and CSRF cookies, which are encrypted.
+/// * `client_ip` - The client IP address, honoring Rocket's `ip_header` (`X-Real-IP` by default), if known.
+/// * `host` - The `Host` of the request, if any.
+/// * `scheme` - `"https"` when TLS is enabled or a proxy says so with `X-Forwarded-Proto`, `"http"` otherwise.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RequestInfo {
+    pub method: String,
+    #[serde(serialize_with = "escaped")]
+    pub path: String,
+    #[serde(serialize_with = "escaped_map")]
+    pub query: BTreeMap<String, String>,
+    #[serde(serialize_with = "escaped_map")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(serialize_with = "escaped_map")]
+    pub cookies: BTreeMap<String, String>,
+    pub client_ip: Option<String>,
+    pub host: Option<String>,
+    pub scheme: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestInfo {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let query = req
+            .uri()
+            .query()
+            .map(|query| {
+                query
+                    .segments()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let headers = EXPOSED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = req.headers().get_one(name)?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect();
+        let hidden: Vec<&str> = match req.rocket().state::<SessionConfig>() {
+            Some(config) => vec![&config.cookie_name, &config.flash_cookie, CSRF_COOKIE],
+            None => vec![CSRF_COOKIE],
+        };
+        let cookies = req
+            .cookies()
+            .iter()
+            .filter(|cookie| !hidden.contains(&cookie.name()))
+            .map(|cookie| (cookie.name().to_string(), cookie.value().to_string()))
+            .collect();
+        let forwarded_https = req
+            .headers()
+            .get_one("X-Forwarded-Proto")
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+        let scheme = if req.rocket().config().tls_enabled() || forwarded_https {
+            "https"
+        } else {
+            "http"
+        };
+        Outcome::Success(RequestInfo {
+            method: req.method().as_str().to_string(),
+            path: req.uri().path().as_str().to_string(),
+            query,
+            headers,
+            cookies,
+            client_ip: req.client_ip().map(|ip| ip.to_string()),
+            host: req.host().map(|host| host.to_string()),
+            scheme: scheme.to_string(),
+        })
+    }
+}
+
+fn escaped<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&html_escape(value))
+}
+
+fn escaped_map<S: Serializer>(
+    map: &BTreeMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        map.iter()
+            .map(|(key, value)| (html_escape(key), html_escape(value))),
+    )
+}
+
+/// A request body, kept raw and parsed into fields.
+///
+/// JSON objects (`application/json`) are used as-is; any other body is parsed as
+/// `application/x-www-form-urlencoded`. The body size is bounded by Rocket's `string` limit.
+#[derive(Debug, Clone, Default)]
+pub struct Submission {
+    pub raw: String,
+    pub fields: Map<String, Value>,
+}
+
+impl Submission {
+    /// Returns the field `name` when it is a string.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).and_then(Value::as_str)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for Submission {
+    type Error = std::io::Error;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("string").unwrap_or(8.kibibytes());
+        let raw = match data.open(limit).into_string().await {
+            Ok(raw) if raw.is_complete() => raw.into_inner(),
+            Ok(_) => {
+                let error = std::io::Error::other("request body exceeds the `string` limit");
+                return data::Outcome::Error((Status::PayloadTooLarge, error));
+            }
+            Err(error) => return data::Outcome::Error((Status::BadRequest, error)),
+        };
+        let fields = parse_body(req.content_type(), &raw);
+        data::Outcome::Success(Submission { raw, fields })
+    }
+}
+
+/// Parses a request body into a JSON object according to its content type.
+pub fn parse_body(content_type: Option<&ContentType>, body: &str) -> Map<String, Value> {
+    if content_type.is_some_and(|ct| ct.is_json()) {
+        return match serde_json::from_str::<Value>(body) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+    }
+    parse_form(body)
+}
+
+/// Parses an `application/x-www-form-urlencoded` body into a JSON object.
+///
+/// Keys and values are URL-decoded; when a key repeats, the last value wins. A body
+/// that is not form encoded yields an object without meaningful fields.
+pub fn parse_form(body: &str) -> Map<String, Value> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| {
+            (
+                RawStr::new(key).url_decode_lossy().into_owned(),
+                RawStr::new(value).url_decode_lossy().into_owned().into(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_info_escapes_client_strings_for_templates() {
+        let mut info = RequestInfo {
+            method: "GET".into(),
+            path: "/search/'x'".into(),
+            scheme: "http".into(),
+            ..Default::default()
+        };
+        info.query.insert("q".into(), "<script>".into());
+        info.headers.insert("referer".into(), "\"><b>".into());
+        info.cookies.insert("<k>".into(), "a&b".into());
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["path"], "/search/&#39;x&#39;");
+        assert_eq!(value["query"]["q"], "&lt;script&gt;");
+        assert_eq!(value["headers"]["referer"], "&quot;&gt;&lt;b&gt;");
+        assert_eq!(value["cookies"]["&lt;k&gt;"], "a&amp;b");
+        // The Rust side keeps the raw values
+        assert_eq!(info.query["q"], "<script>");
+    }
+
+    #[test]
+    fn parse_form_decodes_pairs() {
+        let fields = parse_form("name=Ada+Lovelace&tag=a%26b&tag=last&junk");
+        assert_eq!(fields["name"], "Ada Lovelace");
+        assert_eq!(fields["tag"], "last");
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn parse_body_reads_json_objects_only() {
+        let json = ContentType::JSON;
+        assert_eq!(parse_body(Some(&json), r#"{"a":1}"#)["a"], 1);
+        assert!(parse_body(Some(&json), "[1]").is_empty());
+        assert_eq!(parse_body(Some(&ContentType::Form), "a=1")["a"], "1");
+    }
+}
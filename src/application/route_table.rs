@@ -0,0 +1,174 @@
+use super::{CapsuleRegistry, Method};
+use serde::Serialize;
+use tabled::derive::display;
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
+
+/// One line of the route table: what a site serves at a URI.
+///
+/// Redirect capsules show the redirect (`→ /new (301)`) in place of a template.
+#[derive(Debug, Clone, Serialize, Tabled, PartialEq, Eq)]
+#[tabled(display(Option, "display::option", "-"))]
+pub struct RouteRow {
+    pub method: String,
+    pub uri: String,
+    pub name: String,
+    pub template: String,
+    pub description: String,
+    pub source: Option<String>,
+}
+
+/// The column the route table is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RouteSort {
+    #[default]
+    Uri,
+    Name,
+    Method,
+    Template,
+    Source,
+}
+
+/// Which capsules the route table lists.
+///
+/// # Fields
+///
+/// * `method` - Only capsules answering this method.
+/// * `search` - Only capsules whose URI, name or template contains this text, ignoring case.
+#[derive(Debug, Clone, Default)]
+pub struct RouteFilter {
+    pub method: Option<Method>,
+    pub search: Option<String>,
+}
+
+impl RouteFilter {
+    fn matches(&self, row: &RouteRow, method: Method) -> bool {
+        if self.method.is_some_and(|m| m != method) {
+            return false;
+        }
+        match &self.search {
+            Some(search) => {
+                let search = search.to_lowercase();
+                [&row.uri, &row.name, &row.template]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&search))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Lists the capsules of `registry` that pass `filter`, sorted by `sort` then by URI.
+pub fn route_rows(
+    registry: &CapsuleRegistry,
+    filter: &RouteFilter,
+    sort: RouteSort,
+) -> Vec<RouteRow> {
+    let mut rows: Vec<RouteRow> = registry
+        .all()
+        .filter_map(|(_, capsule)| {
+            let template = match &capsule.redirect {
+                Some(target) => format!("→ {} ({})", target.to, target.status.code()),
+                None => capsule.template.clone(),
+            };
+            let row = RouteRow {
+                method: format!("{:?}", capsule.method),
+                uri: capsule.uri.clone(),
+                name: capsule.name.clone(),
+                template,
+                description: capsule.description.clone(),
+                source: capsule
+                    .source
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            };
+            filter.matches(&row, capsule.method).then_some(row)
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        let key = |row: &RouteRow| match sort {
+            RouteSort::Uri => String::new(),
+            RouteSort::Name => row.name.clone(),
+            RouteSort::Method => row.method.clone(),
+            RouteSort::Template => row.template.clone(),
+            RouteSort::Source => row.source.clone().unwrap_or_default(),
+        };
+        key(a).cmp(&key(b)).then_with(|| a.uri.cmp(&b.uri))
+    });
+    rows
+}
+
+/// Renders rows as a table for the terminal.
+pub fn render_table(rows: &[RouteRow]) -> String {
+    Table::new(rows).with(Style::rounded()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::Capsule;
+    use crate::application::redirects::{RedirectStatus, RedirectTarget};
+
+    fn registry() -> CapsuleRegistry {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(Capsule::new("home", "Home", "/", "index", Method::GET));
+        let mut contact = Capsule::new("contact", "Contact", "/contact", "form", Method::POST);
+        contact.source = Some("capsules/contact.toml".into());
+        registry.add(contact);
+        let mut old = Capsule::new("old-blog", "Old blog", "/blog", "", Method::GET);
+        old.redirect = Some(RedirectTarget {
+            to: "/articles".into(),
+            status: RedirectStatus::Permanent,
+        });
+        registry.add(old);
+        registry
+    }
+
+    fn uris(rows: &[RouteRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.uri.as_str()).collect()
+    }
+
+    #[test]
+    fn route_rows_show_redirects_and_sources() {
+        let rows = route_rows(&registry(), &RouteFilter::default(), RouteSort::Uri);
+        assert_eq!(uris(&rows), ["/", "/blog", "/contact"]);
+        assert_eq!(rows[1].template, "→ /articles (308)");
+        assert_eq!(rows[2].method, "POST");
+        assert_eq!(rows[2].source.as_deref(), Some("capsules/contact.toml"));
+    }
+
+    #[test]
+    fn route_rows_filter_and_sort() {
+        let registry = registry();
+        let get = RouteFilter {
+            method: Some(Method::GET),
+            search: None,
+        };
+        assert_eq!(
+            uris(&route_rows(&registry, &get, RouteSort::Uri)),
+            ["/", "/blog"]
+        );
+        let search = RouteFilter {
+            method: None,
+            search: Some("FORM".into()),
+        };
+        assert_eq!(
+            uris(&route_rows(&registry, &search, RouteSort::Uri)),
+            ["/contact"]
+        );
+        let by_name = route_rows(&registry, &RouteFilter::default(), RouteSort::Name);
+        assert_eq!(uris(&by_name), ["/contact", "/", "/blog"]);
+    }
+
+    #[test]
+    fn render_table_shows_missing_sources_as_dashes() {
+        let rows = route_rows(&registry(), &RouteFilter::default(), RouteSort::Uri);
+        let table = render_table(&rows);
+        assert!(table.contains("capsules/contact.toml"));
+        assert!(
+            table
+                .lines()
+                .any(|line| line.contains("index") && line.contains(" - "))
+        );
+    }
+}
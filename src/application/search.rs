@@ -0,0 +1,434 @@
+use super::{Capsule, CapsuleRegistry, Method};
+use crate::seo::html_escape;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The query parameter read by search capsules: `/search?q=rocket`.
+pub const QUERY_PARAM: &str = "q";
+
+/// How much a term weighs depending on where it appears in a page.
+const TITLE_WEIGHT: f32 = 5.0;
+const KEYWORD_WEIGHT: f32 = 3.0;
+const DESCRIPTION_WEIGHT: f32 = 2.0;
+const CONTENT_WEIGHT: f32 = 1.0;
+/// Matching only the beginning of a term (`rock` for `rocket`) counts half.
+const PREFIX_FACTOR: f32 = 0.5;
+
+/// The number of words shown in a snippet, and before the first match.
+const SNIPPET_WORDS: usize = 30;
+const SNIPPET_LEAD: usize = 8;
+
+/// The `data` fields holding the Markdown content of a page.
+const CONTENT_FIELDS: [&str; 2] = ["content", "body"];
+
+/// Turns a capsule into a search page.
+///
+/// ```toml
+/// name = "search"
+/// uri = "/search"
+/// template = "search"
+///
+/// [search]
+/// limit = 20
+/// ```
+///
+/// The template gets `query` (the `q` parameter), `total` (the number of matching pages) and
+/// `results`, the best [`SearchHit`]s first. Only pages in the locale of the search capsule are
+/// searched.
+///
+/// # Fields
+///
+/// * `limit` - The maximum number of results shown (20 by default).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SearchPage {
+    pub limit: usize,
+}
+
+impl Default for SearchPage {
+    fn default() -> Self {
+        Self { limit: 20 }
+    }
+}
+
+/// A page of the site, as stored in a [`SearchIndex`].
+///
+/// # Fields
+///
+/// * `uri` - The URI of the page.
+/// * `title` - The `seo.title` of the capsule, else its `data.title`, else its name.
+/// * `description` - The `seo.description` of the capsule, else its description.
+/// * `locale` - The locale of the capsule, if any.
+/// * `text` - The plain text of the Markdown content of the page (`data.content` or `data.body`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchDocument {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+    pub locale: Option<String>,
+    pub text: String,
+}
+
+/// One result of [`SearchIndex::search`].
+///
+/// `snippet` is HTML: an extract of the page with the matching words wrapped in `<mark>`. Every
+/// other field is plain text here, and HTML-escaped by [`SearchResults::insert_into`], so that
+/// templates, which are rendered without autoescaping, print every field as it is:
+///
+/// ```text
+/// <a href="{{ hit.uri }}">{{ hit.title }}</a> <p>{{ hit.snippet }}</p>
+/// ```
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SearchHit {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+/// The results of a query, as exposed to search templates.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct SearchResults {
+    pub query: String,
+    pub total: usize,
+    pub results: Vec<SearchHit>,
+}
+
+impl SearchResults {
+    /// Adds `query`, `total` and `results` to a template context.
+    ///
+    /// The query and the `uri`, `title` and `description` of the hits are HTML-escaped, the
+    /// snippets being HTML already.
+    pub fn insert_into(&self, ctx: &mut Value) {
+        if let Some(map) = ctx.as_object_mut() {
+            let results: Vec<SearchHit> = self
+                .results
+                .iter()
+                .map(|hit| SearchHit {
+                    uri: html_escape(&hit.uri),
+                    title: html_escape(&hit.title),
+                    description: html_escape(&hit.description),
+                    ..hit.clone()
+                })
+                .collect();
+            map.insert("query".into(), html_escape(&self.query).into());
+            map.insert("total".into(), self.total.into());
+            map.insert(
+                "results".into(),
+                serde_json::to_value(results).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+/// An inverted index of the pages of a site.
+///
+/// Built from the titles, descriptions, `seo.keywords` and Markdown content of the public `GET`
+/// capsules. Redirects, collections, search pages and dynamic URIs (`<param>`) are left out.
+///
+/// The index serializes to JSON, so that exported sites can search on the client side:
+/// `terms` maps every term to the `[document, weight]` pairs of the pages containing it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub documents: Vec<SearchDocument>,
+    pub terms: BTreeMap<String, Vec<(usize, f32)>>,
+}
+
+impl SearchIndex {
+    /// Indexes the pages of `registry`.
+    pub fn build(registry: &CapsuleRegistry) -> Self {
+        let mut index = Self::default();
+        for (_, capsule) in registry.all() {
+            if indexable(capsule) {
+                index.add(capsule);
+            }
+        }
+        index
+    }
+
+    fn add(&mut self, capsule: &Capsule) {
+        let seo = capsule.seo.clone().unwrap_or_default();
+        let title = Some(seo.title)
+            .filter(|title| !title.is_empty())
+            .or_else(|| {
+                capsule
+                    .data
+                    .get("title")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| capsule.name.clone());
+        let description = Some(seo.description)
+            .filter(|description| !description.is_empty())
+            .unwrap_or_else(|| capsule.description.clone());
+        let text = CONTENT_FIELDS
+            .iter()
+            .filter_map(|field| capsule.data.get(*field).and_then(Value::as_str))
+            .map(markdown_text)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut weights: BTreeMap<String, f32> = BTreeMap::new();
+        let mut count = |text: &str, weight: f32| {
+            for term in tokenize(text) {
+                *weights.entry(term).or_default() += weight;
+            }
+        };
+        count(&title, TITLE_WEIGHT);
+        for keyword in &seo.keywords {
+            count(keyword, KEYWORD_WEIGHT);
+        }
+        count(&description, DESCRIPTION_WEIGHT);
+        count(&text, CONTENT_WEIGHT);
+
+        let document = self.documents.len();
+        for (term, weight) in weights {
+            self.terms.entry(term).or_default().push((document, weight));
+        }
+        self.documents.push(SearchDocument {
+            uri: capsule.uri.clone(),
+            title,
+            description,
+            locale: capsule.locale.clone(),
+            text,
+        });
+    }
+
+    /// Finds the pages in `locale` containing every word of `query`, best first.
+    ///
+    /// Words also match the terms they begin with, for half the score. Pages are ranked by the
+    /// weight of the matching terms (title, then keywords, description and content), each
+    /// multiplied by how rare the term is across the site.
+    ///
+    /// # Returns
+    ///
+    /// At most `limit` hits, along with the total number of matching pages.
+    pub fn search(&self, query: &str, locale: Option<&str>, limit: usize) -> SearchResults {
+        let words = tokenize(query);
+        let mut scores: BTreeMap<usize, f32> = BTreeMap::new();
+        for (position, word) in words.iter().enumerate() {
+            let mut word_scores: BTreeMap<usize, f32> = BTreeMap::new();
+            for (term, postings) in self.terms.range(word.clone()..) {
+                if !term.starts_with(word.as_str()) {
+                    break;
+                }
+                let factor = if term == word { 1.0 } else { PREFIX_FACTOR };
+                let idf = (1.0 + self.documents.len() as f32 / postings.len() as f32).ln();
+                for (document, weight) in postings {
+                    *word_scores.entry(*document).or_default() += weight * idf * factor;
+                }
+            }
+            // Every word must match
+            scores = if position == 0 {
+                word_scores
+            } else {
+                word_scores
+                    .into_iter()
+                    .filter_map(|(document, score)| {
+                        Some((document, scores.get(&document)? + score))
+                    })
+                    .collect()
+            };
+        }
+        let mut ranked: Vec<(usize, f32)> = scores
+            .into_iter()
+            .filter(|(document, _)| self.documents[*document].locale.as_deref() == locale)
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        SearchResults {
+            query: query.to_string(),
+            total: ranked.len(),
+            results: ranked
+                .into_iter()
+                .take(limit)
+                .map(|(document, score)| {
+                    let document = &self.documents[document];
+                    let source = if document.text.is_empty() {
+                        &document.description
+                    } else {
+                        &document.text
+                    };
+                    SearchHit {
+                        uri: document.uri.clone(),
+                        title: document.title.clone(),
+                        description: document.description.clone(),
+                        snippet: snippet(source, &words),
+                        score,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Whether a capsule is a page worth indexing.
+fn indexable(capsule: &Capsule) -> bool {
+    capsule.method == Method::GET
+        && capsule.access.is_public()
+        && capsule.redirect.is_none()
+        && capsule.collection.is_none()
+        && capsule.search.is_none()
+        && !capsule.uri.contains('<')
+}
+
+/// The byte ranges of the words of `text`.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(index),
+            (false, Some(from)) => {
+                spans.push((from, index));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        spans.push((from, text.len()));
+    }
+    spans
+}
+
+/// The lowercase words of `text`, single characters excepted.
+fn tokenize(text: &str) -> Vec<String> {
+    word_spans(text)
+        .into_iter()
+        .map(|(start, end)| text[start..end].to_lowercase())
+        .filter(|word| word.chars().count() > 1)
+        .collect()
+}
+
+/// Renders Markdown and keeps the text only.
+fn markdown_text(markdown: &str) -> String {
+    let html = comrak::markdown_to_html(markdown, &comrak::Options::default());
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// An HTML extract of `text` around the first word matching `words`, matches wrapped in `<mark>`.
+fn snippet(text: &str, words: &[String]) -> String {
+    let spans = word_spans(text);
+    if spans.is_empty() {
+        return String::new();
+    }
+    let matches = |word: &str| {
+        let word = word.to_lowercase();
+        words.iter().any(|w| word.starts_with(w.as_str()))
+    };
+    let first = spans
+        .iter()
+        .position(|&(start, end)| matches(&text[start..end]))
+        .unwrap_or(0);
+    let from = first.saturating_sub(SNIPPET_LEAD);
+    let to = (from + SNIPPET_WORDS).min(spans.len());
+
+    let mut out = String::new();
+    if from > 0 {
+        out.push_str("… ");
+    }
+    let mut position = spans[from].0;
+    for &(start, end) in &spans[from..to] {
+        out.push_str(&html_escape(&text[position..start]));
+        let word = html_escape(&text[start..end]);
+        if matches(&text[start..end]) {
+            out.push_str(&format!("<mark>{word}</mark>"));
+        } else {
+            out.push_str(&word);
+        }
+        position = end;
+    }
+    if to < spans.len() {
+        out.push_str(" …");
+    } else {
+        out.push_str(&html_escape(&text[position..]));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seo::Seo;
+
+    fn page(name: &str, uri: &str, title: &str, content: &str) -> Capsule {
+        let mut seo = Seo::new();
+        seo.title = title.into();
+        let mut capsule = Capsule::new(name, "", uri, "page", Method::GET);
+        capsule.seo = Some(seo);
+        capsule.data = serde_json::json!({ "content": content });
+        capsule
+    }
+
+    fn index() -> SearchIndex {
+        let mut registry = CapsuleRegistry::default();
+        registry.add(page("rockets", "/rockets", "Rockets", "How *rockets* fly."));
+        registry.add(page(
+            "ships",
+            "/ships",
+            "Ships <and> boats",
+            "Rockets are not ships.",
+        ));
+        let mut search = Capsule::new("search", "", "/search", "search", Method::GET);
+        search.search = Some(SearchPage::default());
+        registry.add(search);
+        SearchIndex::build(&registry)
+    }
+
+    #[test]
+    fn search_ranks_titles_first_and_requires_every_word() {
+        let index = index();
+        assert_eq!(index.documents.len(), 2);
+        let results = index.search("rocket", None, 10);
+        let uris: Vec<&str> = results.results.iter().map(|hit| hit.uri.as_str()).collect();
+        assert_eq!(uris, ["/rockets", "/ships"]);
+        assert_eq!(index.search("rockets ships", None, 10).total, 1);
+        assert_eq!(index.search("rockets", Some("fr"), 10).total, 0);
+        assert_eq!(index.search("rockets", None, 1).results.len(), 1);
+    }
+
+    #[test]
+    fn snippet_marks_matches_and_escapes_text() {
+        assert_eq!(
+            snippet("Fast <rockets> & ships", &["rock".to_string()]),
+            "Fast &lt;<mark>rockets</mark>&gt; &amp; ships"
+        );
+        assert_eq!(
+            markdown_text("How *rockets* fly & land."),
+            "How rockets fly & land."
+        );
+        assert_eq!(tokenize("A rocket, a Ship!"), ["rocket", "ship"]);
+    }
+
+    #[test]
+    fn insert_into_escapes_everything_but_snippets() {
+        let results = index().search("<b> ships", None, 10);
+        let mut ctx = serde_json::json!({});
+        results.insert_into(&mut ctx);
+        assert_eq!(ctx["query"], "&lt;b&gt; ships");
+        let hit = &ctx["results"][0];
+        assert_eq!(hit["title"], "Ships &lt;and&gt; boats");
+        assert_eq!(hit["snippet"], "Rockets are not <mark>ships</mark>.");
+    }
+}
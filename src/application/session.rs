@@ -0,0 +1,354 @@
+use crate::seo::html_escape;
+use rocket::http::{Cookie, CookieJar, SameSite};
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The `SameSite` attribute applied to session cookies.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSitePolicy {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl From<SameSitePolicy> for SameSite {
+    fn from(policy: SameSitePolicy) -> Self {
+        match policy {
+            SameSitePolicy::Strict => SameSite::Strict,
+            SameSitePolicy::Lax => SameSite::Lax,
+            SameSitePolicy::None => SameSite::None,
+        }
+    }
+}
+
+/// Settings of the cookie-backed visitor sessions.
+///
+/// Every field has a default, so a partial TOML or JSON table is enough:
+///
+/// ```toml
+/// [session]
+/// lifetime = 86400
+/// secure = true
+/// same_site = "strict"
+/// ```
+///
+/// # Fields
+///
+/// * `cookie_name` - Name of the private cookie holding the session values.
+/// * `flash_cookie` - Name of the private cookie holding the pending flash message.
+/// * `lifetime` - Session lifetime in seconds. `0` keeps the session until the browser is closed.
+/// * `path` - The `Path` attribute of both cookies.
+/// * `domain` - The optional `Domain` attribute of both cookies.
+/// * `secure` - Whether the cookies are only sent over HTTPS.
+/// * `http_only` - Whether the cookies are hidden from JavaScript.
+/// * `same_site` - The `SameSite` policy of both cookies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub cookie_name: String,
+    pub flash_cookie: String,
+    pub lifetime: u64,
+    pub path: String,
+    pub domain: Option<String>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSitePolicy,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "jigi_session".into(),
+            flash_cookie: "jigi_flash".into(),
+            lifetime: 60 * 60 * 24 * 7,
+            path: "/".into(),
+            domain: None,
+            secure: false,
+            http_only: true,
+            same_site: SameSitePolicy::Lax,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Builds a cookie carrying `value` with the configured attributes.
+    fn cookie(&self, name: &str, value: String, persistent: bool) -> Cookie<'static> {
+        let mut cookie = Cookie::build((name.to_string(), value))
+            .path(self.path.clone())
+            .secure(self.secure)
+            .http_only(self.http_only)
+            .same_site(self.same_site.into())
+            .build();
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        if persistent && self.lifetime > 0 {
+            cookie.set_max_age(rocket::time::Duration::seconds(self.lifetime as i64));
+        }
+        cookie
+    }
+
+    /// Builds an empty cookie used to remove `name`.
+    fn removal(&self, name: &str) -> Cookie<'static> {
+        let mut cookie = Cookie::build(name.to_string())
+            .path(self.path.clone())
+            .build();
+        if let Some(domain) = &self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie
+    }
+}
+
+/// A one-shot message displayed on the next rendered page, typically after a redirect.
+///
+/// Templates receive it as `flash`, e.g. `{% if flash %}<p class="{{ flash.kind }}">{{ flash.message }}</p>{% endif %}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Flash {
+    /// Free-form category such as `success`, `error` or `info`.
+    pub kind: String,
+    pub message: String,
+}
+
+impl Flash {
+    pub fn new<K: Into<String>, M: Into<String>>(kind: K, message: M) -> Self {
+        Self {
+            kind: kind.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// What is actually stored in the session cookie.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionData {
+    values: Map<String, Value>,
+    /// Unix timestamp after which the session is discarded, if it has a lifetime.
+    expires: Option<i64>,
+}
+
+/// Small per-visitor state kept in a private (encrypted and signed) cookie.
+///
+/// `Session` is a Rocket request guard. It reads the [`SessionConfig`] managed by the
+/// server (or the defaults when none is managed) and never fails: a missing, tampered or
+/// expired cookie simply yields an empty session.
+///
+/// Writes go straight to the cookie jar, so values set while handling a request are sent
+/// back with its response. Templates receive the values as `session` and the pending
+/// flash message as `flash`.
+pub struct Session<'r> {
+    jar: &'r CookieJar<'r>,
+    config: SessionConfig,
+    data: SessionData,
+}
+
+impl<'r> Session<'r> {
+    /// Returns the value stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.data.values.get(key)
+    }
+
+    /// Returns every value of the session.
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.data.values
+    }
+
+    /// Stores `value` under `key`.
+    pub fn set<K: Into<String>, V: Into<Value>>(&mut self, key: K, value: V) {
+        self.data.values.insert(key.into(), value.into());
+        self.save();
+    }
+
+    /// Removes `key` from the session, returning its previous value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let previous = self.data.values.remove(key);
+        self.save();
+        previous
+    }
+
+    /// Drops every value and removes the session cookie.
+    pub fn clear(&mut self) {
+        self.data.values.clear();
+        self.jar
+            .remove_private(self.config.removal(&self.config.cookie_name));
+    }
+
+    /// Queues a flash message for the next rendered page.
+    pub fn flash(&self, flash: &Flash) {
+        let value = serde_json::to_string(flash).unwrap_or_default();
+        self.jar
+            .add_private(self.config.cookie(&self.config.flash_cookie, value, false));
+    }
+
+    /// Returns the pending flash message and removes it, so it is displayed only once.
+    ///
+    /// A flash queued earlier in the same request is kept for the next page.
+    pub fn take_flash(&self) -> Option<Flash> {
+        let incoming = self.jar.get_private(&self.config.flash_cookie)?;
+        let pending = self.jar.get_pending(&self.config.flash_cookie);
+        self.jar
+            .remove_private(self.config.removal(&self.config.flash_cookie));
+        // Queued cookies carry their attributes, unlike the one sent by the browser
+        if let Some(queued) = pending.filter(|pending| *pending != incoming) {
+            self.jar.add_private(queued);
+        }
+        serde_json::from_str(incoming.value()).ok()
+    }
+
+    /// Writes the session back to its cookie, refreshing its expiry.
+    fn save(&mut self) {
+        self.data.expires = (self.config.lifetime > 0)
+            .then(|| chrono::Utc::now().timestamp() + self.config.lifetime as i64);
+        let value = serde_json::to_string(&self.data).unwrap_or_default();
+        self.jar
+            .add_private(self.config.cookie(&self.config.cookie_name, value, true));
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Session<'r> {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let config = req
+            .rocket()
+            .state::<SessionConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let jar = req.cookies();
+        let now = chrono::Utc::now().timestamp();
+        let data = jar
+            .get_private(&config.cookie_name)
+            .and_then(|cookie| serde_json::from_str::<SessionData>(cookie.value()).ok())
+            .filter(|data| data.expires.is_none_or(|expires| expires > now))
+            .unwrap_or_default();
+        Outcome::Success(Session { jar, config, data })
+    }
+}
+
+/// Declarative side effects of a successful `POST`, `PUT` or `DELETE` on a capsule.
+///
+/// String values in `session` and the flash message may reference submitted form fields
+/// with `{field}` placeholders, e.g. `"Thanks {name}, we will get back to you."`. The values
+/// are HTML-escaped, in the flash message and in the session alike, as templates render them
+/// as is; placeholders inside submitted values are not expanded.
+///
+/// ```toml
+/// [actions]
+/// redirect = "/contact"
+/// flash = { kind = "success", message = "Saved successfully" }
+/// session = { newsletter = true, email = "{email}" }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostActions {
+    /// Values stored in the visitor session.
+    pub session: Map<String, Value>,
+    /// Flash message shown on the next rendered page.
+    pub flash: Option<Flash>,
+    /// Where to send the visitor afterwards (`303 See Other`), instead of rendering the capsule.
+    pub redirect: Option<String>,
+}
+
+impl PostActions {
+    /// Returns `true` when no action is configured.
+    pub fn is_empty(&self) -> bool {
+        self.session.is_empty() && self.flash.is_none() && self.redirect.is_none()
+    }
+
+    /// Applies the configured actions to `session`, filling placeholders from `form`.
+    pub fn apply(&self, session: &mut Session<'_>, form: &Map<String, Value>) {
+        for (key, value) in &self.session {
+            let value = match value {
+                Value::String(s) => Value::String(interpolate(s, form)),
+                other => other.clone(),
+            };
+            session.set(key.clone(), value);
+        }
+        if let Some(flash) = &self.flash {
+            session.flash(&Flash::new(
+                interpolate(&flash.kind, form),
+                interpolate(&flash.message, form),
+            ));
+        }
+    }
+}
+
+/// Replaces `{field}` placeholders in `template` with the matching form values, HTML-escaped.
+///
+/// The template is read once, so a value containing `{other}` is inserted as is. Unknown
+/// placeholders are left untouched.
+fn interpolate(template: &str, form: &Map<String, Value>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest[1..]
+            .find(['{', '}'])
+            .filter(|&end| rest.as_bytes()[end + 1] == b'}')
+            .and_then(|end| Some((form.get(&rest[1..end + 1])?, end + 2)));
+        match value {
+            Some((value, len)) => {
+                match value {
+                    Value::String(s) => out.push_str(&html_escape(s)),
+                    other => out.push_str(&html_escape(&other.to_string())),
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn form(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn interpolate_fills_placeholders() {
+        let form = form(json!({ "name": "Ada", "count": 3 }));
+        assert_eq!(
+            interpolate("Thanks {name}, {count} items", &form),
+            "Thanks Ada, 3 items"
+        );
+    }
+
+    #[test]
+    fn interpolate_leaves_unknown_placeholders() {
+        let form = form(json!({ "name": "Ada" }));
+        assert_eq!(interpolate("{name} {email}", &form), "Ada {email}");
+        assert_eq!(interpolate("{{name}} {", &form), "{Ada} {");
+    }
+
+    #[test]
+    fn interpolate_escapes_html() {
+        let form = form(json!({ "name": "<script>alert('x')</script>" }));
+        assert_eq!(
+            interpolate("Hi {name}", &form),
+            "Hi &lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn interpolate_does_not_expand_submitted_placeholders() {
+        let form = form(json!({ "a": "{b}", "b": "<img src=x onerror=alert(1)>" }));
+        assert_eq!(
+            interpolate("{a} {b}", &form),
+            "{b} &lt;img src=x onerror=alert(1)&gt;"
+        );
+    }
+}
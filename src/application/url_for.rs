@@ -0,0 +1,152 @@
+use super::live::LiveRegistry;
+use rocket::http::RawStr;
+use rocket_dyn_templates::tera::{self, Function, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Fills the `<param>` and `<param..>` segments of a capsule URI.
+///
+/// `<param>` values are percent-encoded as a single segment; `<param..>` values may contain `/`
+/// and only have their segments encoded. Parameters that do not appear in the URI are appended
+/// as a query string, in name order.
+///
+/// # Errors
+///
+/// Fails, naming the parameter, when a segment has no value.
+pub fn fill_route(uri: &str, params: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut used = Vec::new();
+    let mut segments = Vec::new();
+    for segment in uri.split('/') {
+        let Some(param) = segment.strip_prefix('<').and_then(|s| s.strip_suffix('>')) else {
+            segments.push(segment.to_string());
+            continue;
+        };
+        let (name, multi) = match param.strip_suffix("..") {
+            Some(name) => (name, true),
+            None => (param, false),
+        };
+        let value = params
+            .get(name)
+            .ok_or_else(|| format!("missing route parameter `{name}` for `{uri}`"))?;
+        used.push(name);
+        segments.push(if multi {
+            value
+                .split('/')
+                .map(|s| RawStr::new(s).percent_encode().to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        } else {
+            RawStr::new(value).percent_encode().to_string()
+        });
+    }
+    let mut url = segments.join("/");
+    let query: Vec<String> = params
+        .iter()
+        .filter(|(name, _)| !used.contains(&name.as_str()))
+        .map(|(name, value)| {
+            format!(
+                "{}={}",
+                RawStr::new(name).percent_encode(),
+                RawStr::new(value).percent_encode()
+            )
+        })
+        .collect();
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+    Ok(url)
+}
+
+/// The `url_for` template function: `{{ url_for(name="post", slug=post.slug) }}`.
+///
+/// `name` is the capsule name; every other argument is a route parameter (see
+/// [`CapsuleRegistry::url_for`](super::CapsuleRegistry::url_for)). Unknown names and missing
+/// parameters fail the render, so `check` catches broken links before deploying.
+///
+/// Names are resolved against the registry as it is when the template renders. The server installs
+/// it automatically. For offline rendering, register it on the engine:
+///
+/// ```ignore
+/// let registry = Arc::new(LiveRegistry::new(registry));
+/// engine.register_function("url_for", UrlFor::new(registry.clone()));
+/// ```
+pub struct UrlFor {
+    registry: Arc<LiveRegistry>,
+}
+
+impl UrlFor {
+    pub fn new(registry: Arc<LiveRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl Function for UrlFor {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let name = args
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| tera::Error::msg("function `url_for` expects a `name` string"))?;
+        let params = args
+            .iter()
+            .filter(|(key, _)| key.as_str() != "name")
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        self.registry
+            .snapshot()
+            .url_for(name, &params)
+            .map(Value::from)
+            .map_err(|error| tera::Error::msg(format!("function `url_for`: {error}")))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn fill_route_encodes_segments() {
+        let single = params(&[("slug", "a/b c")]);
+        assert_eq!(
+            fill_route("/blog/<slug>", &single).unwrap(),
+            "/blog/a%2Fb%20c"
+        );
+        let multi = params(&[("path", "docs/a b")]);
+        assert_eq!(
+            fill_route("/files/<path..>", &multi).unwrap(),
+            "/files/docs/a%20b"
+        );
+    }
+
+    #[test]
+    fn fill_route_appends_unused_params_as_query() {
+        let extra = params(&[("slug", "x"), ("page", "2"), ("q", "a&b")]);
+        assert_eq!(
+            fill_route("/blog/<slug>", &extra).unwrap(),
+            "/blog/x?page=2&q=a%26b"
+        );
+    }
+
+    #[test]
+    fn fill_route_names_missing_params() {
+        let error = fill_route("/blog/<slug>", &params(&[])).unwrap_err();
+        assert_eq!(error, "missing route parameter `slug` for `/blog/<slug>`");
+    }
+}
@@ -1,5 +1,19 @@
+use crate::application::audit::{AuditOptions, AuditSource, audit_site};
+use crate::application::check::check_site;
+use crate::application::export::{ExportOptions, export_site};
+use crate::application::import::{ImportOptions, import_html};
+use crate::application::live::LiveRegistry;
+use crate::application::redirects::RedirectExport;
+use crate::application::route_table::{RouteFilter, RouteSort, render_table, route_rows};
+use crate::application::url_for::UrlFor;
+use crate::application::{CapsuleRegistry, Method, TeraEngine};
+use crate::seo::SiteSeoDefaults;
+use crate::seo::social_card::{CardBackground, SocialCardStyle, SocialCards};
+use crate::seo::validate::Severity;
 use clap::Subcommand;
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::Arc;
 
 /// A trait representing a shell or command-line interface that executes
 /// the main program logic and returns an `ExitCode` to indicate the
@@ -1027,7 +1041,346 @@ pub trait Capsule {
     fn seed() -> ExitCode;
 }
 
+/// The subcommands of the site tooling.
+///
+/// # Variants
+///
+/// - `Check`: Loads every template and renders every capsule, reporting all failures with the
+///   template name and line, and lints the SEO metadata of every page. Exits with a failure code
+///   when anything is wrong (SEO warnings included with `--strict`), so it can gate deploys. Pages
+///   are checked with the `--site` SEO settings applied, as they are served.
+/// - `Routes`: Prints what the site serves (method, URI, name, template, description, manifest),
+///   as a table or as JSON.
+/// - `Export`: Renders the site into static files: every page, every collection page, `404.html`,
+///   redirects and, given a base URL, `sitemap.xml`. Pages are rendered with the `--site` SEO
+///   settings applied. Given a card font, the pages without an `og_image` get a generated social card.
+/// - `Import`: Turns a directory of HTML pages into capsule manifests.
+/// - `Audit`: Crawls a running site or a static export from `/` and reports broken links,
+///   redirect chains, duplicate titles and descriptions, canonical problems, orphan capsules and
+///   SEO issues. Exits with a failure code when a finding reaches `--fail-on`.
 #[derive(Subcommand)]
 pub enum Command {
-    
+    /// Check that every template loads and every capsule renders.
+    Check {
+        /// The directory holding the `*.html.tera` templates.
+        #[arg(long, default_value = "templates")]
+        templates: String,
+        /// The directory holding the capsule manifests (`*.toml`, `*.json`).
+        #[arg(long, default_value = "capsules")]
+        capsules: String,
+        /// Fail on SEO warnings too, not only on errors.
+        #[arg(long)]
+        strict: bool,
+        /// A TOML or JSON file of site-wide SEO settings, applied to every page (see
+        /// `SiteSeoDefaults::from_file`).
+        #[arg(long)]
+        site: Option<PathBuf>,
+    },
+    /// List the routes served by the capsules.
+    Routes {
+        /// The directory holding the capsule manifests (`*.toml`, `*.json`).
+        #[arg(long, default_value = "capsules")]
+        capsules: String,
+        /// The column to sort by.
+        #[arg(long, value_enum, default_value_t = RouteSort::Uri)]
+        sort: RouteSort,
+        /// Only list capsules answering this method.
+        #[arg(long, value_enum)]
+        method: Option<Method>,
+        /// Only list capsules whose URI, name or template contains this text.
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render the site into static files.
+    Export {
+        /// The directory holding the `*.html.tera` templates.
+        #[arg(long, default_value = "templates")]
+        templates: String,
+        /// The directory holding the capsule manifests (`*.toml`, `*.json`).
+        #[arg(long, default_value = "capsules")]
+        capsules: String,
+        /// The directory the site is written to.
+        #[arg(long, default_value = "public")]
+        out: String,
+        /// The site origin, e.g. `https://example.org`, used for `sitemap.xml`. Defaults to the
+        /// `base_url` of the `--site` settings.
+        #[arg(long)]
+        base_url: Option<String>,
+        /// A TOML or JSON file of site-wide SEO settings, applied to every page (see
+        /// `SiteSeoDefaults::from_file`).
+        #[arg(long)]
+        site: Option<PathBuf>,
+        /// How redirects are written.
+        #[arg(long, value_enum, default_value_t = RedirectExport::MetaRefresh)]
+        redirects: RedirectExport,
+        /// The font of the social cards generated for pages without an `og_image`.
+        #[arg(long)]
+        card_font: Option<PathBuf>,
+        /// The background of the social cards: a `#rrggbb` color or a PNG file.
+        #[arg(long, requires = "card_font")]
+        card_background: Option<CardBackground>,
+        /// A PNG logo drawn on the social cards.
+        #[arg(long, requires = "card_font")]
+        card_logo: Option<PathBuf>,
+    },
+    /// Turn a directory of HTML pages into capsule manifests, keeping their metadata.
+    Import {
+        /// The directory holding the HTML pages.
+        source: String,
+        /// The directory the capsule manifests are written to.
+        #[arg(long, default_value = "capsules")]
+        capsules: String,
+        /// The template of the capsules created; it gets the page body as `data.html`.
+        #[arg(long, default_value = "page")]
+        template: String,
+        /// Replace the manifests that already exist.
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Crawl a site from `/` and report its broken links and SEO problems.
+    Audit {
+        /// A running site (`http://localhost:8000`) or an exported directory.
+        source: AuditSource,
+        /// The directory holding the capsule manifests, to report the capsules nothing links to.
+        #[arg(long)]
+        capsules: Option<String>,
+        /// The public origin of the site, e.g. `https://example.org`, whose URLs are internal.
+        #[arg(long)]
+        base_url: Option<String>,
+        /// The number of URIs fetched before the crawl stops.
+        #[arg(long, default_value_t = 5000)]
+        max_pages: usize,
+        /// Fail when a finding is at least this serious.
+        #[arg(long, value_enum, default_value_t = Severity::Error)]
+        fail_on: Severity,
+        /// Print JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Command {
+    /// Runs the subcommand, printing its report.
+    ///
+    /// # Returns
+    ///
+    /// * `ExitCode::SUCCESS` - If the subcommand found nothing wrong.
+    /// * `ExitCode::FAILURE` - Otherwise.
+    pub fn run(&self) -> ExitCode {
+        match self {
+            Command::Check {
+                templates,
+                capsules,
+                strict,
+                site,
+            } => {
+                let Some(registry) = load_registry(capsules) else {
+                    return ExitCode::FAILURE;
+                };
+                let Ok(site) = load_site(site.as_ref()) else {
+                    return ExitCode::FAILURE;
+                };
+                let registry = Arc::new(LiveRegistry::new(registry));
+                let mut engine = TeraEngine::new(templates.as_str());
+                engine.register_function("url_for", UrlFor::new(registry.clone()));
+                let report = check_site(&registry.snapshot(), &engine, site.as_ref());
+                if report.is_ok() && !(*strict && report.has_warnings()) {
+                    println!("{report}");
+                    ExitCode::SUCCESS
+                } else {
+                    eprintln!("{report}");
+                    ExitCode::FAILURE
+                }
+            }
+            Command::Routes {
+                capsules,
+                sort,
+                method,
+                filter,
+                json,
+            } => {
+                let Some(registry) = load_registry(capsules) else {
+                    return ExitCode::FAILURE;
+                };
+                let filter = RouteFilter {
+                    method: *method,
+                    search: filter.clone(),
+                };
+                let rows = route_rows(&registry, &filter, *sort);
+                if *json {
+                    match serde_json::to_string_pretty(&rows) {
+                        Ok(json) => println!("{json}"),
+                        Err(error) => {
+                            eprintln!("error: {error}");
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                } else {
+                    println!("{}", render_table(&rows));
+                }
+                ExitCode::SUCCESS
+            }
+            Command::Export {
+                templates,
+                capsules,
+                out,
+                base_url,
+                site,
+                redirects,
+                card_font,
+                card_background,
+                card_logo,
+            } => {
+                let Some(registry) = load_registry(capsules) else {
+                    return ExitCode::FAILURE;
+                };
+                let Ok(site) = load_site(site.as_ref()) else {
+                    return ExitCode::FAILURE;
+                };
+                let base_url = base_url
+                    .clone()
+                    .or_else(|| site.as_ref().and_then(|site| site.base_url.clone()));
+                let base_url = &base_url;
+                let registry = Arc::new(LiveRegistry::new(registry));
+                let mut engine = TeraEngine::new(templates.as_str());
+                engine.register_function("url_for", UrlFor::new(registry.clone()));
+                if let Some(base_url) = base_url {
+                    engine.register_global("base_url", base_url.as_str());
+                }
+                let social_cards = match card_font {
+                    Some(font) => {
+                        let mut style = SocialCardStyle::new(font);
+                        if let Some(background) = card_background {
+                            style = style.with_background(background.clone());
+                        }
+                        if let Some(logo) = card_logo {
+                            style = style.with_logo(logo);
+                        }
+                        if let Some(base_url) = base_url {
+                            style = style.with_base_url(base_url);
+                        }
+                        match SocialCards::new(style) {
+                            Ok(cards) => Some(Arc::new(cards)),
+                            Err(error) => {
+                                eprintln!("error: {error:#}");
+                                return ExitCode::FAILURE;
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                let options = ExportOptions {
+                    redirects: *redirects,
+                    base_url: base_url.clone(),
+                    seo_defaults: site,
+                    social_cards,
+                };
+                match export_site(&registry.snapshot(), &engine, out, &options) {
+                    Ok(report) => {
+                        for uri in &report.skipped {
+                            eprintln!("skipped: {uri}");
+                        }
+                        println!(
+                            "exported {} pages, {} redirects and {} social cards to {out}",
+                            report.pages, report.redirects, report.cards
+                        );
+                        ExitCode::SUCCESS
+                    }
+                    Err(error) => {
+                        eprintln!("error: {error:#}");
+                        ExitCode::FAILURE
+                    }
+                }
+            }
+            Command::Import {
+                source,
+                capsules,
+                template,
+                overwrite,
+            } => {
+                let options = ImportOptions {
+                    template: template.clone(),
+                    overwrite: *overwrite,
+                };
+                match import_html(source, capsules, &options) {
+                    Ok(report) => {
+                        for (path, reason) in &report.skipped {
+                            eprintln!("skipped: {}: {reason}", path.display());
+                        }
+                        println!("imported {} pages to {capsules}", report.imported.len());
+                        ExitCode::SUCCESS
+                    }
+                    Err(error) => {
+                        eprintln!("error: {error:#}");
+                        ExitCode::FAILURE
+                    }
+                }
+            }
+            Command::Audit {
+                source,
+                capsules,
+                base_url,
+                max_pages,
+                fail_on,
+                json,
+            } => {
+                let registry = match capsules {
+                    Some(capsules) => match load_registry(capsules) {
+                        Some(registry) => Some(registry),
+                        None => return ExitCode::FAILURE,
+                    },
+                    None => None,
+                };
+                let options = AuditOptions {
+                    base_url: base_url.clone(),
+                    max_pages: *max_pages,
+                };
+                let report = match audit_site(source, registry.as_ref(), &options) {
+                    Ok(report) => report,
+                    Err(error) => {
+                        eprintln!("error: {error:#}");
+                        return ExitCode::FAILURE;
+                    }
+                };
+                if *json {
+                    match serde_json::to_string_pretty(&report) {
+                        Ok(json) => println!("{json}"),
+                        Err(error) => {
+                            eprintln!("error: {error}");
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                } else {
+                    println!("{report}");
+                }
+                if report.fails(*fail_on) {
+                    ExitCode::FAILURE
+                } else {
+                    ExitCode::SUCCESS
+                }
+            }
+        }
+    }
+}
+
+/// Loads the capsule manifests of `dir`, printing the error if any.
+/// Reads the `--site` settings, when given. Errors are printed.
+fn load_site(path: Option<&PathBuf>) -> Result<Option<SiteSeoDefaults>, ()> {
+    path.map(SiteSeoDefaults::from_file)
+        .transpose()
+        .map_err(|error| eprintln!("error: {error:#}"))
+}
+
+fn load_registry(dir: &str) -> Option<CapsuleRegistry> {
+    let mut registry = CapsuleRegistry::default();
+    match registry.load_manifests(dir) {
+        Ok(_) => Some(registry),
+        Err(error) => {
+            eprintln!("error: {error:#}");
+            None
+        }
+    }
 }
@@ -0,0 +1,311 @@
+use super::Seo;
+use super::validate::DESCRIPTION_LENGTH;
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Reading speed used to estimate [`Seo::reading_time`].
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// The syntax of a front matter block.
+///
+/// # Variants
+///
+/// - `Yaml`: Between `---` lines.
+/// - `Toml`: Between `+++` lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+/// Splits a Markdown document into its front matter and its body.
+///
+/// # Returns
+///
+/// The format and text of the front matter, if the document starts with one, and the body.
+pub fn split_front_matter(source: &str) -> (Option<(FrontMatterFormat, &str)>, &str) {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    for (fence, format) in [
+        ("---", FrontMatterFormat::Yaml),
+        ("+++", FrontMatterFormat::Toml),
+    ] {
+        let Some(rest) = source
+            .strip_prefix(fence)
+            .and_then(|rest| rest.strip_prefix('\n').or(rest.strip_prefix("\r\n")))
+        else {
+            continue;
+        };
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                return (
+                    Some((format, &rest[..offset])),
+                    &rest[offset + line.len()..],
+                );
+            }
+            offset += line.len();
+        }
+    }
+    (None, source)
+}
+
+/// Parses the front matter of a Markdown document.
+///
+/// TOML dates are turned into ISO8601 strings.
+///
+/// # Returns
+///
+/// The front matter (an empty object when there is none) and the body of the document.
+///
+/// # Errors
+///
+/// Fails when the front matter is not valid YAML or TOML.
+pub fn parse_front_matter(source: &str) -> anyhow::Result<(Value, &str)> {
+    let (front_matter, body) = split_front_matter(source);
+    let value = match front_matter {
+        None => Value::Object(Default::default()),
+        Some((_, text)) if text.trim().is_empty() => Value::Object(Default::default()),
+        Some((FrontMatterFormat::Yaml, text)) => {
+            serde_yaml::from_str(text).context("invalid YAML front matter")?
+        }
+        Some((FrontMatterFormat::Toml, text)) => {
+            let table: toml::Table = toml::from_str(text).context("invalid TOML front matter")?;
+            toml_to_json(toml::Value::Table(table))
+        }
+    };
+    Ok((value, body))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// The usual front matter keys that have another name in [`Seo`].
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Aliases {
+    tags: Vec<String>,
+    date: Option<String>,
+    image: Option<String>,
+    summary: Option<String>,
+}
+
+impl Seo {
+    /// Reads the metadata of a Markdown document from its front matter and its content.
+    ///
+    /// The front matter (YAML between `---` lines or TOML between `+++` lines) holds [`Seo`]
+    /// fields, and may use the usual `tags`, `date`, `image` and `summary` keys for `keywords`,
+    /// `published`, `og_image` and `description`. What it leaves out is derived:
+    ///
+    /// - `description`: the first paragraph, cut at a word boundary to fit 160 characters.
+    /// - `slug`: the slugified title.
+    /// - `reading_time`: from the number of words, at [`WORDS_PER_MINUTE`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the front matter is not valid YAML or TOML, or does not fit the `Seo` fields.
+    pub fn from_front_matter(source: &str) -> anyhow::Result<Self> {
+        let (front_matter, body) = parse_front_matter(source)?;
+        let mut seo: Seo = serde_json::from_value(front_matter.clone())
+            .context("invalid SEO fields in the front matter")?;
+        let aliases: Aliases = serde_json::from_value(front_matter)
+            .context("invalid `tags`, `date`, `image` or `summary` in the front matter")?;
+
+        if seo.keywords.is_empty() {
+            seo.keywords = aliases.tags;
+        }
+        if seo.published.is_none() {
+            seo.published = aliases.date;
+        }
+        if seo.og_image.is_none() {
+            seo.og_image = aliases.image;
+        }
+        if seo.description.is_empty() {
+            seo.description = aliases
+                .summary
+                .or_else(|| first_paragraph(body))
+                .map(|text| truncate_words(&text, *DESCRIPTION_LENGTH.end()))
+                .unwrap_or_default();
+        }
+        if seo.slug.is_none() && !seo.title.trim().is_empty() {
+            seo.slug = Some(slug::slugify(&seo.title));
+        }
+        if seo.reading_time.is_none() {
+            let words = body.split_whitespace().count();
+            seo.reading_time = Some(words.div_ceil(WORDS_PER_MINUTE).max(1) as u32);
+        }
+        Ok(seo)
+    }
+
+    /// Like [`Seo::from_front_matter`], reading the document from `path`, and taking `updated`
+    /// from the modification time of the file when the front matter leaves it out.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the file cannot be read, or as [`Seo::from_front_matter`].
+    pub fn from_markdown_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let mut seo =
+            Self::from_front_matter(&source).with_context(|| format!("in {}", path.display()))?;
+        if seo.updated.is_none()
+            && let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified())
+        {
+            let modified: chrono::DateTime<chrono::Utc> = modified.into();
+            seo.updated = Some(modified.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        }
+        Ok(seo)
+    }
+}
+
+/// The text of the first paragraph of a Markdown document, headings, lists and code excepted.
+fn first_paragraph(markdown: &str) -> Option<String> {
+    use comrak::nodes::NodeValue;
+    let arena = comrak::Arena::new();
+    let root = comrak::parse_document(&arena, markdown, &comrak::Options::default());
+    let paragraph = root
+        .children()
+        .find(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))?;
+    let mut text = String::new();
+    for node in paragraph.descendants() {
+        match &node.data.borrow().value {
+            NodeValue::Text(t) => text.push_str(t),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Cuts `text` at the last word boundary that fits `max` characters, ellipsis included.
+fn truncate_words(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let end = text
+        .char_indices()
+        .nth(max - 1)
+        .map_or(text.len(), |(index, _)| index);
+    let cut = &text[..end];
+    let cut = if text[end..].starts_with(char::is_whitespace) {
+        cut
+    } else {
+        cut.rfind(char::is_whitespace)
+            .map_or(cut, |index| &cut[..index])
+    };
+    let cut = cut.trim_end_matches(|c: char| c.is_whitespace() || ",;:.-–—".contains(c));
+    format!("{cut}…")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_front_matter_finds_yaml_and_toml_fences() {
+        let (front_matter, body) = split_front_matter("---\ntitle: A\n---\nBody\n");
+        assert_eq!(front_matter, Some((FrontMatterFormat::Yaml, "title: A\n")));
+        assert_eq!(body, "Body\n");
+
+        let (front_matter, body) = split_front_matter("\u{feff}+++\r\ntitle = 'A'\r\n+++\r\nBody");
+        assert_eq!(
+            front_matter,
+            Some((FrontMatterFormat::Toml, "title = 'A'\r\n"))
+        );
+        assert_eq!(body, "Body");
+
+        let (front_matter, body) = split_front_matter("---\n---\nBody");
+        assert_eq!(front_matter, Some((FrontMatterFormat::Yaml, "")));
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn split_front_matter_leaves_other_documents_whole() {
+        for source in [
+            "# Title\n",
+            "---\ntitle: A\nnever closed\n",
+            "----\nBody\n---\n",
+        ] {
+            assert_eq!(split_front_matter(source), (None, source));
+        }
+    }
+
+    #[test]
+    fn parse_front_matter_turns_toml_dates_into_strings() {
+        let (value, body) =
+            parse_front_matter("+++\ndate = 2024-03-01\n[extra]\nn = 2\n+++\nBody").unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "date": "2024-03-01", "extra": { "n": 2 } })
+        );
+        assert_eq!(body, "Body");
+
+        let (value, _) = parse_front_matter("No front matter").unwrap();
+        assert_eq!(value, serde_json::json!({}));
+        assert!(parse_front_matter("---\ntitle: [\n---\n").is_err());
+        assert!(parse_front_matter("+++\ntitle = \n+++\n").is_err());
+    }
+
+    #[test]
+    fn first_paragraph_skips_headings_and_flattens_inline_markup() {
+        let markdown =
+            "# Heading\n\n- item\n\nSome *emphasis* and `code`,\nover two lines.\n\nNext.";
+        assert_eq!(
+            first_paragraph(markdown).as_deref(),
+            Some("Some emphasis and code, over two lines.")
+        );
+        assert_eq!(first_paragraph("# Only a heading\n"), None);
+    }
+
+    #[test]
+    fn truncate_words_cuts_at_word_boundaries() {
+        assert_eq!(truncate_words("short text", 20), "short text");
+        assert_eq!(truncate_words("one two three", 9), "one two…");
+        assert_eq!(truncate_words("one two, three", 10), "one two…");
+        assert_eq!(truncate_words("héllo wörld again", 12), "héllo wörld…");
+        assert!(truncate_words(&"word ".repeat(50), 160).chars().count() <= 160);
+    }
+
+    #[test]
+    fn from_front_matter_maps_aliases_and_derives_the_rest() {
+        let body = "word ".repeat(450);
+        let source = format!(
+            "---\ntitle: Hello World\ntags: [a, b]\ndate: 2024-03-01\nimage: /cover.png\n---\n{body}"
+        );
+        let seo = Seo::from_front_matter(&source).unwrap();
+        assert_eq!(seo.title, "Hello World");
+        assert_eq!(seo.keywords, ["a", "b"]);
+        assert_eq!(seo.published.as_deref(), Some("2024-03-01"));
+        assert_eq!(seo.og_image.as_deref(), Some("/cover.png"));
+        assert_eq!(seo.slug.as_deref(), Some("hello-world"));
+        assert_eq!(seo.reading_time, Some(3));
+        assert!(seo.description.starts_with("word word"));
+        assert!(seo.description.ends_with('…'));
+        assert!(seo.description.chars().count() <= *DESCRIPTION_LENGTH.end());
+
+        let seo = Seo::from_front_matter("+++\nsummary = 'Given.'\n+++\nFirst paragraph.").unwrap();
+        assert_eq!(seo.description, "Given.");
+        assert_eq!(seo.slug, None);
+        assert_eq!(seo.reading_time, Some(1));
+
+        assert!(Seo::from_front_matter("---\nkeywords: 3\n---\n").is_err());
+    }
+}
@@ -0,0 +1,518 @@
+use super::{Seo, html_escape};
+
+/// Properties and names that may appear several times in a head.
+const REPEATABLE: &[&str] = &[
+    "og:locale:alternate",
+    "article:author",
+    "article:tag",
+    "book:author",
+    "book:tag",
+    "video:actor",
+    "video:director",
+    "video:writer",
+    "video:tag",
+    "music:musician",
+];
+
+/// Link relations a head has at most one of.
+const UNIQUE_LINKS: &[&str] = &["canonical", "manifest", "prev", "next"];
+
+/// An element of the `<head>`: `<title>`, `<meta>`, `<link>`, `<script>`…
+///
+/// # Fields
+///
+/// * `tag` - The element name.
+/// * `attributes` - The attributes, in order; values are escaped when rendered.
+/// * `content` - The content of non-void elements. It is escaped in a `<title>` and written as
+///   is in other elements, e.g. a `<script>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadElement {
+    pub tag: String,
+    pub attributes: Vec<(String, String)>,
+    pub content: Option<String>,
+}
+
+impl HeadElement {
+    /// An element without attributes.
+    pub fn new(tag: impl Into<String>) -> Self {
+        Self {
+            tag: tag.into(),
+            attributes: Vec::new(),
+            content: None,
+        }
+    }
+
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// `<title>`.
+    pub fn title(title: impl Into<String>) -> Self {
+        Self::new("title").with_content(title)
+    }
+
+    /// `<meta name content>`.
+    pub fn meta(name: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::new("meta")
+            .with_attribute("name", name)
+            .with_attribute("content", content)
+    }
+
+    /// `<meta property content>`, for Open Graph.
+    pub fn property(property: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::new("meta")
+            .with_attribute("property", property)
+            .with_attribute("content", content)
+    }
+
+    /// `<link rel href>`.
+    pub fn link(rel: impl Into<String>, href: impl Into<String>) -> Self {
+        Self::new("link")
+            .with_attribute("rel", rel)
+            .with_attribute("href", href)
+    }
+
+    /// `<script type="application/ld+json">`, for JSON already safe to embed (see
+    /// [`script_json`](super::json_ld::script_json)).
+    pub fn json_ld(json: impl Into<String>) -> Self {
+        Self::new("script")
+            .with_attribute("type", "application/ld+json")
+            .with_content(json)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Identifies the elements a head has only one of: a later one replaces an earlier one.
+    fn key(&self) -> Option<String> {
+        match self.tag.as_str() {
+            "title" | "base" => Some(self.tag.clone()),
+            "meta" => {
+                if self.attribute("charset").is_some() {
+                    return Some("charset".into());
+                }
+                ["name", "property", "http-equiv"]
+                    .into_iter()
+                    .find_map(|kind| Some((kind, self.attribute(kind)?)))
+                    .filter(|(_, name)| !REPEATABLE.contains(name))
+                    .map(|(kind, name)| format!("{kind}={name}"))
+            }
+            "link" => {
+                let rel = self.attribute("rel")?;
+                if UNIQUE_LINKS.contains(&rel) {
+                    Some(format!("rel={rel}"))
+                } else if rel == "alternate" {
+                    self.attribute("hreflang")
+                        .map(|hreflang| format!("hreflang={hreflang}"))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.tag);
+        for (name, value) in &self.attributes {
+            out.push_str(&format!(r#" {name}="{}""#, html_escape(value)));
+        }
+        out.push('>');
+        if let Some(content) = &self.content {
+            if self.tag == "title" {
+                out.push_str(&html_escape(content));
+            } else {
+                out.push_str(content);
+            }
+            out.push_str(&format!("</{}>", self.tag));
+        } else if !matches!(self.tag.as_str(), "meta" | "link" | "base") {
+            out.push_str(&format!("</{}>", self.tag));
+        }
+    }
+}
+
+/// The kinds of feeds announced with [`HeadBuilder::feed`].
+///
+/// # Variants
+///
+/// - `Rss`: `application/rss+xml`.
+/// - `Atom`: `application/atom+xml`.
+/// - `Json`: `application/feed+json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedKind {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            FeedKind::Rss => "application/rss+xml",
+            FeedKind::Atom => "application/atom+xml",
+            FeedKind::Json => "application/feed+json",
+        }
+    }
+}
+
+/// Composes the `<head>` of a page.
+///
+/// Elements are rendered in the order they are added. Elements a head has only one of (the
+/// `<title>`, `<meta name="description">`, `og:title`, the canonical link…) replace the earlier
+/// ones in place, and exact duplicates are dropped, so site settings can be set once and
+/// completed or overridden page by page:
+///
+/// ```ignore
+/// let site = HeadBuilder::new()
+///     .icon("/favicon.svg")
+///     .manifest("/site.webmanifest")
+///     .feed(FeedKind::Atom, "Articles", "/feed.xml");
+/// let head = site
+///     .clone()
+///     .seo(&seo)
+///     .next("/blog/page/2")
+///     .noindex()
+///     .render();
+/// ```
+#[derive(Debug, Clone)]
+pub struct HeadBuilder {
+    elements: Vec<HeadElement>,
+    noindex: bool,
+    nofollow: bool,
+    minify: bool,
+}
+
+impl Default for HeadBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadBuilder {
+    /// A head with the `charset` and `viewport` metas.
+    pub fn new() -> Self {
+        Self {
+            elements: vec![
+                HeadElement::new("meta").with_attribute("charset", "utf-8"),
+                HeadElement::meta("viewport", "width=device-width, initial-scale=1"),
+            ],
+            noindex: false,
+            nofollow: false,
+            minify: false,
+        }
+    }
+
+    /// Adds an element, replacing the one it stands for if any.
+    pub fn element(mut self, element: HeadElement) -> Self {
+        match element.key() {
+            Some(key) => {
+                match self
+                    .elements
+                    .iter_mut()
+                    .find(|e| e.key().as_ref() == Some(&key))
+                {
+                    Some(existing) => *existing = element,
+                    None => self.elements.push(element),
+                }
+            }
+            None if self.elements.contains(&element) => {}
+            None => self.elements.push(element),
+        }
+        self
+    }
+
+    pub fn title(self, title: impl Into<String>) -> Self {
+        self.element(HeadElement::title(title))
+    }
+
+    pub fn meta(self, name: impl Into<String>, content: impl Into<String>) -> Self {
+        self.element(HeadElement::meta(name, content))
+    }
+
+    pub fn property(self, property: impl Into<String>, content: impl Into<String>) -> Self {
+        self.element(HeadElement::property(property, content))
+    }
+
+    pub fn link(self, rel: impl Into<String>, href: impl Into<String>) -> Self {
+        self.element(HeadElement::link(rel, href))
+    }
+
+    /// A favicon; add one per format or size.
+    pub fn icon(self, href: impl Into<String>) -> Self {
+        self.link("icon", href)
+    }
+
+    pub fn apple_touch_icon(self, href: impl Into<String>) -> Self {
+        self.link("apple-touch-icon", href)
+    }
+
+    /// The web app manifest.
+    pub fn manifest(self, href: impl Into<String>) -> Self {
+        self.link("manifest", href)
+    }
+
+    /// Announces a feed of the site.
+    pub fn feed(self, kind: FeedKind, title: impl Into<String>, href: impl Into<String>) -> Self {
+        self.element(
+            HeadElement::link("alternate", href)
+                .with_attribute("type", kind.mime_type())
+                .with_attribute("title", title),
+        )
+    }
+
+    /// The previous page of a paginated listing.
+    pub fn prev(self, href: impl Into<String>) -> Self {
+        self.link("prev", href)
+    }
+
+    /// The next page of a paginated listing.
+    pub fn next(self, href: impl Into<String>) -> Self {
+        self.link("next", href)
+    }
+
+    /// Asks search engines not to index the page.
+    pub fn noindex(mut self) -> Self {
+        self.noindex = true;
+        self.robots()
+    }
+
+    /// Asks search engines not to follow the links of the page.
+    pub fn nofollow(mut self) -> Self {
+        self.nofollow = true;
+        self.robots()
+    }
+
+    /// Renders the elements without line breaks.
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    fn robots(self) -> Self {
+        let directives = [(self.noindex, "noindex"), (self.nofollow, "nofollow")]
+            .into_iter()
+            .filter_map(|(set, directive)| set.then_some(directive))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.meta("robots", directives)
+    }
+
+    /// Adds the elements describing the page: title, description, keywords, author, robots,
+    /// canonical and alternate links, Open Graph and Twitter metas, and JSON-LD.
+    pub fn seo(mut self, seo: &Seo) -> Self {
+        if !seo.title.is_empty() {
+            self = self.title(&seo.title);
+        }
+        if !seo.description.is_empty() {
+            self = self.meta("description", &seo.description);
+        }
+        if !seo.keywords.is_empty() {
+            self = self.meta("keywords", seo.keywords.join(", "));
+        }
+        if let Some(author) = &seo.author {
+            self = self.meta("author", author);
+        }
+        if seo.noindex {
+            self = self.noindex();
+        }
+        if seo.nofollow {
+            self = self.nofollow();
+        }
+        if let Some(canonical) = &seo.canonical_url {
+            self = self.link("canonical", canonical);
+        }
+        for alternate in &seo.alternates {
+            self = self.element(
+                HeadElement::link("alternate", &alternate.href)
+                    .with_attribute("hreflang", &alternate.hreflang),
+            );
+        }
+
+        // Open Graph
+        if !seo.title.is_empty() {
+            self = self.property("og:title", &seo.title);
+        }
+        if !seo.description.is_empty() {
+            self = self.property("og:description", &seo.description);
+        }
+        if let Some(og_type) = &seo.og_type {
+            self = self.property("og:type", og_type.name());
+        }
+        if let Some(url) = seo.og_url() {
+            self = self.property("og:url", url);
+        }
+        if let Some(locale) = seo.og_locale() {
+            self = self.property("og:locale", locale);
+        }
+        for locale in seo.og_locale_alternates() {
+            self = self.property("og:locale:alternate", locale);
+        }
+        if let Some(image) = &seo.og_image {
+            self = self.property("og:image", image);
+            if let Some(mime) = &seo.og_image_type {
+                self = self.property("og:image:type", mime);
+            }
+            if let Some(width) = seo.og_image_width {
+                self = self.property("og:image:width", width.to_string());
+            }
+            if let Some(height) = seo.og_image_height {
+                self = self.property("og:image:height", height.to_string());
+            }
+            if let Some(alt) = &seo.og_image_alt {
+                self = self.property("og:image:alt", alt);
+            }
+        }
+        if let Some(site_name) = &seo.site_name {
+            self = self.property("og:site_name", site_name);
+        }
+        for (property, content) in seo.og_properties() {
+            self = self.property(property, content);
+        }
+
+        // Twitter
+        if let Some(card) = seo.twitter_card {
+            self = self.meta("twitter:card", card.name());
+        }
+        if let Some(site) = &seo.twitter_site {
+            self = self.meta("twitter:site", site);
+        }
+        if let Some(creator) = &seo.twitter_creator {
+            self = self.meta("twitter:creator", creator);
+        }
+        if !seo.title.is_empty() {
+            self = self.meta("twitter:title", &seo.title);
+        }
+        if !seo.description.is_empty() {
+            self = self.meta("twitter:description", &seo.description);
+        }
+        if let Some(image) = &seo.og_image {
+            self = self.meta("twitter:image", image);
+            if let Some(alt) = seo.twitter_image_alt() {
+                self = self.meta("twitter:image:alt", alt);
+            }
+        }
+
+        for ld in seo.json_ld_scripts() {
+            self = self.element(HeadElement::json_ld(ld));
+        }
+        self
+    }
+
+    /// The elements, in order.
+    pub fn elements(&self) -> &[HeadElement] {
+        &self.elements
+    }
+
+    /// Renders the elements, one per line unless minified.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for element in &self.elements {
+            element.render(&mut out);
+            if !self.minify {
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(head: &HeadBuilder) -> Vec<String> {
+        head.elements()
+            .iter()
+            .map(|element| {
+                let mut out = String::new();
+                element.render(&mut out);
+                out
+            })
+            .collect()
+    }
+
+    #[test]
+    fn element_replaces_unique_elements_in_place() {
+        let head = HeadBuilder::new()
+            .title("Site")
+            .meta("description", "Site description")
+            .link("canonical", "/")
+            .title("Page <1>")
+            .meta("description", "Page description")
+            .link("canonical", "/page");
+        assert_eq!(
+            tags(&head)[2..],
+            [
+                "<title>Page &lt;1&gt;</title>",
+                r#"<meta name="description" content="Page description">"#,
+                r#"<link rel="canonical" href="/page">"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn element_keeps_repeatable_elements_and_drops_exact_duplicates() {
+        let head = HeadBuilder::new()
+            .property("article:tag", "rust")
+            .property("article:tag", "web")
+            .property("article:tag", "rust")
+            .icon("/favicon.svg")
+            .icon("/favicon.png")
+            .icon("/favicon.svg");
+        assert_eq!(
+            tags(&head)[2..],
+            [
+                r#"<meta property="article:tag" content="rust">"#,
+                r#"<meta property="article:tag" content="web">"#,
+                r#"<link rel="icon" href="/favicon.svg">"#,
+                r#"<link rel="icon" href="/favicon.png">"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn element_keys_alternate_links_by_hreflang() {
+        let alternate = |hreflang: &str, href: &str| {
+            HeadElement::link("alternate", href).with_attribute("hreflang", hreflang)
+        };
+        let head = HeadBuilder::new()
+            .element(alternate("fr", "/fr/old"))
+            .element(alternate("de", "/de/"))
+            .element(alternate("fr", "/fr/"))
+            .feed(FeedKind::Atom, "Articles", "/feed.xml")
+            .feed(FeedKind::Rss, "Articles", "/rss.xml");
+        assert_eq!(
+            tags(&head)[2..],
+            [
+                r#"<link rel="alternate" href="/fr/" hreflang="fr">"#,
+                r#"<link rel="alternate" href="/de/" hreflang="de">"#,
+                r#"<link rel="alternate" href="/feed.xml" type="application/atom+xml" title="Articles">"#,
+                r#"<link rel="alternate" href="/rss.xml" type="application/rss+xml" title="Articles">"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn robots_directives_share_one_meta() {
+        let head = HeadBuilder::new().noindex().nofollow().minify(true);
+        assert_eq!(
+            head.render(),
+            concat!(
+                r#"<meta charset="utf-8">"#,
+                r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#,
+                r#"<meta name="robots" content="noindex, nofollow">"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use super::open_graph::{ArticleProperties, OgType};
+use super::{Alternate, Seo};
+use regex::Regex;
+use serde_json::Value;
+
+/// `<tag attributes>`, for the tags holding metadata.
+const HEAD_TAG: &str = r"(?is)<(meta|link|html)\b([^>]*)>";
+/// `name="value"`, `name='value'` or `name=value`.
+const ATTRIBUTE: &str =
+    r#"(?s)([a-zA-Z_:][-a-zA-Z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#;
+/// Where the `<head>` ends, closed or not.
+const HEAD_END: &str = r"(?i)</head\s*>|<body\b";
+const TITLE: &str = r"(?is)<title\b[^>]*>(.*?)</title\s*>";
+const JSON_LD: &str =
+    r#"(?is)<script\b[^>]*type\s*=\s*["']?application/ld\+json["']?[^>]*>(.*?)</script\s*>"#;
+
+impl Seo {
+    /// Extracts the metadata of an HTML page, e.g. to migrate a legacy site.
+    ///
+    /// Reads the `<title>`, `<html lang>`, the `description`, `keywords`, `author` and `robots`
+    /// metas, the canonical and `hreflang` links, the Open Graph (`og:*`, `article:*`) and Twitter
+    /// metas, and the JSON-LD scripts (several are combined into an array). `og:title` and
+    /// `og:description` stand in for a missing title and description. Tags that don't parse, such
+    /// as an unknown `og:type`, are left out.
+    ///
+    /// Tags and the title are only read in the `<head>`, up to `</head>` or `<body>`, so that the
+    /// content of the page (inline SVG titles, microdata metas) is ignored. JSON-LD scripts are
+    /// read in the whole document.
+    pub fn from_html(html: &str) -> Self {
+        let mut seo = Seo::new();
+        let (Ok(head_tag), Ok(attribute)) = (Regex::new(HEAD_TAG), Regex::new(ATTRIBUTE)) else {
+            return seo;
+        };
+        let mut og_title = None;
+        let mut og_description = None;
+        let mut og_type = None;
+        let mut article = ArticleProperties::default();
+        let head = Regex::new(HEAD_END)
+            .ok()
+            .and_then(|end| end.find(html))
+            .map_or(html, |end| &html[..end.start()]);
+
+        for tag in head_tag.captures_iter(head) {
+            let attributes: Vec<(String, String)> = attribute
+                .captures_iter(&tag[2])
+                .map(|a| {
+                    let value = a
+                        .get(2)
+                        .or(a.get(3))
+                        .or(a.get(4))
+                        .map_or("", |v| v.as_str());
+                    (a[1].to_lowercase(), html_unescape(value).trim().to_string())
+                })
+                .collect();
+            let get = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, value)| value.clone())
+            };
+            match tag[1].to_lowercase().as_str() {
+                "html" => {
+                    if let Some(lang) = get("lang").filter(|l| !l.is_empty()) {
+                        seo.lang = Some(lang);
+                    }
+                }
+                "link" => {
+                    let (Some(rel), Some(href)) = (get("rel"), get("href")) else {
+                        continue;
+                    };
+                    let rels: Vec<String> = rel
+                        .to_lowercase()
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect();
+                    if rels.iter().any(|r| r == "canonical") {
+                        seo.canonical_url = Some(href);
+                    } else if rels.iter().any(|r| r == "alternate")
+                        && let Some(hreflang) = get("hreflang")
+                    {
+                        seo.alternates.push(Alternate::new(hreflang, href));
+                    }
+                }
+                _ => {
+                    let Some(content) = get("content") else {
+                        continue;
+                    };
+                    if get("http-equiv").is_some_and(|e| e.eq_ignore_ascii_case("content-language"))
+                    {
+                        seo.lang.get_or_insert(content);
+                        continue;
+                    }
+                    let Some(key) = get("property").or_else(|| get("name")) else {
+                        continue;
+                    };
+                    let number = || content.parse::<u32>().ok();
+                    match key.to_lowercase().as_str() {
+                        "description" => seo.description = content,
+                        "keywords" => {
+                            seo.keywords = content
+                                .split(',')
+                                .map(str::trim)
+                                .filter(|k| !k.is_empty())
+                                .map(str::to_string)
+                                .collect();
+                        }
+                        "author" => seo.author = Some(content),
+                        "robots" => {
+                            let directives = content.to_lowercase();
+                            seo.noindex = directives.contains("noindex");
+                            seo.nofollow = directives.contains("nofollow");
+                        }
+                        "og:title" => og_title = Some(content),
+                        "og:description" => og_description = Some(content),
+                        "og:type" => {
+                            if let (None, Ok(parsed)) = (&og_type, content.parse::<OgType>()) {
+                                og_type = Some(parsed);
+                            }
+                        }
+                        "og:url" => seo.og_url = Some(content),
+                        "og:image" | "og:image:url" | "og:image:secure_url" => {
+                            seo.og_image.get_or_insert(content);
+                        }
+                        "og:image:alt" => seo.og_image_alt = Some(content),
+                        "og:image:width" => seo.og_image_width = number(),
+                        "og:image:height" => seo.og_image_height = number(),
+                        "og:image:type" => seo.og_image_type = Some(content),
+                        "og:locale" => seo.og_locale = Some(content),
+                        "og:locale:alternate" => seo.og_locale_alternates.push(content),
+                        "og:site_name" => seo.site_name = Some(content),
+                        "article:published_time" => article.published_time = Some(content),
+                        "article:modified_time" => article.modified_time = Some(content),
+                        "article:expiration_time" => article.expiration_time = Some(content),
+                        "article:author" => article.author.push(content),
+                        "article:section" => article.section = Some(content),
+                        "article:tag" => article.tag.push(content),
+                        "twitter:card" => seo.twitter_card = content.parse().ok(),
+                        "twitter:site" => seo.twitter_site = Some(content),
+                        "twitter:creator" => seo.twitter_creator = Some(content),
+                        "twitter:image" | "twitter:image:src" => {
+                            seo.og_image.get_or_insert(content);
+                        }
+                        "twitter:image:alt" => seo.twitter_image_alt = Some(content),
+                        "twitter:title" => {
+                            og_title.get_or_insert(content);
+                        }
+                        "twitter:description" => {
+                            og_description.get_or_insert(content);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if let Ok(title) = Regex::new(TITLE)
+            && let Some(captures) = title.captures(head)
+        {
+            seo.title = collapse_whitespace(&html_unescape(&captures[1]));
+        }
+        if seo.title.is_empty() {
+            seo.title = og_title.unwrap_or_default();
+        }
+        if seo.description.is_empty() {
+            seo.description = og_description.unwrap_or_default();
+        }
+        seo.og_type = match og_type {
+            Some(OgType::Article(_)) => {
+                seo.published = article.published_time.clone();
+                seo.updated = article.modified_time.clone();
+                Some(OgType::Article(article))
+            }
+            other => other,
+        };
+
+        if let Ok(json_ld) = Regex::new(JSON_LD) {
+            let mut documents: Vec<Value> = json_ld
+                .captures_iter(html)
+                .filter_map(|script| serde_json::from_str(script[1].trim()).ok())
+                .collect();
+            seo.json_ld = match documents.len() {
+                0 => None,
+                1 => Some(documents.remove(0).to_string()),
+                _ => Some(Value::Array(documents).to_string()),
+            };
+        }
+        seo
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decodes the character references of attribute values and titles.
+fn html_unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_html_reads_head_metadata() {
+        let seo = Seo::from_html(
+            r#"<!doctype html>
+<html lang="fr">
+<head>
+  <title> Le  titre &amp; plus </title>
+  <meta name="description" content="Une page">
+  <meta name='keywords' content='a, b,,c'>
+  <meta name="robots" content="noindex">
+  <meta property="og:image" content="/card.png">
+  <link rel="canonical" href="https://example.com/page">
+  <link rel="alternate" hreflang="en" href="https://example.com/en/page">
+</head>
+<body></body>
+</html>"#,
+        );
+        assert_eq!(seo.lang.as_deref(), Some("fr"));
+        assert_eq!(seo.title, "Le titre & plus");
+        assert_eq!(seo.description, "Une page");
+        assert_eq!(seo.keywords, ["a", "b", "c"]);
+        assert!(seo.noindex && !seo.nofollow);
+        assert_eq!(seo.og_image.as_deref(), Some("/card.png"));
+        assert_eq!(
+            seo.canonical_url.as_deref(),
+            Some("https://example.com/page")
+        );
+        assert_eq!(
+            seo.alternates,
+            [Alternate::new("en", "https://example.com/en/page")]
+        );
+    }
+
+    #[test]
+    fn from_html_ignores_the_body() {
+        let seo = Seo::from_html(
+            r#"<html><head><meta property="og:title" content="Shared"></head>
+<body>
+  <svg><title>Icon</title></svg>
+  <div itemscope><meta itemprop="name" name="description" content="Body meta"></div>
+  <link rel="canonical" href="/elsewhere">
+  <script type="application/ld+json">{"@type": "Article"}</script>
+</body></html>"#,
+        );
+        assert_eq!(seo.title, "Shared");
+        assert_eq!(seo.description, "");
+        assert_eq!(seo.canonical_url, None);
+        assert_eq!(seo.json_ld.as_deref(), Some(r#"{"@type":"Article"}"#));
+    }
+
+    #[test]
+    fn from_html_stops_at_an_unclosed_head() {
+        let seo = Seo::from_html(
+            r#"<title>Page</title><meta name="author" content="Ann"><body><meta name="author" content="Bob">"#,
+        );
+        assert_eq!(seo.title, "Page");
+        assert_eq!(seo.author.as_deref(), Some("Ann"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Typed schema.org structured data, rendered as `<script type="application/ld+json">`.
+//!
+//! Every field is optional: empty fields are filled from the [`Seo`] of the page when the head is
+//! rendered (see [`Schema::filled_from`]), so a manifest can declare just the type. Properties keep
+//! their schema.org names:
+//!
+//! ```toml
+//! [[seo.schemas]]
+//! "@type" = "BlogPosting"
+//! datePublished = "2025-05-20"
+//! ```
+
+use super::Seo;
+use super::open_graph::OgType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The JSON-LD vocabulary of every [`Schema`].
+pub const SCHEMA_CONTEXT: &str = "https://schema.org";
+
+/// A schema.org item describing the page, tagged by its `@type`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "@type")]
+pub enum Schema {
+    WebSite(WebSite),
+    Organization(Organization),
+    Person(Person),
+    Article(Article),
+    /// An [`Article`] published on a blog.
+    BlogPosting(Article),
+    BreadcrumbList(BreadcrumbList),
+    Event(Event),
+    Book(Book),
+    CreativeWork(CreativeWork),
+}
+
+/// The author or publisher of a work.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "@type")]
+pub enum Party {
+    Person(Person),
+    Organization(Organization),
+}
+
+/// Where an [`Event`] takes place.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "@type")]
+pub enum Location {
+    Place {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<String>,
+    },
+    VirtualLocation {
+        url: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebSite {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<Party>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Organization {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub same_as: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Person {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_title: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub same_as: Vec<String>,
+}
+
+/// An `Article` or a `BlogPosting`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Article {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub article_section: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+/// The trail of pages leading to the current one, from the home page.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BreadcrumbList {
+    pub item_list_element: Vec<ListItem>,
+}
+
+/// The `@type` of a [`ListItem`], which has only one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ListItemType {
+    #[default]
+    ListItem,
+}
+
+/// One step of a [`BreadcrumbList`]: `position` starts at 1, `item` is the URL of the page.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct ListItem {
+    #[serde(rename = "@type")]
+    pub kind: ListItemType,
+    pub position: usize,
+    pub name: String,
+    pub item: String,
+}
+
+impl BreadcrumbList {
+    /// Builds the list from `(name, url)` pairs, numbering them from 1.
+    pub fn new<N: Into<String>, U: Into<String>>(crumbs: impl IntoIterator<Item = (N, U)>) -> Self {
+        Self {
+            item_list_element: crumbs
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, url))| ListItem {
+                    kind: ListItemType::ListItem,
+                    position: index + 1,
+                    name: name.into(),
+                    item: url.into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Event {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<Party>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Book {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isbn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_of_pages: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CreativeWork {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<Party>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_language: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+/// Sets `field` to `value` when it is empty and `value` is not.
+fn fill(field: &mut Option<String>, value: Option<&String>) {
+    if field.is_none() {
+        *field = value.filter(|v| !v.is_empty()).cloned();
+    }
+}
+
+/// The page author as a [`Party`].
+fn author_of(seo: &Seo) -> Option<Party> {
+    seo.author.as_ref().map(|name| {
+        Party::Person(Person {
+            name: Some(name.clone()),
+            ..Person::default()
+        })
+    })
+}
+
+impl Schema {
+    /// The schema matching the kind of page, populated from `seo`: `Article` for an `article`
+    /// [`OgType`], `Book` for a `book`, `Person` for a `profile`, `CreativeWork` for videos and
+    /// music, `WebSite` otherwise.
+    pub fn from_seo(seo: &Seo) -> Self {
+        let schema = match &seo.og_type {
+            Some(OgType::Article(article)) => Schema::Article(Article {
+                date_published: article.published_time.clone(),
+                date_modified: article.modified_time.clone(),
+                article_section: article.section.clone(),
+                ..Article::default()
+            }),
+            Some(OgType::Book(book)) => Schema::Book(Book {
+                isbn: book.isbn.clone(),
+                date_published: book.release_date.clone(),
+                ..Book::default()
+            }),
+            Some(OgType::Profile(_)) => Schema::Person(Person::default()),
+            Some(OgType::Video(..) | OgType::Music(..)) => {
+                Schema::CreativeWork(CreativeWork::default())
+            }
+            Some(OgType::Website) | None => Schema::WebSite(WebSite::default()),
+        };
+        schema.filled_from(seo)
+    }
+
+    /// Fills the empty fields from the page metadata: title, description, canonical URL, image,
+    /// author, language, publication and last update dates, and keywords, wherever the type has a matching property.
+    pub fn filled_from(mut self, seo: &Seo) -> Self {
+        let title = Some(&seo.title);
+        let description = Some(&seo.description);
+        let url = seo.canonical_url.as_ref();
+        let image = seo.og_image.as_ref();
+        let lang = seo.lang.as_ref();
+        let updated = seo.updated.as_ref();
+        let published = seo.published.as_ref();
+        match &mut self {
+            Schema::WebSite(site) => {
+                fill(&mut site.name, title);
+                fill(&mut site.description, description);
+                fill(&mut site.url, url);
+                fill(&mut site.in_language, lang);
+            }
+            Schema::Organization(organization) => {
+                fill(&mut organization.name, title);
+                fill(&mut organization.url, url);
+                fill(&mut organization.logo, image);
+            }
+            Schema::Person(person) => {
+                fill(&mut person.name, seo.author.as_ref().or(title));
+                fill(&mut person.url, url);
+                fill(&mut person.image, image);
+            }
+            Schema::Article(article) | Schema::BlogPosting(article) => {
+                fill(&mut article.headline, title);
+                fill(&mut article.description, description);
+                fill(&mut article.url, url);
+                fill(&mut article.image, image);
+                fill(&mut article.date_published, published);
+                fill(&mut article.date_modified, updated);
+                fill(&mut article.in_language, lang);
+                if article.author.is_none() {
+                    article.author = author_of(seo);
+                }
+                if article.keywords.is_empty() {
+                    article.keywords = seo.keywords.clone();
+                }
+            }
+            Schema::BreadcrumbList(_) => {}
+            Schema::Event(event) => {
+                fill(&mut event.name, title);
+                fill(&mut event.description, description);
+                fill(&mut event.url, url);
+                fill(&mut event.image, image);
+            }
+            Schema::Book(book) => {
+                fill(&mut book.name, title);
+                fill(&mut book.description, description);
+                fill(&mut book.url, url);
+                fill(&mut book.image, image);
+                fill(&mut book.in_language, lang);
+                if book.author.is_none() {
+                    book.author = author_of(seo);
+                }
+            }
+            Schema::CreativeWork(work) => {
+                fill(&mut work.name, title);
+                fill(&mut work.description, description);
+                fill(&mut work.url, url);
+                fill(&mut work.image, image);
+                fill(&mut work.date_published, published);
+                fill(&mut work.date_modified, updated);
+                fill(&mut work.in_language, lang);
+                if work.author.is_none() {
+                    work.author = author_of(seo);
+                }
+                if work.keywords.is_empty() {
+                    work.keywords = seo.keywords.clone();
+                }
+            }
+        }
+        self
+    }
+
+    /// Serializes the schema with its `@context`, ready for a `<script type="application/ld+json">`
+    /// (see [`script_json`]).
+    pub fn to_script_json(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(map) = value.as_object_mut() {
+            map.insert("@context".into(), SCHEMA_CONTEXT.into());
+        }
+        script_json(&value)
+    }
+}
+
+/// Serializes JSON so that it can be embedded in a `<script>` element.
+///
+/// `<`, `>` and `&` only appear inside JSON strings, where they are written as `\u003c`,
+/// `\u003e` and `\u0026`: the output cannot close the element (`</script>`) or open a comment,
+/// and parses back to the same value.
+pub fn script_json(value: &Value) -> String {
+    let json = value.to_string();
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => out.push_str("\\u003c"),
+            '>' => out.push_str("\\u003e"),
+            '&' => out.push_str("\\u0026"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn script_json_cannot_close_the_script_element() {
+        let value = json!({ "name": "</script><!-- a & b -->" });
+        let script = script_json(&value);
+        assert_eq!(
+            script,
+            r#"{"name":"\u003c/script\u003e\u003c!-- a \u0026 b --\u003e"}"#
+        );
+        assert!(!script.contains('<') && !script.contains('>') && !script.contains('&'));
+        assert_eq!(serde_json::from_str::<Value>(&script).unwrap(), value);
+    }
+
+    #[test]
+    fn schemas_round_trip_with_their_type() {
+        let source = r#"{ "@type": "BlogPosting", "datePublished": "2025-05-20" }"#;
+        let schema: Schema = serde_json::from_str(source).unwrap();
+        let Schema::BlogPosting(article) = &schema else {
+            panic!("not a blog posting: {schema:?}");
+        };
+        assert_eq!(article.date_published.as_deref(), Some("2025-05-20"));
+        let value = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            value,
+            json!({ "@type": "BlogPosting", "datePublished": "2025-05-20" })
+        );
+    }
+
+    #[test]
+    fn from_seo_fills_empty_fields_only() {
+        let mut seo = Seo::new();
+        seo.title = "Hello".into();
+        seo.canonical_url = Some("https://example.org/hello".into());
+        seo.lang = Some("en".into());
+        let value: Value = serde_json::from_str(&Schema::from_seo(&seo).to_script_json()).unwrap();
+        assert_eq!(value["@context"], SCHEMA_CONTEXT);
+        assert_eq!(value["@type"], "WebSite");
+        assert_eq!(value["name"], "Hello");
+        assert_eq!(value["url"], "https://example.org/hello");
+        assert_eq!(value["inLanguage"], "en");
+
+        let named = Schema::WebSite(WebSite {
+            name: Some("Kept".into()),
+            ..WebSite::default()
+        });
+        let Schema::WebSite(site) = named.filled_from(&seo) else {
+            unreachable!()
+        };
+        assert_eq!(site.name.as_deref(), Some("Kept"));
+    }
+
+    #[test]
+    fn breadcrumbs_are_numbered_from_one() {
+        let crumbs = BreadcrumbList::new([("Home", "/"), ("Blog", "/blog")]);
+        let value = serde_json::to_value(&crumbs).unwrap();
+        assert_eq!(
+            value["itemListElement"][1],
+            json!({ "@type": "ListItem", "position": 2, "name": "Blog", "item": "/blog" })
+        );
+    }
+}
@@ -1,7 +1,19 @@
+pub mod front_matter;
+pub mod head;
+pub mod html;
+pub mod json_ld;
+pub mod open_graph;
+pub mod sitemap;
+pub mod social_card;
+pub mod validate;
+
+use head::HeadBuilder;
+use json_ld::{Schema, script_json};
+use open_graph::{OgType, TwitterCard};
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
 pub struct Seo {
     /// Title (~60 chars)
     pub title: String,
@@ -11,16 +23,46 @@ pub struct Seo {
     pub author: Option<String>,
 
     pub canonical_url: Option<String>,
-    pub lang: Option<String>,    // ex: "fr"
-    pub updated: Option<String>, // ISO8601
+    pub lang: Option<String>,      // ex: "fr"
+    pub updated: Option<String>,   // ISO8601
+    pub published: Option<String>, // ISO8601
+    /// Estimated reading time, in minutes.
+    pub reading_time: Option<u32>,
+    /// Asks search engines not to index the page (`<meta name="robots">`).
+    pub noindex: bool,
+    /// Asks search engines not to follow the links of the page.
+    pub nofollow: bool,
+    /// Translations of the page, rendered as `<link rel="alternate" hreflang>`.
+    #[serde(default)]
+    pub alternates: Vec<Alternate>,
 
     // Social
     pub og_image: Option<String>,
-    pub og_type: Option<String>,      // "website" | "article" | "book"…
-    pub twitter_card: Option<String>, // "summary_large_image"
+    pub og_image_alt: Option<String>,
+    pub og_image_width: Option<u32>,
+    pub og_image_height: Option<u32>,
+    pub og_image_type: Option<String>, // "image/png"
+    /// `"website"`, `"article"`…, or a table with the properties of the type (see [`OgType`]).
+    pub og_type: Option<OgType>,
+    /// URL shared for the page; `canonical_url` when unset.
+    pub og_url: Option<String>,
+    /// `language_TERRITORY` (`"fr_FR"`); `lang` when unset.
+    pub og_locale: Option<String>,
+    /// Other locales of the page; those of `alternates` when empty.
+    pub og_locale_alternates: Vec<String>,
+    pub twitter_card: Option<TwitterCard>,
+    pub twitter_site: Option<String>,      // "@site"
+    pub twitter_creator: Option<String>,   // "@author"
+    pub twitter_image_alt: Option<String>, // `og_image_alt` when unset
+    /// Rendered as `og:site_name`; set by [`Seo::with_defaults`].
+    pub site_name: Option<String>,
 
     // (Optionnel) Pour générer du JSON-LD
+    /// Raw JSON-LD, only rendered when it is valid JSON. Prefer `schemas`.
     pub json_ld: Option<String>,
+    /// Typed schema.org items, each rendered as a JSON-LD `<script>`; their empty fields are
+    /// filled from the fields above (see [`Schema::filled_from`]).
+    pub schemas: Vec<Schema>,
 
     // (Optionnel) Jardin
     pub content_type: Option<String>, // "work" | "author" | "season" | "event"
@@ -58,14 +100,45 @@ impl Seo {
         self.lang = Some(l.into());
         self
     }
+    pub fn noindex(&mut self) -> &mut Self {
+        self.noindex = true;
+        self
+    }
+    pub fn nofollow(&mut self) -> &mut Self {
+        self.nofollow = true;
+        self
+    }
+    /// Declares a translation of the page: `hreflang` is a locale (`"fr"`, `"en-GB"`) or `"x-default"`.
+    pub fn with_alternate<L: Into<String>, H: Into<String>>(
+        &mut self,
+        hreflang: L,
+        href: H,
+    ) -> &mut Self {
+        self.alternates.push(Alternate::new(hreflang, href));
+        self
+    }
     pub fn with_updated<S: Into<String>>(&mut self, u: S) -> &mut Self {
         self.updated = Some(u.into());
         self
     }
+    pub fn with_published<S: Into<String>>(&mut self, p: S) -> &mut Self {
+        self.published = Some(p.into());
+        self
+    }
     pub fn with_json_ld<S: Into<String>>(&mut self, j: S) -> &mut Self {
         self.json_ld = Some(j.into());
         self
     }
+    /// Adds a schema.org item; leave out what the page metadata already says.
+    pub fn with_schema(&mut self, schema: Schema) -> &mut Self {
+        self.schemas.push(schema);
+        self
+    }
+    /// Adds the schema.org item matching the page (see [`Schema::from_seo`]).
+    pub fn with_default_schema(&mut self) -> &mut Self {
+        self.schemas.push(Schema::from_seo(self));
+        self
+    }
     pub fn with_content_type<S: Into<String>>(&mut self, c: S) -> &mut Self {
         self.content_type = Some(c.into());
         self
@@ -82,16 +155,60 @@ impl Seo {
         self.og_image = Some(img.into());
         self
     }
-    pub fn with_og_type<S: Into<String>>(&mut self, t: S) -> &mut Self {
+    pub fn with_og_image_alt<S: Into<String>>(&mut self, alt: S) -> &mut Self {
+        self.og_image_alt = Some(alt.into());
+        self
+    }
+    pub fn with_og_image_size(&mut self, width: u32, height: u32) -> &mut Self {
+        self.og_image_width = Some(width);
+        self.og_image_height = Some(height);
+        self
+    }
+    pub fn with_og_image_type<S: Into<String>>(&mut self, mime: S) -> &mut Self {
+        self.og_image_type = Some(mime.into());
+        self
+    }
+    /// Takes an [`OgType`] or its name, e.g. `"article"` (see [`OgType::from`]).
+    pub fn with_og_type<T: Into<OgType>>(&mut self, t: T) -> &mut Self {
         self.og_type = Some(t.into());
         self
     }
-    pub fn with_twitter_card<S: Into<String>>(&mut self, c: S) -> &mut Self {
+    pub fn with_og_url<S: Into<String>>(&mut self, url: S) -> &mut Self {
+        self.og_url = Some(url.into());
+        self
+    }
+    pub fn with_og_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self {
+        self.og_locale = Some(locale.into());
+        self
+    }
+    pub fn with_og_locale_alternate<S: Into<String>>(&mut self, locale: S) -> &mut Self {
+        self.og_locale_alternates.push(locale.into());
+        self
+    }
+    /// Takes a [`TwitterCard`] or its name, e.g. `"summary"` (see [`TwitterCard::from`]).
+    pub fn with_twitter_card<C: Into<TwitterCard>>(&mut self, c: C) -> &mut Self {
         self.twitter_card = Some(c.into());
         self
     }
     pub fn twitter_summary(&mut self) -> &mut Self {
-        self.twitter_card = Some("summary".to_string());
+        self.twitter_card = Some(TwitterCard::Summary);
+        self
+    }
+    pub fn with_twitter_site<S: Into<String>>(&mut self, handle: S) -> &mut Self {
+        self.twitter_site = Some(handle.into());
+        self
+    }
+    pub fn with_twitter_creator<S: Into<String>>(&mut self, handle: S) -> &mut Self {
+        self.twitter_creator = Some(handle.into());
+        self
+    }
+    pub fn with_twitter_image_alt<S: Into<String>>(&mut self, alt: S) -> &mut Self {
+        self.twitter_image_alt = Some(alt.into());
+        self
+    }
+    /// Rendered as `og:site_name`; [`Seo::with_defaults`] sets it from the site settings.
+    pub fn with_site_name<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.site_name = Some(name.into());
         self
     }
 
@@ -113,13 +230,36 @@ impl Seo {
         if !other.keywords.is_empty() {
             self.keywords = other.keywords.clone();
         }
+        if !other.alternates.is_empty() {
+            self.alternates = other.alternates.clone();
+        }
+        if !other.schemas.is_empty() {
+            self.schemas = other.schemas.clone();
+        }
+        self.noindex |= other.noindex;
+        self.nofollow |= other.nofollow;
+        if !other.og_locale_alternates.is_empty() {
+            self.og_locale_alternates = other.og_locale_alternates.clone();
+        }
         take_if_some!(author);
         take_if_some!(canonical_url);
         take_if_some!(lang);
         take_if_some!(updated);
+        take_if_some!(published);
+        take_if_some!(reading_time);
         take_if_some!(og_image);
+        take_if_some!(og_image_alt);
+        take_if_some!(og_image_width);
+        take_if_some!(og_image_height);
+        take_if_some!(og_image_type);
         take_if_some!(og_type);
+        take_if_some!(og_url);
+        take_if_some!(og_locale);
         take_if_some!(twitter_card);
+        take_if_some!(twitter_site);
+        take_if_some!(twitter_creator);
+        take_if_some!(twitter_image_alt);
+        take_if_some!(site_name);
         take_if_some!(json_ld);
         take_if_some!(content_type);
         take_if_some!(slug);
@@ -127,9 +267,17 @@ impl Seo {
     }
 
     /// Provide sane fallbacks (site-wide defaults).
+    ///
+    /// Page titles go through the title template of the site, and pages without a title get the
+    /// site title. With a `base_url`, relative canonical, `og:url`, image and alternate URLs are
+    /// made absolute. Apply once: the title template would be applied again.
     pub fn with_defaults(mut self, site: &SiteSeoDefaults) -> Self {
         if self.title.is_empty() {
             self.title = site.site_title.clone();
+        } else if let Some(template) = &site.title_template {
+            self.title = template
+                .replace("{title}", &self.title)
+                .replace("{site}", &site.site_name);
         }
         if self.description.is_empty() {
             self.description = site.site_desc.clone();
@@ -137,11 +285,41 @@ impl Seo {
         if self.lang.is_none() {
             self.lang = Some(site.lang.clone());
         }
+        if self.og_locale.is_none() {
+            self.og_locale = site.locale.clone();
+        }
+        if self.author.is_none() {
+            self.author = site.author.clone();
+        }
+        if self.og_image.is_none() {
+            self.og_image = site.og_image.clone();
+        }
         if self.og_type.is_none() {
-            self.og_type = Some("website".into());
+            self.og_type = Some(OgType::Website);
         }
         if self.twitter_card.is_none() {
-            self.twitter_card = Some("summary_large_image".into());
+            self.twitter_card = Some(TwitterCard::SummaryLargeImage);
+        }
+        if self.twitter_site.is_none() {
+            self.twitter_site = site.twitter_site.clone();
+        }
+        if self.site_name.is_none() {
+            self.site_name = Some(site.site_name.clone());
+        }
+        if let Some(base_url) = &site.base_url {
+            for url in [
+                &mut self.canonical_url,
+                &mut self.og_url,
+                &mut self.og_image,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *url = absolute_url(base_url, url);
+            }
+            for alternate in &mut self.alternates {
+                alternate.href = absolute_url(base_url, &alternate.href);
+            }
         }
         self
     }
@@ -156,131 +334,149 @@ impl Seo {
             "canonical_url": self.canonical_url,
             "lang": self.lang,
             "updated": self.updated,
+            "published": self.published,
+            "reading_time": self.reading_time,
+            "noindex": self.noindex,
+            "nofollow": self.nofollow,
+            "alternates": self.alternates,
             "og_image": self.og_image,
-            "og_type": self.og_type,
+            "og_image_alt": self.og_image_alt,
+            "og_image_width": self.og_image_width,
+            "og_image_height": self.og_image_height,
+            "og_image_type": self.og_image_type,
+            "og_type": self.og_type.as_ref().map(OgType::name),
+            "og_properties": self.og_properties(),
+            "og_url": self.og_url(),
+            "og_locale": self.og_locale(),
+            "og_locale_alternates": self.og_locale_alternates(),
             "twitter_card": self.twitter_card,
+            "twitter_site": self.twitter_site,
+            "twitter_creator": self.twitter_creator,
+            "twitter_image_alt": self.twitter_image_alt(),
             "json_ld": self.json_ld,
+            "json_ld_scripts": self.json_ld_scripts(),
             "content_type": self.content_type,
-            "slug": self.slug
+            "slug": self.slug,
+            "site_name": self.site_name
         })
     }
 
-    /// Render a ready-to-inject `<head>` HTML string (useful for non-Tera engines too).
-    pub fn render_head(&self, site_name: impl Into<Cow<'static, str>>) -> String {
-        let site_name = site_name.into();
-        let kw = if self.keywords.is_empty() {
-            String::new()
-        } else {
-            self.keywords.join(", ")
-        };
-        let mut out = String::new();
-        use std::fmt::Write;
-
-        // basics
-        let _ = writeln!(out, r#"<meta charset="utf-8">"#);
-        if let Some(lang) = &self.lang {
-            let _ = writeln!(
-                out,
-                r#"<meta http-equiv="content-language" content="{lang}">"#
-            );
-        }
-        let _ = writeln!(
-            out,
-            r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#
-        );
-        if !self.description.is_empty() {
-            let _ = writeln!(
-                out,
-                r#"<meta name="description" content="{}">"#,
-                html_escape(&self.description)
-            );
-        }
-        if !kw.is_empty() {
-            let _ = writeln!(
-                out,
-                r#"<meta name="keywords" content="{}">"#,
-                html_escape(&kw)
-            );
-        }
-        if let Some(a) = &self.author {
-            let _ = writeln!(out, r#"<meta name="author" content="{}">"#, html_escape(a));
-        }
-        if let Some(c) = &self.canonical_url {
-            let _ = writeln!(out, r#"<link rel="canonical" href="{}">"#, html_escape(c));
-        }
+    /// `og_url`, or else `canonical_url`.
+    pub fn og_url(&self) -> Option<&str> {
+        self.og_url.as_deref().or(self.canonical_url.as_deref())
+    }
 
-        // Open Graph
-        let _ = writeln!(
-            out,
-            r#"<meta property="og:title" content="{}">"#,
-            html_escape(&self.title)
-        );
-        if !self.description.is_empty() {
-            let _ = writeln!(
-                out,
-                r#"<meta property="og:description" content="{}">"#,
-                html_escape(&self.description)
-            );
-        }
-        if let Some(t) = &self.og_type {
-            let _ = writeln!(
-                out,
-                r#"<meta property="og:type" content="{}">"#,
-                html_escape(t)
-            );
-        }
-        if let Some(img) = &self.og_image {
-            let _ = writeln!(
-                out,
-                r#"<meta property="og:image" content="{}">"#,
-                html_escape(img)
-            );
-        }
-        let _ = writeln!(
-            out,
-            r#"<meta property="og:site_name" content="{}">"#,
-            html_escape(&site_name)
-        );
+    /// `og_locale`, or else `lang` written the Open Graph way (`en-GB` becomes `en_GB`).
+    pub fn og_locale(&self) -> Option<String> {
+        self.og_locale
+            .clone()
+            .or_else(|| self.lang.as_deref().map(og_locale_of))
+    }
 
-        // Twitter
-        if let Some(card) = &self.twitter_card {
-            let _ = writeln!(
-                out,
-                r#"<meta name="twitter:card" content="{}">"#,
-                html_escape(card)
-            );
+    /// `og_locale_alternates`, or else the locales of the `alternates` other than the page's own.
+    pub fn og_locale_alternates(&self) -> Vec<String> {
+        if !self.og_locale_alternates.is_empty() {
+            return self.og_locale_alternates.clone();
         }
-        let _ = writeln!(
-            out,
-            r#"<meta name="twitter:title" content="{}">"#,
-            html_escape(&self.title)
-        );
-        if !self.description.is_empty() {
-            let _ = writeln!(
-                out,
-                r#"<meta name="twitter:description" content="{}">"#,
-                html_escape(&self.description)
-            );
-        }
-        if let Some(img) = &self.og_image {
-            let _ = writeln!(
-                out,
-                r#"<meta name="twitter:image" content="{}">"#,
-                html_escape(img)
-            );
+        let own = self.og_locale();
+        let mut locales: Vec<String> = Vec::new();
+        for alternate in &self.alternates {
+            let locale = og_locale_of(&alternate.hreflang);
+            if alternate.hreflang != "x-default"
+                && own.as_ref() != Some(&locale)
+                && !locales.contains(&locale)
+            {
+                locales.push(locale);
+            }
         }
+        locales
+    }
+
+    /// `twitter_image_alt`, or else `og_image_alt`.
+    pub fn twitter_image_alt(&self) -> Option<&str> {
+        self.twitter_image_alt
+            .as_deref()
+            .or(self.og_image_alt.as_deref())
+    }
 
-        // JSON-LD
-        if let Some(ld) = &self.json_ld {
-            let _ = writeln!(out, r#"<script type="application/ld+json">{}</script>"#, ld);
+    /// The properties of the `og_type` (see [`OgType::properties`]). Articles default to the
+    /// `published` and `updated` dates, the `author` and the `keywords` of the page.
+    pub fn og_properties(&self) -> Vec<(String, String)> {
+        match &self.og_type {
+            Some(OgType::Article(article)) => {
+                let mut article = article.clone();
+                if article.published_time.is_none() {
+                    article.published_time = self.published.clone();
+                }
+                if article.modified_time.is_none() {
+                    article.modified_time = self.updated.clone();
+                }
+                if article.author.is_empty() {
+                    article.author = self.author.iter().cloned().collect();
+                }
+                if article.tag.is_empty() {
+                    article.tag = self.keywords.clone();
+                }
+                OgType::Article(article).properties()
+            }
+            Some(og_type) => og_type.properties(),
+            None => Vec::new(),
         }
+    }
+
+    /// The JSON-LD of the page, one document per `<script type="application/ld+json">`, safe to
+    /// embed as is: the raw `json_ld` when it is valid JSON, then every item of `schemas`.
+    pub fn json_ld_scripts(&self) -> Vec<String> {
+        let raw = self
+            .json_ld
+            .as_deref()
+            .and_then(|ld| serde_json::from_str::<serde_json::Value>(ld).ok())
+            .map(|ld| script_json(&ld));
+        raw.into_iter()
+            .chain(
+                self.schemas
+                    .iter()
+                    .map(|schema| schema.clone().filled_from(self).to_script_json()),
+            )
+            .collect()
+    }
+
+    /// Render a ready-to-inject `<head>` HTML string (useful for non-Tera engines too).
+    ///
+    /// Use a [`HeadBuilder`] to add site-wide elements (favicons, feeds…) or to minify it.
+    ///
+    /// This used to take the site name as an argument; `og:site_name` now comes from
+    /// [`Seo::site_name`], so `seo.render_head(name)` becomes
+    /// `seo.with_site_name(name).render_head()`, or is left to [`Seo::with_defaults`].
+    pub fn render_head(&self) -> String {
+        HeadBuilder::new().seo(self).render()
+    }
+}
 
-        out
+/// A translation of a page, for `<link rel="alternate" hreflang>` and sitemaps.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Alternate {
+    /// A locale (`"fr"`, `"en-GB"`) or `"x-default"` for the fallback page.
+    pub hreflang: String,
+    /// Absolute URL (or site-relative path) of the translation.
+    pub href: String,
+}
+impl Alternate {
+    pub fn new<L: Into<String>, H: Into<String>>(hreflang: L, href: H) -> Self {
+        Self {
+            hreflang: hreflang.into(),
+            href: href.into(),
+        }
     }
 }
 
+/// Writes a BCP 47 language tag as an Open Graph locale: `en-GB` becomes `en_GB`.
+fn og_locale_of(lang: &str) -> String {
+    lang.replace('-', "_")
+}
+
 /// Small HTML escaper for meta attributes.
-fn html_escape(s: &str) -> String {
+pub(crate) fn html_escape(s: &str) -> String {
     s.chars()
         .flat_map(|c| match c {
             '&' => "&amp;".chars().collect::<Vec<_>>(),
@@ -293,18 +489,297 @@ fn html_escape(s: &str) -> String {
         .collect()
 }
 
-#[derive(Debug, Clone)]
+/// Makes `url` absolute against `base_url`, unless it already is.
+fn absolute_url(base_url: &str, url: &str) -> String {
+    if url.contains("://") || url.starts_with("//") {
+        return url.to_string();
+    }
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+/// Site-wide SEO settings, applied by [`Seo::with_defaults`].
+///
+/// # Fields
+///
+/// * `site_title` - Title of the pages that have none, e.g. the home page.
+/// * `site_desc` - Description of the pages that have none.
+/// * `lang` - Language of the pages that don't say.
+/// * `site_name` - The name of the site, for `og:site_name` and `{site}` in `title_template`;
+///   `site_title` by default.
+/// * `base_url` - e.g. `https://example.com`, to make relative URLs absolute.
+/// * `title_template` - Wraps page titles, e.g. `{title} | {site}`.
+/// * `og_image` - Image of the pages that have none.
+/// * `twitter_site` - `@handle` of the site.
+/// * `author` - Author of the pages that don't say.
+/// * `locale` - `og:locale` of the pages that don't say, e.g. `fr_FR`.
+///
+/// They can be read from a file with [`SiteSeoDefaults::from_file`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SiteSeoDefaults {
     pub site_title: String,
     pub site_desc: String,
     pub lang: String,
+    #[serde(default)]
+    pub site_name: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub title_template: Option<String>,
+    #[serde(default)]
+    pub og_image: Option<String>,
+    #[serde(default)]
+    pub twitter_site: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 impl SiteSeoDefaults {
     pub fn new<S: Into<String>>(title: S, desc: S, lang: S) -> Self {
+        let site_title = title.into();
         Self {
-            site_title: title.into(),
+            site_name: site_title.clone(),
+            site_title,
             site_desc: desc.into(),
             lang: lang.into(),
+            base_url: None,
+            title_template: None,
+            og_image: None,
+            twitter_site: None,
+            author: None,
+            locale: None,
+        }
+    }
+
+    /// Reads the settings from a TOML file, or a JSON one when `path` ends with `.json`.
+    ///
+    /// ```toml
+    /// site_title = "Example"
+    /// site_desc = "What the site is about."
+    /// lang = "en"
+    /// base_url = "https://example.org"
+    /// title_template = "{title} | {site}"
+    /// ```
+    ///
+    /// `site_title`, `site_desc` and `lang` are required; `site_name` defaults to `site_title`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the file cannot be read, misses a required field or has an unknown one.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read site settings {}", path.display()))?;
+        let mut site: Self = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&source)?,
+            _ => toml::from_str(&source)?,
+        };
+        if site.site_name.is_empty() {
+            site.site_name = site.site_title.clone();
+        }
+        Ok(site)
+    }
+
+    pub fn with_site_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.site_name = name.into();
+        self
+    }
+    pub fn with_base_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+    /// `{title}` is replaced with the page title and `{site}` with the site name.
+    pub fn with_title_template<S: Into<String>>(mut self, template: S) -> Self {
+        self.title_template = Some(template.into());
+        self
+    }
+    pub fn with_og_image<S: Into<String>>(mut self, image: S) -> Self {
+        self.og_image = Some(image.into());
+        self
+    }
+    pub fn with_twitter_site<S: Into<String>>(mut self, handle: S) -> Self {
+        self.twitter_site = Some(handle.into());
+        self
+    }
+    pub fn with_author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+    pub fn with_locale<S: Into<String>>(mut self, locale: S) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn site_defaults_read_from_toml_and_json() {
+        let dir = std::env::temp_dir().join(format!("jigi-site-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let toml = dir.join("site.toml");
+        std::fs::write(
+            &toml,
+            "site_title = \"Example\"\nsite_desc = \"About\"\nlang = \"en\"\nbase_url = \"https://example.org\"\n",
+        )
+        .unwrap();
+        let site = SiteSeoDefaults::from_file(&toml).unwrap();
+        assert_eq!(site.site_name, "Example");
+        assert_eq!(site.base_url.as_deref(), Some("https://example.org"));
+
+        let json = dir.join("site.json");
+        std::fs::write(
+            &json,
+            r#"{ "site_title": "Example", "site_desc": "", "lang": "en", "site_name": "Ex" }"#,
+        )
+        .unwrap();
+        assert_eq!(SiteSeoDefaults::from_file(&json).unwrap().site_name, "Ex");
+
+        std::fs::write(
+            &toml,
+            "site_title = \"Example\"\nsite_desc = \"\"\nlang = \"en\"\ntypo = 1\n",
+        )
+        .unwrap();
+        assert!(SiteSeoDefaults::from_file(&toml).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn site() -> SiteSeoDefaults {
+        SiteSeoDefaults::new("Example", "About the site.", "en")
+            .with_site_name("Ex")
+            .with_base_url("https://example.org/")
+            .with_title_template("{title} | {site}")
+            .with_og_image("/default.png")
+            .with_twitter_site("@example")
+            .with_locale("en_US")
+    }
+
+    #[test]
+    fn with_defaults_fills_what_the_page_leaves_out() {
+        let seo = Seo::new().with_defaults(&site());
+        assert_eq!(seo.title, "Example");
+        assert_eq!(seo.description, "About the site.");
+        assert_eq!(seo.lang.as_deref(), Some("en"));
+        assert_eq!(seo.site_name.as_deref(), Some("Ex"));
+        assert_eq!(seo.og_locale.as_deref(), Some("en_US"));
+        assert_eq!(seo.og_type, Some(OgType::Website));
+        assert_eq!(seo.twitter_card, Some(TwitterCard::SummaryLargeImage));
+        assert_eq!(seo.twitter_site.as_deref(), Some("@example"));
+        assert_eq!(
+            seo.og_image.as_deref(),
+            Some("https://example.org/default.png")
+        );
+    }
+
+    #[test]
+    fn with_defaults_keeps_page_values_and_makes_urls_absolute() {
+        let mut seo = Seo::new();
+        seo.with_title("Hello")
+            .with_desc("A page.")
+            .with_lang("fr")
+            .with_canonical("/hello")
+            .with_og_image("//cdn.example.org/hello.png")
+            .with_alternate("en", "https://example.com/en/hello")
+            .with_alternate("fr", "fr/hello");
+        let seo = seo.with_defaults(&site());
+        assert_eq!(seo.title, "Hello | Ex");
+        assert_eq!(seo.description, "A page.");
+        assert_eq!(seo.lang.as_deref(), Some("fr"));
+        assert_eq!(
+            seo.canonical_url.as_deref(),
+            Some("https://example.org/hello")
+        );
+        assert_eq!(seo.og_image.as_deref(), Some("//cdn.example.org/hello.png"));
+        assert_eq!(
+            seo.alternates,
+            [
+                Alternate::new("en", "https://example.com/en/hello"),
+                Alternate::new("fr", "https://example.org/fr/hello"),
+            ]
+        );
+    }
+
+    #[test]
+    fn with_defaults_leaves_relative_urls_without_a_base_url() {
+        let mut seo = Seo::new();
+        seo.with_title("Hello").with_canonical("/hello");
+        let site = SiteSeoDefaults::new("Example", "", "en");
+        let seo = seo.with_defaults(&site);
+        assert_eq!(seo.title, "Hello");
+        assert_eq!(seo.site_name.as_deref(), Some("Example"));
+        assert_eq!(seo.canonical_url.as_deref(), Some("/hello"));
+        assert_eq!(seo.og_image, None);
+    }
+
+    fn article() -> Seo {
+        let mut seo = Seo::new();
+        seo.title = "Hello".into();
+        seo.lang = Some("en-GB".into());
+        seo.canonical_url = Some("https://example.org/hello".into());
+        seo.author = Some("Ada".into());
+        seo.keywords = vec!["rust".into()];
+        seo.published = Some("2025-05-20".into());
+        seo.alternates = vec![
+            Alternate::new("en-GB", "/hello"),
+            Alternate::new("fr", "/fr/hello"),
+            Alternate::new("x-default", "/hello"),
+        ];
+        seo.with_og_type("article")
+            .with_og_image("https://example.org/hello.png")
+            .with_og_image_alt("A wave")
+            .with_twitter_site("@example");
+        seo
+    }
+
+    #[test]
+    fn og_fields_fall_back_to_the_page_metadata() {
+        let seo = article();
+        assert_eq!(seo.og_url(), Some("https://example.org/hello"));
+        assert_eq!(seo.og_locale().as_deref(), Some("en_GB"));
+        assert_eq!(seo.og_locale_alternates(), ["fr"]);
+        assert_eq!(seo.twitter_image_alt(), Some("A wave"));
+        assert_eq!(
+            seo.og_properties(),
+            [
+                ("article:author".to_string(), "Ada".to_string()),
+                (
+                    "article:published_time".to_string(),
+                    "2025-05-20".to_string()
+                ),
+                ("article:tag".to_string(), "rust".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn render_head_writes_open_graph_and_twitter_tags() {
+        let head = article().render_head();
+        for tag in [
+            r#"<meta property="og:type" content="article">"#,
+            r#"<meta property="og:url" content="https://example.org/hello">"#,
+            r#"<meta property="og:locale" content="en_GB">"#,
+            r#"<meta property="og:locale:alternate" content="fr">"#,
+            r#"<meta property="og:image" content="https://example.org/hello.png">"#,
+            r#"<meta property="article:published_time" content="2025-05-20">"#,
+            r#"<meta name="twitter:site" content="@example">"#,
+            r#"<meta name="twitter:image:alt" content="A wave">"#,
+        ] {
+            assert!(head.contains(tag), "{tag} missing from\n{head}");
         }
     }
+
+    #[test]
+    fn render_head_takes_the_site_name_from_the_seo() {
+        assert!(!article().render_head().contains("og:site_name"));
+        let head = article().with_site_name("Example & Co").render_head();
+        assert!(head.contains(r#"<meta property="og:site_name" content="Example &amp; Co">"#));
+    }
 }
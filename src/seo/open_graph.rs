@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// The Open Graph type of a page (`og:type`), with the properties specific to it.
+///
+/// Serialized as the bare type name (`"website"`, `"article"`, `"video.movie"`…) when it has no
+/// properties, and as a table otherwise:
+///
+/// ```toml
+/// [seo.og_type]
+/// type = "article"
+/// published_time = "2025-05-20T08:00:00Z"
+/// section = "Essays"
+/// ```
+///
+/// Unknown types and properties are rejected.
+///
+/// # Variants
+///
+/// - `Website`: The default, for home and landing pages.
+/// - `Article`: A post or a news item.
+/// - `Book`: A book.
+/// - `Profile`: A person.
+/// - `Video`: A movie, an episode, a TV show or another video (`video.movie`…).
+/// - `Music`: A song, an album, a playlist or a radio station (`music.song`…).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "OgTypeRepr", into = "OgTypeRepr")]
+pub enum OgType {
+    Website,
+    Article(ArticleProperties),
+    Book(BookProperties),
+    Profile(ProfileProperties),
+    Video(VideoKind, VideoProperties),
+    Music(MusicKind, MusicProperties),
+}
+
+/// The `article:*` properties.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArticleProperties {
+    /// ISO8601 datetime.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_time: Option<String>,
+    /// ISO8601 datetime.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_time: Option<String>,
+    /// ISO8601 datetime after which the article is out of date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_time: Option<String>,
+    /// Names or profile URLs of the authors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub author: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag: Vec<String>,
+}
+
+/// The `book:*` properties.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BookProperties {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub author: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isbn: Option<String>,
+    /// ISO8601 date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag: Vec<String>,
+}
+
+/// The `profile:*` properties.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gender: Option<String>,
+}
+
+/// The `video:*` properties.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoProperties {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub actor: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub director: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writer: Vec<String>,
+    /// Length in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    /// ISO8601 date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag: Vec<String>,
+}
+
+/// The `music:*` properties.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MusicProperties {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub musician: Vec<String>,
+    /// Length in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    /// ISO8601 date.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+}
+
+/// The kinds of `video.*` pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VideoKind {
+    Movie,
+    Episode,
+    TvShow,
+    #[default]
+    Other,
+}
+
+/// The kinds of `music.*` pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MusicKind {
+    #[default]
+    Song,
+    Album,
+    Playlist,
+    RadioStation,
+}
+
+impl OgType {
+    /// An `article` without properties yet.
+    pub fn article() -> Self {
+        OgType::Article(ArticleProperties::default())
+    }
+
+    /// The `og:type` value, e.g. `"video.movie"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OgType::Website => "website",
+            OgType::Article(_) => "article",
+            OgType::Book(_) => "book",
+            OgType::Profile(_) => "profile",
+            OgType::Video(VideoKind::Movie, _) => "video.movie",
+            OgType::Video(VideoKind::Episode, _) => "video.episode",
+            OgType::Video(VideoKind::TvShow, _) => "video.tv_show",
+            OgType::Video(VideoKind::Other, _) => "video.other",
+            OgType::Music(MusicKind::Song, _) => "music.song",
+            OgType::Music(MusicKind::Album, _) => "music.album",
+            OgType::Music(MusicKind::Playlist, _) => "music.playlist",
+            OgType::Music(MusicKind::RadioStation, _) => "music.radio_station",
+        }
+    }
+
+    /// The type-specific properties, as `(property, content)` pairs ready for
+    /// `<meta property content>`, e.g. `("article:section", "Essays")`.
+    pub fn properties(&self) -> Vec<(String, String)> {
+        let (namespace, properties) = match self {
+            OgType::Website => return Vec::new(),
+            OgType::Article(p) => ("article", serde_json::to_value(p)),
+            OgType::Book(p) => ("book", serde_json::to_value(p)),
+            OgType::Profile(p) => ("profile", serde_json::to_value(p)),
+            OgType::Video(_, p) => ("video", serde_json::to_value(p)),
+            OgType::Music(_, p) => ("music", serde_json::to_value(p)),
+        };
+        let Ok(Value::Object(properties)) = properties else {
+            return Vec::new();
+        };
+        let mut pairs = Vec::new();
+        for (name, value) in properties {
+            let values = match value {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            for value in values {
+                let content = match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                pairs.push((format!("{namespace}:{name}"), content));
+            }
+        }
+        pairs
+    }
+}
+
+impl fmt::Display for OgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OgType {
+    type Err = String;
+
+    /// Parses an `og:type` value; `"video"` and `"music"` stand for `video.other` and
+    /// `music.song`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "website" => OgType::Website,
+            "article" => OgType::article(),
+            "book" => OgType::Book(BookProperties::default()),
+            "profile" => OgType::Profile(ProfileProperties::default()),
+            "video.movie" => OgType::Video(VideoKind::Movie, VideoProperties::default()),
+            "video.episode" => OgType::Video(VideoKind::Episode, VideoProperties::default()),
+            "video.tv_show" => OgType::Video(VideoKind::TvShow, VideoProperties::default()),
+            "video" | "video.other" => OgType::Video(VideoKind::Other, VideoProperties::default()),
+            "music" | "music.song" => OgType::Music(MusicKind::Song, MusicProperties::default()),
+            "music.album" => OgType::Music(MusicKind::Album, MusicProperties::default()),
+            "music.playlist" => OgType::Music(MusicKind::Playlist, MusicProperties::default()),
+            "music.radio_station" => {
+                OgType::Music(MusicKind::RadioStation, MusicProperties::default())
+            }
+            other => {
+                return Err(format!(
+                    "unknown og_type `{other}` (expected website, article, book, profile, \
+                     video.movie, video.episode, video.tv_show, video.other, music.song, \
+                     music.album, music.playlist or music.radio_station)"
+                ));
+            }
+        })
+    }
+}
+
+impl From<&str> for OgType {
+    /// Parses an `og:type` value like [`OgType::from_str`], for builders such as
+    /// [`Seo::with_og_type`](super::Seo::with_og_type). An unknown name is logged and replaced
+    /// with `website`; use `parse` to handle it instead.
+    fn from(name: &str) -> Self {
+        name.parse().unwrap_or_else(|error| {
+            log::warn!("{error}, using `website`");
+            OgType::Website
+        })
+    }
+}
+
+/// How an [`OgType`] is written in manifests: a name, or a table of properties with a `type`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OgTypeRepr {
+    Name(String),
+    Typed {
+        #[serde(rename = "type")]
+        name: String,
+        #[serde(flatten)]
+        properties: Map<String, Value>,
+    },
+}
+
+impl TryFrom<OgTypeRepr> for OgType {
+    type Error = String;
+
+    fn try_from(repr: OgTypeRepr) -> Result<Self, Self::Error> {
+        let (name, properties) = match repr {
+            OgTypeRepr::Name(name) => return name.parse(),
+            OgTypeRepr::Typed { name, properties } => (name, Value::Object(properties)),
+        };
+        fn parse<T: serde::de::DeserializeOwned>(name: &str, value: Value) -> Result<T, String> {
+            serde_json::from_value(value).map_err(|error| format!("og_type `{name}`: {error}"))
+        }
+        Ok(match name.parse()? {
+            OgType::Website if properties.as_object().is_some_and(|p| !p.is_empty()) => {
+                return Err("og_type `website` has no properties".to_string());
+            }
+            OgType::Website => OgType::Website,
+            OgType::Article(_) => OgType::Article(parse(&name, properties)?),
+            OgType::Book(_) => OgType::Book(parse(&name, properties)?),
+            OgType::Profile(_) => OgType::Profile(parse(&name, properties)?),
+            OgType::Video(kind, _) => OgType::Video(kind, parse(&name, properties)?),
+            OgType::Music(kind, _) => OgType::Music(kind, parse(&name, properties)?),
+        })
+    }
+}
+
+impl From<OgType> for OgTypeRepr {
+    fn from(og_type: OgType) -> Self {
+        let name = og_type.name().to_string();
+        let properties = match og_type {
+            OgType::Website => Ok(Value::Null),
+            OgType::Article(p) => serde_json::to_value(p),
+            OgType::Book(p) => serde_json::to_value(p),
+            OgType::Profile(p) => serde_json::to_value(p),
+            OgType::Video(_, p) => serde_json::to_value(p),
+            OgType::Music(_, p) => serde_json::to_value(p),
+        };
+        match properties {
+            Ok(Value::Object(properties)) if !properties.is_empty() => {
+                OgTypeRepr::Typed { name, properties }
+            }
+            _ => OgTypeRepr::Name(name),
+        }
+    }
+}
+
+/// The Twitter card layout (`twitter:card`).
+///
+/// # Variants
+///
+/// - `Summary`: A small square image next to the title.
+/// - `SummaryLargeImage`: A large image above the title, the default.
+/// - `App`: A mobile app download card.
+/// - `Player`: An inline video or audio player.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TwitterCard {
+    Summary,
+    #[default]
+    SummaryLargeImage,
+    App,
+    Player,
+}
+
+impl TwitterCard {
+    /// The `twitter:card` value, e.g. `"summary_large_image"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TwitterCard::Summary => "summary",
+            TwitterCard::SummaryLargeImage => "summary_large_image",
+            TwitterCard::App => "app",
+            TwitterCard::Player => "player",
+        }
+    }
+}
+
+impl fmt::Display for TwitterCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TwitterCard {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "summary" => Ok(TwitterCard::Summary),
+            "summary_large_image" => Ok(TwitterCard::SummaryLargeImage),
+            "app" => Ok(TwitterCard::App),
+            "player" => Ok(TwitterCard::Player),
+            other => Err(format!(
+                "unknown twitter_card `{other}` (expected summary, summary_large_image, app or \
+                 player)"
+            )),
+        }
+    }
+}
+
+impl From<&str> for TwitterCard {
+    /// Parses a `twitter:card` value like [`TwitterCard::from_str`]. An unknown name is logged
+    /// and replaced with the default, `summary_large_image`; use `parse` to handle it instead.
+    fn from(name: &str) -> Self {
+        name.parse().unwrap_or_else(|error| {
+            log::warn!("{error}, using `summary_large_image`");
+            TwitterCard::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Page {
+        og_type: OgType,
+        twitter_card: TwitterCard,
+    }
+
+    fn round_trip(toml: &str) -> Page {
+        let page: Page = toml::from_str(toml).unwrap();
+        let written = toml::to_string(&page).unwrap();
+        assert_eq!(toml::from_str::<Page>(&written).unwrap(), page, "{written}");
+        page
+    }
+
+    #[test]
+    fn og_type_names_round_trip() {
+        for name in [
+            "website",
+            "article",
+            "book",
+            "profile",
+            "video.tv_show",
+            "music.radio_station",
+        ] {
+            let page = round_trip(&format!("og_type = \"{name}\"\ntwitter_card = \"summary\""));
+            assert_eq!(page.og_type.name(), name);
+            assert_eq!(page.twitter_card, TwitterCard::Summary);
+        }
+    }
+
+    #[test]
+    fn og_type_tables_round_trip() {
+        let page = round_trip(
+            "twitter_card = \"player\"\n[og_type]\ntype = \"article\"\nsection = \"Essays\"\ntag = [\"rust\"]\n",
+        );
+        let OgType::Article(article) = &page.og_type else {
+            panic!("not an article: {:?}", page.og_type);
+        };
+        assert_eq!(article.section.as_deref(), Some("Essays"));
+        assert_eq!(
+            page.og_type.properties(),
+            [
+                ("article:section".to_string(), "Essays".to_string()),
+                ("article:tag".to_string(), "rust".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_types_and_properties_are_rejected() {
+        let parse = |toml: &str| toml::from_str::<Page>(toml);
+        assert!(parse("og_type = \"blog\"\ntwitter_card = \"app\"").is_err());
+        assert!(parse("og_type = \"website\"\ntwitter_card = \"large\"").is_err());
+        let extra = "twitter_card = \"app\"\n[og_type]\ntype = \"article\"\nisbn = \"1\"\n";
+        assert!(parse(extra).is_err());
+    }
+
+    #[test]
+    fn from_str_falls_back_to_defaults() {
+        assert_eq!(
+            OgType::from("video"),
+            OgType::Video(VideoKind::Other, VideoProperties::default())
+        );
+        assert_eq!(OgType::from("blog"), OgType::Website);
+        assert_eq!(TwitterCard::from("summary"), TwitterCard::Summary);
+        assert_eq!(TwitterCard::from("large"), TwitterCard::SummaryLargeImage);
+    }
+}
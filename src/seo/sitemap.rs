@@ -0,0 +1,77 @@
+use super::{Alternate, html_escape};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// One page of a sitemap.
+///
+/// `loc` and the `href` of `alternates` may be site-relative (`/fr/about`): [`render_sitemap`]
+/// prefixes them with the site's base URL.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    /// Last modification date, ISO8601 (`2024-05-01`).
+    pub lastmod: Option<String>,
+    /// Translations of the page, emitted as `<xhtml:link rel="alternate" hreflang>`.
+    pub alternates: Vec<Alternate>,
+}
+
+impl SitemapEntry {
+    pub fn new<S: Into<String>>(loc: S) -> Self {
+        Self {
+            loc: loc.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Renders a `sitemap.xml` document.
+///
+/// # Arguments
+///
+/// * `base_url` - The site origin, e.g. `https://example.org`, used for relative locations.
+/// * `entries` - The pages to list. Locale variants should each have their own entry, with the
+///   same alternates, as search engines expect.
+///
+/// # Returns
+///
+/// The XML document, with the `xhtml` namespace declared for hreflang alternates.
+pub fn render_sitemap<'a>(
+    base_url: &str,
+    entries: impl IntoIterator<Item = &'a SitemapEntry>,
+) -> String {
+    let absolute = |href: &str| {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            href.to_string()
+        } else {
+            format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                href.trim_start_matches('/')
+            )
+        }
+    };
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:xhtml="http://www.w3.org/1999/xhtml">"#
+    );
+    for entry in entries {
+        let _ = writeln!(out, "  <url>");
+        let _ = writeln!(out, "    <loc>{}</loc>", html_escape(&absolute(&entry.loc)));
+        if let Some(lastmod) = &entry.lastmod {
+            let _ = writeln!(out, "    <lastmod>{}</lastmod>", html_escape(lastmod));
+        }
+        for alt in &entry.alternates {
+            let _ = writeln!(
+                out,
+                r#"    <xhtml:link rel="alternate" hreflang="{}" href="{}"/>"#,
+                html_escape(&alt.hreflang),
+                html_escape(&absolute(&alt.href))
+            );
+        }
+        let _ = writeln!(out, "  </url>");
+    }
+    let _ = writeln!(out, "</urlset>");
+    out
+}
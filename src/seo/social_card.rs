@@ -0,0 +1,661 @@
+use super::Seo;
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use anyhow::{Context, bail};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Width of the generated cards, in pixels (the size Open Graph and Twitter recommend).
+pub const CARD_WIDTH: u32 = 1200;
+/// Height of the generated cards, in pixels.
+pub const CARD_HEIGHT: u32 = 630;
+/// Where the cards are served and exported: `/__cards/{hash}.png`.
+pub const CARDS_BASE: &str = "/__cards";
+
+const PADDING: f32 = 80.0;
+const TITLE_SIZES: [f32; 3] = [72.0, 60.0, 52.0];
+const TITLE_LINES: usize = 3;
+const DESCRIPTION_SIZE: f32 = 32.0;
+const DESCRIPTION_LINES: usize = 2;
+const SITE_NAME_SIZE: f32 = 30.0;
+const LOGO_HEIGHT: u32 = 80;
+const LOGO_WIDTH: u32 = 240;
+
+/// What is drawn behind the text of a card.
+///
+/// # Variants
+///
+/// - `Color`: A plain RGB color, written `#rrggbb` or `#rgb`.
+/// - `Image`: A PNG file, scaled to cover the card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardBackground {
+    Color([u8; 3]),
+    Image(PathBuf),
+}
+
+impl Default for CardBackground {
+    fn default() -> Self {
+        CardBackground::Color([0x1f, 0x29, 0x37])
+    }
+}
+
+impl FromStr for CardBackground {
+    type Err = String;
+
+    /// Parses a `#rrggbb`/`#rgb` color, or else takes `s` as the path of a PNG file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("empty card background".to_string()),
+            s if s.starts_with('#') => parse_color(s)
+                .map(CardBackground::Color)
+                .ok_or_else(|| format!("invalid color `{s}`")),
+            s => Ok(CardBackground::Image(PathBuf::from(s))),
+        }
+    }
+}
+
+/// Parses a `#rrggbb` or `#rgb` color.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        6 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ]),
+        3 => {
+            let mut rgb = [0; 3];
+            for (i, c) in hex.chars().enumerate() {
+                rgb[i] = channel(&c.to_string())? * 0x11;
+            }
+            Some(rgb)
+        }
+        _ => None,
+    }
+}
+
+/// The branding of the social cards.
+///
+/// # Fields
+///
+/// * `font` - The TrueType or OpenType font the text is drawn with.
+/// * `background` - What is drawn behind the text (see [`CardBackground`]).
+/// * `text_color` - The color of the text.
+/// * `logo` - A PNG drawn in the bottom-right corner, scaled to fit 240×80 pixels.
+/// * `site_name` - The name drawn in the bottom-left corner when the page has no `site_name`.
+/// * `base_url` - The site origin, e.g. `https://example.org`, making the card URLs absolute.
+/// * `cache_dir` - Where the rendered cards are kept, named after their content hash.
+#[derive(Debug, Clone)]
+pub struct SocialCardStyle {
+    pub font: PathBuf,
+    pub background: CardBackground,
+    pub text_color: [u8; 3],
+    pub logo: Option<PathBuf>,
+    pub site_name: Option<String>,
+    pub base_url: Option<String>,
+    pub cache_dir: PathBuf,
+}
+
+impl SocialCardStyle {
+    /// Creates the style of cards drawn with `font`, white on a dark background.
+    pub fn new(font: impl Into<PathBuf>) -> Self {
+        Self {
+            font: font.into(),
+            background: CardBackground::default(),
+            text_color: [0xff; 3],
+            logo: None,
+            site_name: None,
+            base_url: None,
+            cache_dir: PathBuf::from(".cache/social-cards"),
+        }
+    }
+
+    pub fn with_background(mut self, background: CardBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_text_color(mut self, text_color: [u8; 3]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    pub fn with_logo(mut self, logo: impl Into<PathBuf>) -> Self {
+        self.logo = Some(logo.into());
+        self
+    }
+
+    pub fn with_site_name(mut self, site_name: impl Into<String>) -> Self {
+        self.site_name = Some(site_name.into());
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+}
+
+/// The text drawn on a card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardText {
+    pub title: String,
+    pub description: String,
+    pub site_name: String,
+}
+
+/// The cards that can be rendered on request.
+///
+/// # Fields
+///
+/// * `texts` - The text of each card, by file name.
+/// * `pages` - The file name of the card of each page, by page URI.
+#[derive(Debug, Default)]
+struct PendingCards {
+    texts: HashMap<String, CardText>,
+    pages: HashMap<String, String>,
+}
+
+impl PendingCards {
+    /// Forgets the card named `file_name` when no page uses it anymore.
+    fn release(&mut self, file_name: &str) {
+        if !self.pages.values().any(|used| used == file_name) {
+            self.texts.remove(file_name);
+        }
+    }
+}
+
+/// A decoded PNG, as RGBA pixels.
+struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// Generates the social cards of the pages without an `og_image`.
+///
+/// A card is a 1200×630 PNG showing the title, the description and the site name of a page,
+/// drawn with a [`SocialCardStyle`]. It is named after the hash of its text and style, so it is
+/// rendered once and its URL only changes with its content. Cards are rendered on first request
+/// and kept in [`SocialCardStyle::cache_dir`]; only the current cards of the pages given to
+/// [`SocialCards::apply`] can be rendered, until [`SocialCards::retain`] drops the pages gone.
+pub struct SocialCards {
+    style: SocialCardStyle,
+    font: FontVec,
+    background: Option<Bitmap>,
+    logo: Option<Bitmap>,
+    fingerprint: String,
+    pending: parking_lot::RwLock<PendingCards>,
+}
+
+impl std::fmt::Debug for SocialCards {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SocialCards")
+            .field("style", &self.style)
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SocialCards {
+    /// Loads the font and images of `style`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the font, background or logo cannot be read or decoded.
+    pub fn new(style: SocialCardStyle) -> anyhow::Result<Self> {
+        let font_data = std::fs::read(&style.font)
+            .with_context(|| format!("cannot read font {}", style.font.display()))?;
+        let mut fingerprint = Sha256::new();
+        fingerprint.update(&font_data);
+        fingerprint.update(style.text_color);
+        let font = FontVec::try_from_vec(font_data)
+            .with_context(|| format!("invalid font {}", style.font.display()))?;
+
+        let background = match &style.background {
+            CardBackground::Color(rgb) => {
+                fingerprint.update(rgb);
+                None
+            }
+            CardBackground::Image(path) => Some(read_png(path, &mut fingerprint)?),
+        };
+        let logo = match &style.logo {
+            Some(path) => Some(read_png(path, &mut fingerprint)?),
+            None => None,
+        };
+        Ok(Self {
+            style,
+            font,
+            background,
+            logo,
+            fingerprint: hex(&fingerprint.finalize()),
+            pending: Default::default(),
+        })
+    }
+
+    /// The text of the card of `seo`.
+    pub fn text(&self, seo: &Seo) -> CardText {
+        CardText {
+            title: seo.title.trim().to_string(),
+            description: seo.description.trim().to_string(),
+            site_name: seo
+                .site_name
+                .clone()
+                .or_else(|| self.style.site_name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The file name of the card showing `text`: `{hash}.png`.
+    pub fn file_name(&self, text: &CardText) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            self.fingerprint.as_str(),
+            &text.title,
+            &text.description,
+            &text.site_name,
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        format!("{}.png", &hex(&hasher.finalize())[..16])
+    }
+
+    /// Points `seo`, the metadata of the page at `page`, at its card when it has no `og_image`.
+    ///
+    /// Sets `og_image` (absolute with [`SocialCardStyle::base_url`]), its size and type, and
+    /// `og_image_alt` when missing. Pages without a title keep no image. Apply the site defaults
+    /// first (see [`Seo::with_defaults`]), so that a site-wide `og_image` wins over the cards.
+    ///
+    /// A page has one card at a time: when its text changes, the previous card can no longer be
+    /// rendered, unless it is cached already.
+    ///
+    /// # Returns
+    ///
+    /// The URI of the card, e.g. `/__cards/3f2a9c1d0b7e4a65.png`, when `seo` now uses it.
+    pub fn apply(&self, page: &str, seo: &mut Seo) -> Option<String> {
+        if seo.og_image.is_some() || seo.title.trim().is_empty() {
+            return None;
+        }
+        let text = self.text(seo);
+        let file_name = self.file_name(&text);
+        let uri = format!("{CARDS_BASE}/{file_name}");
+        if self.pending.read().pages.get(page) != Some(&file_name) {
+            let mut pending = self.pending.write();
+            let previous = pending.pages.insert(page.to_string(), file_name.clone());
+            pending.texts.entry(file_name).or_insert(text);
+            if let Some(previous) = previous {
+                pending.release(&previous);
+            }
+        }
+
+        seo.og_image = Some(match &self.style.base_url {
+            Some(base_url) => format!("{}{uri}", base_url.trim_end_matches('/')),
+            None => uri.clone(),
+        });
+        seo.og_image_width = Some(CARD_WIDTH);
+        seo.og_image_height = Some(CARD_HEIGHT);
+        seo.og_image_type = Some("image/png".to_string());
+        seo.og_image_alt.get_or_insert_with(|| seo.title.clone());
+        Some(uri)
+    }
+
+    /// Forgets the cards of the pages for which `keep` returns `false`, e.g. the pages removed
+    /// from the registry.
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) {
+        let mut pending = self.pending.write();
+        let gone: Vec<String> = pending
+            .pages
+            .keys()
+            .filter(|page| !keep(page))
+            .cloned()
+            .collect();
+        for page in gone {
+            if let Some(file_name) = pending.pages.remove(&page) {
+                pending.release(&file_name);
+            }
+        }
+    }
+
+    /// The PNG named `file_name`, from the cache or rendered and cached.
+    ///
+    /// # Returns
+    ///
+    /// `None` when no such card is cached nor was given to [`SocialCards::apply`].
+    ///
+    /// # Errors
+    ///
+    /// Fails when the card cannot be encoded or cached.
+    pub fn get(&self, file_name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let valid = file_name
+            .strip_suffix(".png")
+            .is_some_and(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()));
+        if !valid {
+            return Ok(None);
+        }
+        let path = self.style.cache_dir.join(file_name);
+        if let Ok(png) = std::fs::read(&path) {
+            return Ok(Some(png));
+        }
+        let Some(text) = self.pending.read().texts.get(file_name).cloned() else {
+            return Ok(None);
+        };
+        let png = self.render(&text)?;
+        std::fs::create_dir_all(&self.style.cache_dir).with_context(|| {
+            format!("cannot create directory {}", self.style.cache_dir.display())
+        })?;
+        std::fs::write(&path, &png).with_context(|| format!("cannot write {}", path.display()))?;
+        Ok(Some(png))
+    }
+
+    /// Renders the card showing `text` as a PNG, without caching it.
+    ///
+    /// The title is wrapped on up to three lines, in a smaller size when it is long, and the
+    /// description on up to two; the text that does not fit ends with `…`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the PNG cannot be encoded.
+    pub fn render(&self, text: &CardText) -> anyhow::Result<Vec<u8>> {
+        let mut canvas = Canvas::new(&self.style.background);
+        if let Some(background) = &self.background {
+            let scale = (CARD_WIDTH as f32 / background.width as f32)
+                .max(CARD_HEIGHT as f32 / background.height as f32);
+            let offset_x = (background.width as f32 * scale - CARD_WIDTH as f32) / 2.0;
+            let offset_y = (background.height as f32 * scale - CARD_HEIGHT as f32) / 2.0;
+            canvas.draw_bitmap(background, 0, 0, CARD_WIDTH, CARD_HEIGHT, |x, y| {
+                (
+                    ((x as f32 + offset_x) / scale) as u32,
+                    ((y as f32 + offset_y) / scale) as u32,
+                )
+            });
+        }
+
+        let color = self.style.text_color;
+        let max_width = CARD_WIDTH as f32 - 2.0 * PADDING;
+        let footer_top = CARD_HEIGHT as f32 - PADDING - SITE_NAME_SIZE * 1.6;
+        let mut top = PADDING;
+
+        let (title_size, title_lines) = TITLE_SIZES
+            .iter()
+            .map(|&size| (size, self.wrap(&text.title, size, max_width, usize::MAX)))
+            .find(|(_, lines)| lines.len() <= TITLE_LINES)
+            .unwrap_or_else(|| {
+                let size = TITLE_SIZES[TITLE_SIZES.len() - 1];
+                (size, self.wrap(&text.title, size, max_width, TITLE_LINES))
+            });
+        for line in &title_lines {
+            self.draw_text(&mut canvas, line, title_size, PADDING, top, color, 1.0);
+            top += title_size * 1.2;
+        }
+
+        top += 24.0;
+        let line_height = DESCRIPTION_SIZE * 1.4;
+        let room = ((footer_top - top) / line_height).floor().max(0.0) as usize;
+        let lines = self.wrap(
+            &text.description,
+            DESCRIPTION_SIZE,
+            max_width,
+            room.min(DESCRIPTION_LINES),
+        );
+        for line in &lines {
+            self.draw_text(
+                &mut canvas,
+                line,
+                DESCRIPTION_SIZE,
+                PADDING,
+                top,
+                color,
+                0.8,
+            );
+            top += line_height;
+        }
+
+        let mut site_name_width = max_width;
+        if let Some(logo) = &self.logo {
+            let scale = (LOGO_WIDTH as f32 / logo.width as f32)
+                .min(LOGO_HEIGHT as f32 / logo.height as f32);
+            let width = ((logo.width as f32 * scale) as u32).max(1);
+            let height = ((logo.height as f32 * scale) as u32).max(1);
+            let x = CARD_WIDTH - PADDING as u32 - width;
+            let y = CARD_HEIGHT - PADDING as u32 - height;
+            canvas.draw_bitmap(logo, x, y, width, height, |x, y| {
+                ((x as f32 / scale) as u32, (y as f32 / scale) as u32)
+            });
+            site_name_width -= width as f32 + 40.0;
+        }
+        if let Some(site_name) = self
+            .wrap(&text.site_name, SITE_NAME_SIZE, site_name_width, 1)
+            .first()
+        {
+            let top = CARD_HEIGHT as f32 - PADDING - SITE_NAME_SIZE;
+            self.draw_text(
+                &mut canvas,
+                site_name,
+                SITE_NAME_SIZE,
+                PADDING,
+                top,
+                color,
+                0.7,
+            );
+        }
+        canvas.encode()
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    }
+
+    /// Splits `text` into lines fitting `max_width`, ending the last one with `…` when more
+    /// than `max_lines` are needed.
+    fn wrap(&self, text: &str, size: f32, max_width: f32, max_lines: usize) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for word in text.split_whitespace() {
+            match lines.last_mut() {
+                Some(line) if self.text_width(&format!("{line} {word}"), size) <= max_width => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
+            }
+        }
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(last) = lines.last_mut() {
+                while self.text_width(&format!("{last}…"), size) > max_width {
+                    match last.rfind(' ') {
+                        Some(index) => last.truncate(index),
+                        None => break,
+                    }
+                }
+                last.push('…');
+            }
+        }
+        lines
+    }
+
+    /// Draws one line of text whose top is at `top`.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_text(
+        &self,
+        canvas: &mut Canvas,
+        text: &str,
+        size: f32,
+        left: f32,
+        top: f32,
+        color: [u8; 3],
+        opacity: f32,
+    ) {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let baseline = top + font.ascent();
+        let mut x = left;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(size, point(x, baseline));
+            x += font.h_advance(id);
+            previous = Some(id);
+            let Some(outline) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                canvas.blend(px, py, color, coverage * opacity);
+            });
+        }
+    }
+}
+
+/// The RGB pixels of a card being drawn.
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(background: &CardBackground) -> Self {
+        let rgb = match background {
+            CardBackground::Color(rgb) => *rgb,
+            CardBackground::Image(_) => [0; 3],
+        };
+        Self {
+            pixels: rgb.repeat((CARD_WIDTH * CARD_HEIGHT) as usize),
+        }
+    }
+
+    fn blend(&mut self, x: i64, y: i64, color: [u8; 3], alpha: f32) {
+        if x < 0 || y < 0 || x >= CARD_WIDTH as i64 || y >= CARD_HEIGHT as i64 {
+            return;
+        }
+        let alpha = alpha.clamp(0.0, 1.0);
+        let index = (y as usize * CARD_WIDTH as usize + x as usize) * 3;
+        for (pixel, channel) in self.pixels[index..index + 3].iter_mut().zip(color) {
+            *pixel = (*pixel as f32 * (1.0 - alpha) + channel as f32 * alpha).round() as u8;
+        }
+    }
+
+    /// Draws `bitmap` into the `width`×`height` box at (`left`, `top`), `source` mapping a pixel
+    /// of the box to a pixel of the bitmap.
+    fn draw_bitmap(
+        &mut self,
+        bitmap: &Bitmap,
+        left: u32,
+        top: u32,
+        width: u32,
+        height: u32,
+        source: impl Fn(u32, u32) -> (u32, u32),
+    ) {
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x, y);
+                let (sx, sy) = (sx.min(bitmap.width - 1), sy.min(bitmap.height - 1));
+                let index = ((sy * bitmap.width + sx) * 4) as usize;
+                let [r, g, b, a] = [0, 1, 2, 3].map(|i| bitmap.pixels[index + i]);
+                self.blend(
+                    (left + x) as i64,
+                    (top + y) as i64,
+                    [r, g, b],
+                    a as f32 / 255.0,
+                );
+            }
+        }
+    }
+
+    fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, CARD_WIDTH, CARD_HEIGHT);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(png)
+    }
+}
+
+/// Reads a PNG as RGBA pixels, adding its bytes to `fingerprint`.
+fn read_png(path: &Path, fingerprint: &mut Sha256) -> anyhow::Result<Bitmap> {
+    let data = std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+    fingerprint.update(&data);
+    let mut decoder = png::Decoder::new(data.as_slice());
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .with_context(|| format!("invalid PNG {}", path.display()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .with_context(|| format!("invalid PNG {}", path.display()))?;
+    buffer.truncate(info.buffer_size());
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => bail!("unsupported PNG colors in {}", path.display()),
+    };
+    if info.width == 0 || info.height == 0 {
+        bail!("empty PNG {}", path.display());
+    }
+    Ok(Bitmap {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_reads_long_and_short_hex() {
+        assert_eq!(parse_color("#1f2937"), Some([0x1f, 0x29, 0x37]));
+        assert_eq!(parse_color(" #FFF "), Some([0xff, 0xff, 0xff]));
+        assert_eq!(parse_color("#0a8"), Some([0x00, 0xaa, 0x88]));
+    }
+
+    #[test]
+    fn parse_color_rejects_invalid_colors() {
+        for color in [
+            "1f2937", "#", "#12", "#12345", "#1234567", "#ggg", "#+1f", "#+1ffff", "#é12",
+        ] {
+            assert_eq!(parse_color(color), None, "{color}");
+        }
+    }
+}
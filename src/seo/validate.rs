@@ -0,0 +1,308 @@
+use super::Seo;
+use super::open_graph::OgType;
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::LazyLock;
+
+/// Recommended lengths, in characters, and number of keywords (see `Seo.md`).
+pub const TITLE_LENGTH: RangeInclusive<usize> = 50..=60;
+pub const DESCRIPTION_LENGTH: RangeInclusive<usize> = 120..=160;
+pub const KEYWORD_COUNT: RangeInclusive<usize> = 3..=8;
+
+/// Language tag, extended language, script, region, variants and private use (RFC 5646).
+static BCP47: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^([a-z]{2,3}(-[a-z]{3}){0,3}|[a-z]{4,8})(-[a-z]{4})?(-([a-z]{2}|[0-9]{3}))?(-([a-z0-9]{5,8}|[0-9][a-z0-9]{3}))*(-x(-[a-z0-9]{1,8})+)?$").expect("valid BCP 47 pattern")
+});
+
+/// How serious a [`SeoIssue`] is.
+///
+/// # Variants
+///
+/// - `Warning`: Goes against a recommendation; the page still works.
+/// - `Error`: Invalid metadata that search engines or social platforms will ignore or reject.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A problem found by [`Seo::validate`].
+///
+/// # Fields
+///
+/// * `field` - The [`Seo`] field at fault, e.g. `title`.
+/// * `severity` - See [`Severity`].
+/// * `message` - What is wrong, with the value when it helps.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SeoIssue {
+    pub field: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl SeoIssue {
+    fn warning(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    fn error(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SeoIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: seo.{}: {}", self.severity, self.field, self.message)
+    }
+}
+
+impl Seo {
+    /// Checks the metadata against the `Seo.md` checklist.
+    ///
+    /// # Rules
+    ///
+    /// - `title`: required (error); 50–60 characters (warning).
+    /// - `description`: recommended (warning); 120–160 characters (warning).
+    /// - `canonical_url`, `og_image`, `og_url`: absolute `http(s)` URLs (error).
+    /// - `lang`: a BCP 47 tag such as `en`, `fr` or `pt-BR` (error).
+    /// - `keywords`: none, or 3–8 of them (warning).
+    /// - `updated`, `published`: ISO8601 dates or datetimes (error).
+    /// - `og_type`: the dates of articles are ISO8601 (error).
+    /// - `json_ld`: valid JSON (error).
+    ///
+    /// Optional fields are only checked when set.
+    ///
+    /// # Returns
+    ///
+    /// Every issue found, in field order. An empty list means the metadata follows the checklist.
+    pub fn validate(&self) -> Vec<SeoIssue> {
+        let mut issues = Vec::new();
+
+        let title = self.title.trim().chars().count();
+        if title == 0 {
+            issues.push(SeoIssue::error("title", "missing"));
+        } else if !TITLE_LENGTH.contains(&title) {
+            issues.push(SeoIssue::warning(
+                "title",
+                format!("{title} characters, 50–60 recommended"),
+            ));
+        }
+
+        let description = self.description.trim().chars().count();
+        if description == 0 {
+            issues.push(SeoIssue::warning("description", "missing"));
+        } else if !DESCRIPTION_LENGTH.contains(&description) {
+            issues.push(SeoIssue::warning(
+                "description",
+                format!("{description} characters, 120–160 recommended"),
+            ));
+        }
+
+        if !self.keywords.is_empty() && !KEYWORD_COUNT.contains(&self.keywords.len()) {
+            issues.push(SeoIssue::warning(
+                "keywords",
+                format!("{} keyword(s), 3–8 recommended", self.keywords.len()),
+            ));
+        }
+
+        if let Some(url) = &self.canonical_url
+            && !is_absolute_url(url)
+        {
+            issues.push(SeoIssue::error(
+                "canonical_url",
+                format!("`{url}` is not an absolute URL"),
+            ));
+        }
+
+        if let Some(lang) = &self.lang
+            && !BCP47.is_match(lang)
+        {
+            issues.push(SeoIssue::error(
+                "lang",
+                format!("`{lang}` is not a BCP 47 language tag"),
+            ));
+        }
+
+        if let Some(updated) = &self.updated
+            && !is_iso8601(updated)
+        {
+            issues.push(SeoIssue::error(
+                "updated",
+                format!("`{updated}` is not an ISO8601 date"),
+            ));
+        }
+
+        if let Some(published) = &self.published
+            && !is_iso8601(published)
+        {
+            issues.push(SeoIssue::error(
+                "published",
+                format!("`{published}` is not an ISO8601 date"),
+            ));
+        }
+
+        if let Some(image) = &self.og_image
+            && !is_absolute_url(image)
+        {
+            issues.push(SeoIssue::error(
+                "og_image",
+                format!("`{image}` is not an absolute URL"),
+            ));
+        }
+
+        if let Some(url) = &self.og_url
+            && !is_absolute_url(url)
+        {
+            issues.push(SeoIssue::error(
+                "og_url",
+                format!("`{url}` is not an absolute URL"),
+            ));
+        }
+
+        if let Some(OgType::Article(article)) = &self.og_type {
+            let dates = [
+                ("published_time", &article.published_time),
+                ("modified_time", &article.modified_time),
+                ("expiration_time", &article.expiration_time),
+            ];
+            for (property, date) in dates {
+                if let Some(date) = date
+                    && !is_iso8601(date)
+                {
+                    issues.push(SeoIssue::error(
+                        "og_type",
+                        format!("article {property} `{date}` is not an ISO8601 date"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(json_ld) = &self.json_ld
+            && let Err(error) = serde_json::from_str::<serde_json::Value>(json_ld)
+        {
+            issues.push(SeoIssue::error("json_ld", format!("invalid JSON: {error}")));
+        }
+
+        issues
+    }
+}
+
+/// `http://` or `https://` followed by a host.
+fn is_absolute_url(url: &str) -> bool {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+    rest.and_then(|rest| rest.split(['/', '?', '#']).next())
+        .is_some_and(|host| !host.is_empty() && !host.contains(char::is_whitespace))
+}
+
+/// An RFC 3339 datetime, or an ISO8601 date or local datetime.
+fn is_iso8601(value: &str) -> bool {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    DateTime::parse_from_rfc3339(value).is_ok()
+        || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
+        || NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").is_ok()
+        || NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complete() -> Seo {
+        let mut seo = Seo::new();
+        seo.title = "A title long enough to follow the fifty characters rule".into();
+        seo.description = "d".repeat(140);
+        seo.canonical_url = Some("https://example.org/about".into());
+        seo.lang = Some("pt-BR".into());
+        seo
+    }
+
+    fn fields(issues: &[SeoIssue]) -> Vec<(&str, Severity)> {
+        issues
+            .iter()
+            .map(|issue| (issue.field, issue.severity))
+            .collect()
+    }
+
+    #[test]
+    fn validate_accepts_complete_metadata() {
+        assert_eq!(complete().validate(), []);
+    }
+
+    #[test]
+    fn validate_reports_lengths_as_warnings() {
+        let mut seo = complete();
+        seo.title = "Short".into();
+        seo.description.clear();
+        seo.keywords = vec!["one".into()];
+        assert_eq!(
+            fields(&seo.validate()),
+            [
+                ("title", Severity::Warning),
+                ("description", Severity::Warning),
+                ("keywords", Severity::Warning),
+            ]
+        );
+        seo.title.clear();
+        assert_eq!(seo.validate()[0], SeoIssue::error("title", "missing"));
+    }
+
+    #[test]
+    fn validate_reports_invalid_values_as_errors() {
+        let mut seo = complete();
+        seo.canonical_url = Some("/about".into());
+        seo.lang = Some("english please".into());
+        seo.updated = Some("yesterday".into());
+        seo.og_image = Some("https:///image.png".into());
+        seo.json_ld = Some("{".into());
+        assert_eq!(
+            fields(&seo.validate()),
+            [
+                ("canonical_url", Severity::Error),
+                ("lang", Severity::Error),
+                ("updated", Severity::Error),
+                ("og_image", Severity::Error),
+                ("json_ld", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn bcp47_and_iso8601_accept_common_forms() {
+        for tag in ["en", "fr", "pt-BR", "zh-Hant-TW", "es-419", "en-x-private"] {
+            assert!(BCP47.is_match(tag), "{tag}");
+        }
+        for tag in ["", "e", "en_US", "english please"] {
+            assert!(!BCP47.is_match(tag), "{tag}");
+        }
+        for date in [
+            "2025-05-20",
+            "2025-05-20T12:30",
+            "2025-05-20T12:30:00+02:00",
+        ] {
+            assert!(is_iso8601(date), "{date}");
+        }
+        assert!(!is_iso8601("20/05/2025"));
+    }
+}